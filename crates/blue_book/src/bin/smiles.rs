@@ -0,0 +1,31 @@
+//! Prints the SMILES string for an IUPAC name.
+//!
+//! # Usage
+//!
+//! ```sh
+//! cargo run --bin smiles 'Propan-2-ol'
+//! cargo run --bin smiles -- --canonical 'Propan-2-ol'
+//! ```
+
+use std::env;
+
+use blue_book::{graph::Graph, parser::parse};
+
+fn main() {
+    let mut canonical = false;
+    let mut name = None;
+    for arg in env::args().skip(1) {
+        match arg.as_str() {
+            "--canonical" => canonical = true,
+            _ => name = Some(arg),
+        }
+    }
+
+    let ast = parse(&name.unwrap());
+    let graph = Graph::from(&*ast);
+    if canonical {
+        println!("{}", graph.canonical_smiles());
+    } else {
+        println!("{}", graph.smiles());
+    }
+}
@@ -1,3 +1,6 @@
+pub mod p_10_natural_products;
 pub mod p_1_general;
 pub mod p_2_hydrides;
 pub mod p_3_substituent_groups;
+pub mod p_6_specific_classes;
+pub mod p_8_isotopically_modified_compounds;
@@ -0,0 +1,3 @@
+//! # P-10 Parent Structures for Natural Products and Related Compounds
+
+pub mod p_103_amino_acids;
@@ -0,0 +1,51 @@
+//! # P-103 Amino Acids and Peptides
+
+use parsing::dfa;
+
+use crate::{
+    chapters::{
+        p_3_substituent_groups::CharacteristicGroup,
+        p_6_specific_classes::p_65_acids_and_derivatives::p_65_1_7_acyl_groups::AcylSuffix,
+    },
+    graph::{self, Graph},
+    plugin::Plugin,
+    scanner::Token,
+    Locant,
+};
+
+pub struct AminoAcidsPlugin;
+
+/// Amino acids with retained names, numbered as the acids they are named
+/// after, with the carboxy carbon at 1 and the amino group on the
+/// α-carbon at 2.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AminoAcid {
+    /// 2-Aminoethanoic acid
+    Glycine,
+    /// 2-Aminopropanoic acid
+    Alanine,
+}
+
+impl Plugin for AminoAcidsPlugin {
+    fn init_tokens(&self, dfa: &mut dfa::Automaton<Token>) {
+        dfa.insert("glycine", Token::AminoAcid(AminoAcid::Glycine));
+        dfa.insert("alanine", Token::AminoAcid(AminoAcid::Alanine));
+    }
+}
+
+impl AminoAcid {
+    pub fn to_graph(self) -> Graph {
+        let length = match self {
+            AminoAcid::Glycine => 2,
+            AminoAcid::Alanine => 3,
+        };
+        let acid =
+            graph::acid(AcylSuffix::Oyl, &[Locant::Number(1)], graph::alkane(length)).unwrap();
+        graph::substitute(
+            Locant::Number(2),
+            CharacteristicGroup::Amino.to_graph(),
+            acid,
+        )
+        .unwrap()
+    }
+}
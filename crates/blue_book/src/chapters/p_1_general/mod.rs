@@ -1,4 +1,5 @@
 //! # P-1 General principles, rules, and conventions
 
 pub mod p_11_scope;
+pub mod p_13_operations;
 pub mod p_14_general_rules;
@@ -5,8 +5,24 @@ use std::str::FromStr;
 use paste::paste;
 use serde::{de, Deserialize, Serialize};
 
+macro_rules! optional {
+    () => {
+        None
+    };
+    ($value:literal) => {
+        Some($value)
+    };
+}
+
 macro_rules! elements {
-    (#![doc = $doc:literal] $($symbol:ident $name:ident $group:literal)*) => {
+    (
+        #![doc = $doc:literal]
+        $(
+            $symbol:ident $name:ident $group:literal $period:literal $mass:literal
+            $monoisotopic_mass:literal [$($electronegativity:literal)?] $colour:literal
+            [$($valence:literal)*]
+        )*
+    ) => {
         paste! {
             #[doc = $doc]
             #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -32,51 +48,230 @@ macro_rules! elements {
                     }
                 }
 
+                /// The group, from 1 to 18. The lanthanoids and actinoids are
+                /// counted in group 3.
                 pub fn group(&self) -> u32 {
                     match self {
                         $(Element::[<$name:camel>] => $group,)*
                     }
                 }
+
+                pub fn period(&self) -> u32 {
+                    match self {
+                        $(Element::[<$name:camel>] => $period,)*
+                    }
+                }
+
+                /// The standard atomic weight, or the mass number of the
+                /// longest-lived isotope for elements that have none.
+                pub fn atomic_mass(&self) -> f64 {
+                    match self {
+                        $(Element::[<$name:camel>] => $mass,)*
+                    }
+                }
+
+                /// The mass of the most abundant isotope, in daltons.
+                pub fn monoisotopic_mass(&self) -> f64 {
+                    match self {
+                        $(Element::[<$name:camel>] => $monoisotopic_mass,)*
+                    }
+                }
+
+                /// The Pauling electronegativity, for elements that have one.
+                pub fn electronegativity(&self) -> Option<f64> {
+                    match self {
+                        $(Element::[<$name:camel>] => optional!($($electronegativity)?),)*
+                    }
+                }
+
+                /// The CPK colour, as used by Jmol, in the form `0xRRGGBB`.
+                pub fn cpk_colour(&self) -> u32 {
+                    match self {
+                        $(Element::[<$name:camel>] => $colour,)*
+                    }
+                }
+
+                /// The usual valences of the element when bonded to other
+                /// nonmetals, from lowest to highest. Empty for the noble gases
+                /// and the transition metals, which have none.
+                pub fn default_valences(&self) -> &'static [u8] {
+                    match self {
+                        $(Element::[<$name:camel>] => &[$($valence),*],)*
+                    }
+                }
             }
         }
     };
 }
 
 elements! {
-    //! Table 1.1 Elements included in these recommendations
-
-    // Include hydrogen to aid with graph construction
-    H   hydrogen      1
-
-    B   boron         13
-    C   carbon        14
-    N   nitrogen      15
-    O   oxygen        16
-    F   fluorine      17
-
-    Al  aluminium     13
-    Si  silicon       14
-    P   phosphorus    15
-    S   sulfur        16
-    Cl  chlorine      17
-
-    Ga  gallium       13
-    Ge  germanium     14
-    As  arsenic       15
-    Se  selenium      16
-    Br  bromine       17
-
-    In  indium        13
-    Sn  tin           14
-    Sb  antimony      15
-    Te  tellurium     16
-    I   iodine        17
-
-    Tl  thallium      13
-    Pb  lead          14
-    Bi  bismuth       15
-    Po  polonium      16
-    At  astatine      17
+    //! The elements, in order of atomic number.
+
+    H   hydrogen      1   1  1.008         1.00782503207   [2.20]  0xFFFFFF  [1]
+    He  helium        18  1  4.002602      4.00260325413   []      0xD9FFFF  []
+
+    Li  lithium       1   2  6.94          7.0160034366    [0.98]  0xCC80FF  [1]
+    Be  beryllium     2   2  9.0121831     9.012183065     [1.57]  0xC2FF00  [2]
+    B   boron         13  2  10.81         11.0093054      [2.04]  0xFFB5B5  [3]
+    C   carbon        14  2  12.011        12.0            [2.55]  0x909090  [4]
+    N   nitrogen      15  2  14.007        14.0030740048   [3.04]  0x3050F8  [3 5]
+    O   oxygen        16  2  15.999        15.99491461956  [3.44]  0xFF0D0D  [2]
+    F   fluorine      17  2  18.998403     18.99840322     [3.98]  0x90E050  [1]
+    Ne  neon          18  2  20.1797       19.9924401762   []      0xB3E3F5  []
+
+    Na  sodium        1   3  22.98976928   22.989769282    [0.93]  0xAB5CF2  [1]
+    Mg  magnesium     2   3  24.305        23.985041697    [1.31]  0x8AFF00  [2]
+    Al  aluminium     13  3  26.981538     26.98153863     [1.61]  0xBFA6A6  [3]
+    Si  silicon       14  3  28.085        27.9769265325   [1.90]  0xF0C8A0  [4]
+    P   phosphorus    15  3  30.973762     30.97376163     [2.19]  0xFF8000  [3 5]
+    S   sulfur        16  3  32.06         31.97207100     [2.58]  0xFFFF30  [2 4 6]
+    Cl  chlorine      17  3  35.45         34.96885268     [3.16]  0x1FF01F  [1]
+    Ar  argon         18  3  39.95         39.9623831237   []      0x80D1E3  []
+
+    K   potassium     1   4  39.0983       38.9637064864   [0.82]  0x8F40D4  [1]
+    Ca  calcium       2   4  40.078        39.962590863    [1.00]  0x3DFF00  [2]
+    Sc  scandium      3   4  44.955908     44.95590828     [1.36]  0xE6E6E6  []
+    Ti  titanium      4   4  47.867        47.94794198     [1.54]  0xBFC2C7  []
+    V   vanadium      5   4  50.9415       50.94395704     [1.63]  0xA6A6AB  []
+    Cr  chromium      6   4  51.9961       51.94050623     [1.66]  0x8A99C7  []
+    Mn  manganese     7   4  54.938043     54.93804391     [1.55]  0x9C7AC7  []
+    Fe  iron          8   4  55.845        55.93493633     [1.83]  0xE06633  []
+    Co  cobalt        9   4  58.933194     58.93319429     [1.88]  0xF090A0  []
+    Ni  nickel        10  4  58.6934       57.93534241     [1.91]  0x50D050  []
+    Cu  copper        11  4  63.546        62.92959772     [1.90]  0xC88033  []
+    Zn  zinc          12  4  65.38         63.92914201     [1.65]  0x7D80B0  []
+    Ga  gallium       13  4  69.723        68.9255736      [1.81]  0xC28F8F  [3]
+    Ge  germanium     14  4  72.630        73.9211778      [2.01]  0x668F8F  [4]
+    As  arsenic       15  4  74.921595     74.9215965      [2.18]  0xBD80E3  [3 5]
+    Se  selenium      16  4  78.971        79.9165213      [2.55]  0xFFA100  [2 4 6]
+    Br  bromine       17  4  79.904        78.9183371      [2.96]  0xA62929  [1]
+    Kr  krypton       18  4  83.798        83.9114977282   [3.00]  0x5CB8D1  []
+
+    Rb  rubidium      1   5  85.4678       84.9117897379   [0.82]  0x702EB0  [1]
+    Sr  strontium     2   5  87.62         87.9056125      [0.95]  0x00FF00  [2]
+    Y   yttrium       3   5  88.90584      88.9058403      [1.22]  0x94FFFF  []
+    Zr  zirconium     4   5  91.224        89.9046977      [1.33]  0x94E0E0  []
+    Nb  niobium       5   5  92.90637      92.906373       [1.6]   0x73C2C9  []
+    Mo  molybdenum    6   5  95.95         97.90540482     [2.16]  0x54B5B5  []
+    Tc  technetium    7   5  98.0          97.9072124      [1.9]   0x3B9E9E  []
+    Ru  ruthenium     8   5  101.07        101.9043441     [2.2]   0x248F8F  []
+    Rh  rhodium       9   5  102.90549     102.905498      [2.28]  0x0A7D8C  []
+    Pd  palladium     10  5  106.42        105.9034804     [2.20]  0x006985  []
+    Ag  silver        11  5  107.8682      106.9050916     [1.93]  0xC0C0C0  []
+    Cd  cadmium       12  5  112.414       113.90336509    [1.69]  0xFFD98F  []
+    In  indium        13  5  114.818       114.903878      [1.78]  0xA67573  [3]
+    Sn  tin           14  5  118.710       119.9021947     [1.96]  0x668080  [2 4]
+    Sb  antimony      15  5  121.760       120.9038157     [2.05]  0x9E63B5  [3 5]
+    Te  tellurium     16  5  127.60        129.9062244     [2.1]   0xD47A00  [2 4 6]
+    I   iodine        17  5  126.90447     126.904473      [2.66]  0x940094  [1 3 5]
+    Xe  xenon         18  5  131.293       131.9041550856  [2.6]   0x429EB0  []
+
+    Cs  caesium       1   6  132.90545196  132.905451961   [0.79]  0x57178F  [1]
+    Ba  barium        2   6  137.327       137.905247      [0.89]  0x00C900  [2]
+    La  lanthanum     3   6  138.90547     138.9063563     [1.10]  0x70D4FF  []
+    Ce  cerium        3   6  140.116       139.9054431     [1.12]  0xFFFFC7  []
+    Pr  praseodymium  3   6  140.90766     140.9076576     [1.13]  0xD9FFC7  []
+    Nd  neodymium     3   6  144.242       141.907729      [1.14]  0xC7FFC7  []
+    Pm  promethium    3   6  145.0         144.9127559     [1.13]  0xA3FFC7  []
+    Sm  samarium      3   6  150.36        151.9197397     [1.17]  0x8FFFC7  []
+    Eu  europium      3   6  151.964       152.921238      [1.2]   0x61FFC7  []
+    Gd  gadolinium    3   6  157.25        157.9241123     [1.20]  0x45FFC7  []
+    Tb  terbium       3   6  158.925354    158.9253547     [1.1]   0x30FFC7  []
+    Dy  dysprosium    3   6  162.500       163.9291819     [1.22]  0x1FFFC7  []
+    Ho  holmium       3   6  164.930328    164.9303288     [1.23]  0x00FF9C  []
+    Er  erbium        3   6  167.259       165.9302995     [1.24]  0x00E675  []
+    Tm  thulium       3   6  168.934218    168.9342179     [1.25]  0x00D452  []
+    Yb  ytterbium     3   6  173.045       173.9388664     [1.1]   0x00BF38  []
+    Lu  lutetium      3   6  174.9668      174.9407752     [1.27]  0x00AB24  []
+    Hf  hafnium       4   6  178.486       179.946557      [1.3]   0x4DC2FF  []
+    Ta  tantalum      5   6  180.94788     180.9479958     [1.5]   0x4DA6FF  []
+    W   tungsten      6   6  183.84        183.95093092    [2.36]  0x2194D6  []
+    Re  rhenium       7   6  186.207       186.9557501     [1.9]   0x267DAB  []
+    Os  osmium        8   6  190.23        191.961477      [2.2]   0x266696  []
+    Ir  iridium       9   6  192.217       192.9629216     [2.20]  0x175487  []
+    Pt  platinum      10  6  195.084       194.9647917     [2.28]  0xD0D0E0  []
+    Au  gold          11  6  196.966570    196.96656879    [2.54]  0xFFD123  []
+    Hg  mercury       12  6  200.592       201.9706434     [2.00]  0xB8B8D0  []
+    Tl  thallium      13  6  204.38        204.9744275     [1.62]  0xA6544D  [1 3]
+    Pb  lead          14  6  207.2         207.9766521     [2.33]  0x575961  [2 4]
+    Bi  bismuth       15  6  208.98040     208.9803987     [2.02]  0x9E4FB5  [3 5]
+    Po  polonium      16  6  209.0         208.9824304     [2.0]   0xAB5C00  [2 4 6]
+    At  astatine      17  6  210.0         209.9871479     [2.2]   0x754F45  [1]
+    Rn  radon         18  6  222.0         222.0175782     [2.2]   0x428296  []
+
+    Fr  francium      1   7  223.0         223.019736      [0.7]   0x420066  [1]
+    Ra  radium        2   7  226.0         226.0254103     [0.9]   0x007D00  [2]
+    Ac  actinium      3   7  227.0         227.0277523     [1.1]   0x70ABFA  []
+    Th  thorium       3   7  232.0377      232.0380558     [1.3]   0x00BAFF  []
+    Pa  protactinium  3   7  231.03588     231.0358842     [1.5]   0x00A1FF  []
+    U   uranium       3   7  238.02891     238.0507884     [1.38]  0x008FFF  []
+    Np  neptunium     3   7  237.0         237.0481736     [1.36]  0x0080FF  []
+    Pu  plutonium     3   7  244.0         244.0642053     [1.28]  0x006BFF  []
+    Am  americium     3   7  243.0         243.0613813     [1.13]  0x545CF2  []
+    Cm  curium        3   7  247.0         247.0703541     [1.28]  0x785CE3  []
+    Bk  berkelium     3   7  247.0         247.0703073     [1.3]   0x8A4FE3  []
+    Cf  californium   3   7  251.0         251.0795886     [1.3]   0xA136D4  []
+    Es  einsteinium   3   7  252.0         252.08298       [1.3]   0xB31FD4  []
+    Fm  fermium       3   7  257.0         257.0951061     [1.3]   0xB31FBA  []
+    Md  mendelevium   3   7  258.0         258.0984315     [1.3]   0xB30DA6  []
+    No  nobelium      3   7  259.0         259.10103       [1.3]   0xBD0D87  []
+    Lr  lawrencium    3   7  266.0         266.11983       [1.3]   0xC70066  []
+    Rf  rutherfordium 4   7  267.0         267.12179       []      0xCC0059  []
+    Db  dubnium       5   7  268.0         268.12567       []      0xD1004F  []
+    Sg  seaborgium    6   7  269.0         269.12863       []      0xD90045  []
+    Bh  bohrium       7   7  270.0         270.13336       []      0xE00038  []
+    Hs  hassium       8   7  269.0         269.13375       []      0xE6002E  []
+    Mt  meitnerium    9   7  278.0         278.15631       []      0xEB0026  []
+    Ds  darmstadtium  10  7  281.0         281.16451       []      0xFF1493  []
+    Rg  roentgenium   11  7  282.0         282.16912       []      0xFF1493  []
+    Cn  copernicium   12  7  285.0         285.17712       []      0xFF1493  []
+    Nh  nihonium      13  7  286.0         286.18221       []      0xFF1493  []
+    Fl  flerovium     14  7  289.0         289.19042       []      0xFF1493  []
+    Mc  moscovium     15  7  290.0         290.19598       []      0xFF1493  []
+    Lv  livermorium   16  7  293.0         293.20449       []      0xFF1493  []
+    Ts  tennessine    17  7  294.0         294.21046       []      0xFF1493  []
+    Og  oganesson     18  7  294.0         294.21392       []      0xFF1493  []
+
+}
+
+impl Element {
+    pub fn atomic_number(&self) -> u32 {
+        *self as u32 + 1
+    }
+
+    /// Table 1.1 Elements included in these recommendations, with hydrogen
+    /// to aid with graph construction.
+    pub fn is_in_scope(&self) -> bool {
+        matches!(
+            self,
+            Element::Hydrogen
+                | Element::Boron
+                | Element::Carbon
+                | Element::Nitrogen
+                | Element::Oxygen
+                | Element::Fluorine
+                | Element::Aluminium
+                | Element::Silicon
+                | Element::Phosphorus
+                | Element::Sulfur
+                | Element::Chlorine
+                | Element::Gallium
+                | Element::Germanium
+                | Element::Arsenic
+                | Element::Selenium
+                | Element::Bromine
+                | Element::Indium
+                | Element::Tin
+                | Element::Antimony
+                | Element::Tellurium
+                | Element::Iodine
+                | Element::Thallium
+                | Element::Lead
+                | Element::Bismuth
+                | Element::Polonium
+                | Element::Astatine
+        )
+    }
 }
 
 impl PartialOrd for Element {
@@ -134,7 +329,32 @@ impl<'de> Deserialize<'de> for Element {
 
 #[cfg(test)]
 mod tests {
-    use crate::Element;
+    use crate::{Element, ELEMENTS};
+
+    #[test]
+    fn test_periodic_table() {
+        assert_eq!(ELEMENTS.len(), 118);
+        assert_eq!(Element::Oganesson.atomic_number(), 118);
+        assert_eq!(Element::from_symbol("Fe"), Some(Element::Iron));
+        assert_eq!(Element::Iron.atomic_number(), 26);
+        assert_eq!((Element::Iron.group(), Element::Iron.period()), (8, 4));
+        assert_eq!(Element::Platinum.symbol(), "Pt");
+        assert_eq!(Element::Sodium.electronegativity(), Some(0.93));
+        assert_eq!(Element::Helium.electronegativity(), None);
+        assert_eq!(Element::Oxygen.cpk_colour(), 0xFF0D0D);
+        assert_eq!(Element::Sulfur.default_valences(), &[2, 4, 6]);
+        assert!(Element::Copper.default_valences().is_empty());
+
+        // Each period ends with a noble gas
+        for element in ELEMENTS.windows(2) {
+            if element[0].period() != element[1].period() {
+                assert_eq!(element[0].group(), 18, "{:?}", element[0]);
+            }
+        }
+
+        assert!(Element::Carbon.is_in_scope());
+        assert!(!Element::Sodium.is_in_scope());
+    }
 
     #[test]
     fn test_sort_elements() {
@@ -0,0 +1,3 @@
+//! # P-13 Operations in Nomenclature
+
+pub mod p_13_2_subtractive_operations;
@@ -0,0 +1,146 @@
+//! # P-13.2 Subtractive Operations
+//!
+//! Subtractive prefixes remove atoms or groups from a structure that has been
+//! named in full, as in "2-deoxy-D-ribose" or "19-norsteroid", so they apply
+//! to the whole name once its suffixes and substituents are in place.
+
+use parsing::dfa;
+
+use crate::{
+    graph::{anhydro, demethyl, deoxy, nor, Graph},
+    parser::{self, StackItem},
+    plugin::Plugin,
+    scanner::Token,
+    Locant, LocantError,
+};
+
+pub struct SubtractivePrefixesPlugin;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SubtractivePrefix {
+    /// Removes a hydroxy group's oxygen atom.
+    Deoxy,
+    /// Removes a skeletal atom with its hydrogens.
+    Nor,
+    /// Removes water from two hydroxy groups, joining them into an ether.
+    Anhydro,
+    /// Removes a methyl group.
+    Demethyl,
+}
+
+impl Plugin for SubtractivePrefixesPlugin {
+    fn init_tokens(&self, dfa: &mut dfa::Automaton<Token>) {
+        dfa.insert("deoxy", Token::Subtractive(SubtractivePrefix::Deoxy));
+        dfa.insert("nor", Token::Subtractive(SubtractivePrefix::Nor));
+        dfa.insert("anhydr", Token::Subtractive(SubtractivePrefix::Anhydro));
+        dfa.insert("demethyl", Token::Subtractive(SubtractivePrefix::Demethyl));
+    }
+}
+
+/// Applies a subtractive prefix at each of its locants. "anhydro" takes its
+/// locants in pairs, one for each hydroxy group.
+pub fn subtract(
+    prefix: SubtractivePrefix,
+    locants: &[Locant],
+    base: Graph,
+) -> Result<Graph, LocantError> {
+    match prefix {
+        SubtractivePrefix::Deoxy => locants
+            .iter()
+            .try_fold(base, |molecule, &pos| deoxy(pos, molecule)),
+        SubtractivePrefix::Nor => locants
+            .iter()
+            .try_fold(base, |molecule, &pos| nor(pos, molecule)),
+        SubtractivePrefix::Anhydro => {
+            locants
+                .chunks(2)
+                .try_fold(base, |molecule, pair| match *pair {
+                    [a, b] => anhydro(a, b, molecule),
+                    [a] => Err(LocantError::Unpaired(a)),
+                    _ => unreachable!(),
+                })
+        }
+        SubtractivePrefix::Demethyl => locants
+            .iter()
+            .try_fold(base, |molecule, &pos| demethyl(pos, molecule)),
+    }
+}
+
+impl parser::State {
+    /// Sets aside the subtractive prefix on top of the stack, with its
+    /// locants, until the rest of the name has been parsed.
+    pub fn pop_subtractive_prefix(&mut self) {
+        let Some(StackItem::Subtractive(prefix)) = self.stack.pop() else {
+            unreachable!()
+        };
+        let mut count = self.pop_multiplicity();
+        if prefix == SubtractivePrefix::Anhydro {
+            count *= 2;
+        }
+        let mut locants = (0..count)
+            .map(|_| match self.stack.last() {
+                Some(&StackItem::Locant(pos)) => {
+                    self.stack.pop();
+                    pos
+                }
+                _ => Locant::Unspecified,
+            })
+            .collect::<Vec<_>>();
+        locants.reverse();
+        self.subtractive_prefixes.push((prefix, locants));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{subtract, SubtractivePrefix};
+    use crate::{
+        formula::Formula, graph::Graph, parser::parse, test::assert_same_compound, Element, Locant,
+        LocantError,
+    };
+
+    #[test]
+    fn test_subtractive_prefixes() {
+        let graph = |name: &str| Graph::from(&*parse(name));
+
+        assert_same_compound("2-Deoxypropane-1,2,3-triol", "Propane-1,3-diol");
+        assert_same_compound("2,3-Dideoxybutane-1,2,3,4-tetrol", "Butane-1,4-diol");
+
+        // A chain atom is taken out, joining its neighbours
+        assert_same_compound("3-Norpentane", "Butane");
+        assert_same_compound("1-Norpentan-2-ol", "Butan-1-ol");
+
+        assert_same_compound("N-Demethyl-N,N-dimethylethanamine", "N-Methylethanamine");
+
+        // The two hydroxy groups close up into a ring ether, as in oxolane
+        let molecule = graph("1,4-Anhydrobutane-1,4-diol");
+        assert_eq!(Formula::from(&molecule).to_string(), "C4H8O");
+        let oxygen = (0..molecule.atoms.len())
+            .find(|&i| molecule.atoms[i] == Element::Oxygen)
+            .unwrap();
+        assert!(molecule
+            .neighbors(oxygen)
+            .all(|i| molecule.atoms[i] == Element::Carbon));
+        assert_eq!(molecule.neighbors(oxygen).count(), 2);
+    }
+
+    #[test]
+    fn test_missing_groups() {
+        let error = |name: &str| Graph::from_ast(&parse(name)).unwrap_err();
+
+        assert_eq!(
+            error("2-Deoxyethanol"),
+            LocantError::NoGroup(Locant::Number(2))
+        );
+        assert_eq!(
+            error("N-Demethylethanamine"),
+            LocantError::NoGroup(Locant::Heteroatom(Element::Nitrogen, 0)),
+        );
+
+        let diol = Graph::from(&*parse("Ethane-1,2-diol"));
+        assert_eq!(
+            subtract(SubtractivePrefix::Anhydro, &[Locant::Number(1)], diol).unwrap_err(),
+            LocantError::Unpaired(Locant::Number(1)),
+        );
+    }
+}
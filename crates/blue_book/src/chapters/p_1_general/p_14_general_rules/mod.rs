@@ -3,3 +3,4 @@
 pub mod p_14_1_bonding_number;
 pub mod p_14_2_multiplicative_prefixes;
 pub mod p_14_3_locants;
+pub mod p_14_7_indicated_hydrogen;
@@ -6,6 +6,7 @@ impl Element {
     pub fn standard_bonding_number(self) -> u8 {
         match self.group() {
             1 => 1,
+            2 => 2,
             13 => 3,
             14 => 4,
             15 => 3,
@@ -29,5 +30,10 @@ mod tests {
         assert_eq!(Element::Nitrogen.standard_bonding_number(), 3);
         assert_eq!(Element::Oxygen.standard_bonding_number(), 2);
         assert_eq!(Element::Fluorine.standard_bonding_number(), 1);
+
+        assert_eq!(Element::Sodium.standard_bonding_number(), 1);
+        assert_eq!(Element::Calcium.standard_bonding_number(), 2);
+        assert_eq!(Element::Iron.standard_bonding_number(), 0);
+        assert_eq!(Element::Neon.standard_bonding_number(), 0);
     }
 }
@@ -1,10 +1,262 @@
 //! # P-14.3 Locants
 
+use std::{error::Error, fmt};
+
 use crate::Element;
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum Locant {
     Unspecified,
     Number(u16),
+    /// A number followed by an element symbol, as in "1H".
     Element(u16, Element),
+    /// A number with primes, as in "1'" or "2''".
+    Primed(u16, u8),
+    /// An interior atom of a fused ring system, with any primes, as in "3a"
+    /// or "4a'".
+    Letter(u16, char, u8),
+    /// A Greek letter, with any primes, as in "α" or "ω'", counting along a
+    /// chain.
+    Greek(char, u8),
+    /// An atom of a characteristic group, with any primes, as in "N" or
+    /// "N'".
+    Heteroatom(Element, u8),
+    /// A compound locant, as in "1(6)": the atom, and the atom after it in
+    /// parentheses where that is not the next one.
+    Compound(u16, u16),
+}
+
+/// A locant with no atom, bond or group of its own in the structure being
+/// named.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LocantError {
+    NoAtom(Locant),
+    NoBond(Locant),
+    /// The locant is on the group being attached there.
+    SameAtom(Locant),
+    /// No group at the locant for a subtractive prefix to remove, as in
+    /// "2-deoxyethanol".
+    NoGroup(Locant),
+    /// The last of locants that come in pairs, left without a partner.
+    Unpaired(Locant),
+    /// Too few hydrogen atoms at the locant to take away.
+    NoHydrogen(Locant),
+    /// Too few atoms of the element at the locant to give a nuclide, as in
+    /// "(1-¹⁵N)ethanol".
+    NoNuclide(Locant),
+    /// The group substituted at the locant has no free valence to bond with,
+    /// as in "benzene" read as a prefix of "benzenemethanol".
+    NoFreeValence(Locant),
+}
+
+impl fmt::Display for LocantError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            LocantError::NoAtom(locant) => write!(f, "no atom at locant {locant:?}"),
+            LocantError::NoBond(locant) => write!(f, "no bond at locant {locant:?}"),
+            LocantError::SameAtom(locant) => {
+                write!(f, "locant {locant:?} is on the group attached there")
+            }
+            LocantError::NoGroup(locant) => write!(f, "no group to remove at locant {locant:?}"),
+            LocantError::Unpaired(locant) => write!(f, "locant {locant:?} has no partner"),
+            LocantError::NoHydrogen(locant) => write!(f, "too few hydrogens at locant {locant:?}"),
+            LocantError::NoNuclide(locant) => {
+                write!(f, "too few atoms to label at locant {locant:?}")
+            }
+            LocantError::NoFreeValence(locant) => {
+                write!(f, "no free valence to attach at locant {locant:?}")
+            }
+        }
+    }
+}
+
+impl Error for LocantError {}
+
+const GREEK: [char; 24] = [
+    'α', 'β', 'γ', 'δ', 'ε', 'ζ', 'η', 'θ', 'ι', 'κ', 'λ', 'μ', 'ν', 'ξ', 'ο', 'π', 'ρ', 'σ', 'τ',
+    'υ', 'φ', 'χ', 'ψ', 'ω',
+];
+
+impl Locant {
+    /// The index of a Greek letter in the alphabet, so that "α" is the first
+    /// atom of a chain. "ω" is always the last atom instead.
+    pub fn greek_index(letter: char) -> Option<usize> {
+        GREEK.iter().position(|&c| c == letter)
+    }
+
+    /// The number of primes, which tell the locants of a substituent group
+    /// apart from those of its parent.
+    pub fn primes(self) -> u8 {
+        match self {
+            Locant::Primed(_, primes)
+            | Locant::Letter(_, _, primes)
+            | Locant::Greek(_, primes)
+            | Locant::Heteroatom(_, primes) => primes,
+            _ => 0,
+        }
+    }
+
+    /// Whether the locant is on a substituent group rather than the parent,
+    /// as "1'" is. Heteroatom locants are told apart by their element
+    /// instead.
+    pub fn is_primed(self) -> bool {
+        !matches!(self, Locant::Heteroatom(..)) && self.primes() > 0
+    }
+
+    /// The locant with `primes` more primes.
+    pub fn primed(self, primes: u8) -> Locant {
+        match self {
+            Locant::Number(n) => Locant::Primed(n, primes),
+            Locant::Primed(n, count) => Locant::Primed(n, count + primes),
+            Locant::Letter(n, letter, count) => Locant::Letter(n, letter, count + primes),
+            Locant::Greek(letter, count) => Locant::Greek(letter, count + primes),
+            Locant::Heteroatom(element, count) => Locant::Heteroatom(element, count + primes),
+            locant => locant,
+        }
+    }
+
+    /// Reads a locant other than a plain number, as at the start of
+    /// "3a,7a-dihydro", "1'-methyl", "N,N-dimethyl" or "α-hydroxy", and
+    /// returns it with its length. `number` is the number already read
+    /// before `input`, if any.
+    pub(crate) fn scan_suffix(number: Option<u16>, input: &str) -> Option<(Locant, usize)> {
+        let primes = |input: &str| {
+            let len = input
+                .find(|c: char| !matches!(c, '\'' | '′' | '″'))
+                .unwrap_or(input.len());
+            let count = input[..len]
+                .chars()
+                .map(|c| if c == '″' { 2 } else { 1 })
+                .sum::<u8>();
+            (count, len)
+        };
+        // A locant is always followed by another one, a hyphen or a bracket
+        let is_end =
+            |input: &str| input.is_empty() || input.starts_with([',', '-', '(', ')', '[', ']']);
+
+        let mut chars = input.chars();
+        let first = chars.next()?;
+        match number {
+            Some(number) => {
+                let (count, len) = primes(input);
+                if count > 0 && is_end(&input[len..]) {
+                    return Some((Locant::Primed(number, count), len));
+                }
+
+                if first.is_ascii_lowercase() {
+                    let (count, primes_len) = primes(&input[1..]);
+                    let len = 1 + primes_len;
+                    return is_end(&input[len..])
+                        .then_some((Locant::Letter(number, first, count), len));
+                }
+
+                // As in "1(6)", but not "2(1H)"
+                let inner = input.strip_prefix('(')?;
+                let digits = inner
+                    .find(|c: char| !c.is_ascii_digit())
+                    .unwrap_or(inner.len());
+                let rest = inner[digits..].strip_prefix(')')?;
+                let other = inner[..digits].parse().ok()?;
+                let len = input.len() - rest.len();
+                is_end(rest).then_some((Locant::Compound(number, other), len))
+            }
+            None if Self::greek_index(first).is_some() => {
+                let (count, primes_len) = primes(chars.as_str());
+                let len = first.len_utf8() + primes_len;
+                is_end(&input[len..]).then_some((Locant::Greek(first, count), len))
+            }
+            None => {
+                let symbol_len = match chars.next() {
+                    Some(c) if c.is_ascii_lowercase() => 2,
+                    _ => 1,
+                };
+                if !first.is_ascii_uppercase() {
+                    return None;
+                }
+                let element = Element::from_symbol(&input[..symbol_len])?;
+                let (count, primes_len) = primes(&input[symbol_len..]);
+                let len = symbol_len + primes_len;
+                // Not a stereodescriptor such as "(S)"
+                let rest = &input[len..];
+                (rest.starts_with([',', '-']) && element.is_in_scope())
+                    .then_some((Locant::Heteroatom(element, count), len))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        graph::{alkane, Graph},
+        parser::parse,
+        test::assert_same_compound,
+    };
+
+    use super::{Locant, LocantError};
+
+    #[test]
+    fn test_resolve_locants() {
+        let butane = alkane(4);
+        let atom = |locant| butane.position(locant).unwrap().1;
+        assert_eq!(atom(Locant::Number(2)), 1);
+        assert_eq!(atom(Locant::Compound(2, 3)), 1);
+        assert_eq!(atom(Locant::Greek('α', 0)), 0);
+        assert_eq!(atom(Locant::Greek('γ', 0)), 2);
+        assert_eq!(atom(Locant::Greek('ω', 0)), 3);
+        assert_eq!(
+            butane.position(Locant::Number(5)),
+            Err(LocantError::NoAtom(Locant::Number(5))),
+        );
+
+        // The locants of a substituent group are primed, and Greek locants
+        // count along them alone
+        let graph = alkane(2).merge(alkane(3));
+        let atom = |locant| graph.position(locant).unwrap().1;
+        assert_eq!(atom(Locant::Primed(1, 1)), 8);
+        assert_eq!(atom(Locant::Greek('ω', 0)), 1);
+        assert_eq!(atom(Locant::Greek('ω', 1)), 10);
+        assert_eq!(atom(Locant::Greek('β', 1)), 9);
+
+        let mut graph = alkane(2);
+        graph.positions[1].0 = Locant::Letter(4, 'a', 1);
+        assert_eq!(graph.position(Locant::Letter(4, 'a', 1)).unwrap().1, 1);
+        assert!(graph.position(Locant::Letter(4, 'a', 0)).is_err());
+    }
+
+    #[test]
+    fn test_locants_in_names() {
+        let graph = |name: &str| Graph::from(&*parse(name));
+
+        assert_same_compound("ω-Hydroxypropane", "Propan-1-ol");
+        assert_same_compound("α,ω-Dihydroxybutane", "Butane-1,4-diol");
+        // Primed locants are on the substituent group
+        assert_same_compound("1-Ethyl-2'-hydroxybenzene", "2-Phenylethan-1-ol");
+        assert_same_compound("1-Ethyl-α'-hydroxybenzene", "1-Phenylethan-1-ol");
+
+        assert_eq!(
+            graph("But-2-ene").canonical_key(),
+            Graph::from_smiles("CC=CC").unwrap().canonical_key(),
+        );
+        assert_eq!(
+            graph("Buta-1,3-diene").canonical_key(),
+            Graph::from_smiles("C=CC=C").unwrap().canonical_key(),
+        );
+
+        // Atoms 1 and 6 of a chain are not bonded
+        assert_eq!(
+            Graph::from_ast(&parse("Hex-1(6)-ene")).unwrap_err(),
+            LocantError::NoBond(Locant::Compound(1, 6)),
+        );
+        // The methyl group cannot be attached to its own atom
+        assert_eq!(
+            Graph::from_ast(&parse("1'-Methylbutane")).unwrap_err(),
+            LocantError::SameAtom(Locant::Primed(1, 1)),
+        );
+        // Benzene has no free valence to attach it to methanol by
+        assert_eq!(
+            Graph::from_ast(&parse("α-Methylbenzenemethanol")).unwrap_err(),
+            LocantError::NoFreeValence(Locant::Unspecified),
+        );
+    }
 }
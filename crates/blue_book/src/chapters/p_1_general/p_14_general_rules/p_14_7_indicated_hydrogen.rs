@@ -0,0 +1,221 @@
+//! # P-14.7 Indicated and Added Hydrogen
+
+use std::rc::Rc;
+
+use crate::{
+    graph::{aromaticity::perfect_matching, Graph},
+    parser::{self, StackItem, AST},
+    Element, Locant, LocantError,
+};
+
+/// The atoms of a ring system: those at its locants, leaving out the
+/// heteroatoms of characteristic groups and the primed locants of substituent
+/// groups.
+pub(crate) fn ring_atoms(graph: &Graph) -> Vec<usize> {
+    graph
+        .positions
+        .iter()
+        .filter(|(pos, _)| !matches!(pos, Locant::Heteroatom(..)) && !pos.is_primed())
+        .map(|&(_, i)| i)
+        .collect()
+}
+
+/// Whether the atoms of the ring system other than `indicated` can all be
+/// paired up by double bonds.
+fn is_mancude(graph: &Graph, ring: &[usize], indicated: &[usize]) -> bool {
+    let candidates = ring
+        .iter()
+        .copied()
+        .filter(|&i| !indicated.contains(&i) && graph.valence_deficit(i) > 0)
+        .collect::<Vec<_>>();
+    let mut partners = vec![None; graph.atoms.len()];
+    perfect_matching(graph, &candidates, &mut partners)
+}
+
+/// Places the hydrogens of a ring system with the maximum number of
+/// non-cumulative double bonds. Every atom short of its bonding number takes
+/// part in one double bond, apart from those at the `indicated` locants, which
+/// take an extra hydrogen instead.
+///
+/// Where the indicated hydrogens still leave an atom out of the double bonds,
+/// as in pyran or purine, the lowest locant that pairs up the rest takes an
+/// extra hydrogen too.
+///
+/// # Panics
+///
+/// If no single extra hydrogen gives such a structure.
+pub fn indicated_hydrogen(indicated: &[Locant], base: Graph) -> Result<Graph, LocantError> {
+    let mut molecule = base;
+
+    let ring = ring_atoms(&molecule);
+    let mut hydrogens = ring
+        .iter()
+        .flat_map(|&i| molecule.neighbors(i))
+        .filter(|&j| molecule.is_terminal_hydrogen(j))
+        .collect::<Vec<_>>();
+    hydrogens.sort_unstable();
+    hydrogens.dedup();
+    for &h in hydrogens.iter().rev() {
+        molecule.remove_atom(h);
+    }
+
+    let ring = ring_atoms(&molecule);
+    let mut indicated = indicated
+        .iter()
+        .map(|&pos| Ok(molecule.position(pos)?.1))
+        .collect::<Result<Vec<_>, LocantError>>()?;
+    if !is_mancude(&molecule, &ring, &indicated) {
+        let extra = ring
+            .iter()
+            .copied()
+            .filter(|&i| !indicated.contains(&i) && molecule.valence_deficit(i) > 0)
+            .find(|&i| is_mancude(&molecule, &ring, &[indicated.as_slice(), &[i]].concat()))
+            .unwrap_or_else(|| panic!("no mancude ring system with hydrogens at {indicated:?}"));
+        indicated.push(extra);
+    }
+
+    // The extra hydrogens come last, after those of the double-bonded atoms
+    let mut extra = Vec::new();
+    for &i in &ring {
+        let deficit = molecule.valence_deficit(i);
+        if deficit == 0 {
+            continue;
+        }
+        for _ in 1..deficit {
+            add_hydrogen(&mut molecule, i);
+        }
+        if indicated.contains(&i) {
+            extra.push(i);
+        }
+    }
+    for i in extra {
+        add_hydrogen(&mut molecule, i);
+    }
+
+    Ok(molecule)
+}
+
+pub(crate) fn add_hydrogen(molecule: &mut Graph, i: usize) {
+    let h = molecule.atoms.len();
+    molecule.atoms.push(Element::Hydrogen);
+    molecule.bonds.push((i, h));
+}
+
+/// A mancude ring system from its ring atoms and bonds, numbered by
+/// `locants`, with hydrogens placed as for [`indicated_hydrogen`].
+pub fn mancude(atoms: &[Element], bonds: &[(usize, usize)], locants: &[Locant]) -> Graph {
+    let skeleton = Graph {
+        atoms: atoms.to_vec(),
+        bonds: bonds.to_vec(),
+        positions: locants.iter().copied().zip(0..).collect(),
+        ..Default::default()
+    };
+    indicated_hydrogen(&[], skeleton).unwrap()
+}
+
+impl parser::State {
+    /// Applies the indicated hydrogen just before a parent hydride, as in
+    /// "2H-pyran", to it.
+    pub fn pop_indicated_hydrogen(&mut self, molecule: Rc<AST>) -> Rc<AST> {
+        let mut indicated = Vec::new();
+        while let Some(&StackItem::Locant(Locant::Element(n, Element::Hydrogen))) =
+            self.stack.last()
+        {
+            self.stack.pop();
+            indicated.push(Locant::Number(n));
+        }
+        if indicated.is_empty() {
+            return molecule;
+        }
+        indicated.reverse();
+        AST::IndicatedHydrogen(indicated, molecule).into()
+    }
+
+    /// Applies added hydrogen, as in "quinolin-2(1H)-one", to the last
+    /// molecule. The suffix at the locants before the brackets needs those
+    /// atoms saturated, so they take an extra hydrogen as well.
+    pub fn add_hydrogen(&mut self, hydrogens: Vec<Locant>) {
+        let mut locants = self
+            .stack
+            .iter()
+            .rev()
+            .map_while(|item| match item {
+                &StackItem::Locant(pos) => Some(pos),
+                _ => None,
+            })
+            .collect::<Vec<_>>();
+        locants.reverse();
+        locants.extend(hydrogens);
+
+        let StackItem::Molecule(molecule) = self
+            .stack
+            .iter_mut()
+            .rfind(|item| matches!(item, StackItem::Molecule(_)))
+            .expect("added hydrogen without a parent hydride")
+        else {
+            unreachable!()
+        };
+        *molecule = match &**molecule {
+            AST::IndicatedHydrogen(indicated, base) => {
+                let indicated = indicated.iter().copied().chain(locants).collect();
+                AST::IndicatedHydrogen(indicated, base.clone()).into()
+            }
+            _ => AST::IndicatedHydrogen(locants, molecule.clone()).into(),
+        };
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{formula::Formula, graph::Graph, parser::parse, Element};
+
+    #[test]
+    fn test_indicated_hydrogen() {
+        let graph = Graph::from(&*parse("2H-Pyran"));
+        assert_eq!(Formula::from(&graph).to_string(), "C5H6O");
+        // The carbon atom next to the oxygen atom is saturated
+        let oxygen = graph
+            .atoms
+            .iter()
+            .position(|&a| a == Element::Oxygen)
+            .unwrap();
+        assert!(graph
+            .neighbors(oxygen)
+            .any(|i| graph.hydrogen_count(i) == 2));
+
+        let mut graph = Graph::from(&*parse("4H-Chromen-4-one"));
+        assert_eq!(Formula::from(&graph).to_string(), "C9H6O2");
+        assert!(graph.kekulize().is_ok());
+
+        let graph = Graph::from(&*parse("4H-Pyran"));
+        let oxygen = graph
+            .atoms
+            .iter()
+            .position(|&a| a == Element::Oxygen)
+            .unwrap();
+        assert!(graph
+            .neighbors(oxygen)
+            .all(|i| graph.hydrogen_count(i) == 1));
+    }
+
+    #[test]
+    fn test_added_hydrogen() {
+        let mut graph = Graph::from(&*parse("Quinolin-2(1H)-one"));
+        assert_eq!(Formula::from(&graph).to_string(), "C9H7NO");
+        let nitrogen = graph
+            .atoms
+            .iter()
+            .position(|&a| a == Element::Nitrogen)
+            .unwrap();
+        assert_eq!(graph.hydrogen_count(nitrogen), 1);
+        assert!(graph.kekulize().is_ok());
+
+        let mut graph = Graph::from(&*parse("Pyridin-4(1H)-one"));
+        assert_eq!(Formula::from(&graph).to_string(), "C5H5NO");
+        assert!(graph.kekulize().is_ok());
+
+        let mut graph = Graph::from(&*parse("2H-Pyran-3(4H)-one"));
+        assert_eq!(Formula::from(&graph).to_string(), "C5H6O2");
+        assert!(graph.kekulize().is_ok());
+    }
+}
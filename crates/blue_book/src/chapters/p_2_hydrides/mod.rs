@@ -13,6 +13,7 @@ pub enum Hydride {
     Monocyclic(p_22_monocyclic_hydrides::MonocyclicHydride),
     FusedRing(p_25_fused_ring_systems::FusedRingSystem),
     Isobutane,
+    Aniline,
 }
 
 impl Hydride {
@@ -22,6 +23,7 @@ impl Hydride {
             Hydride::Monocyclic(ast) => ast.to_graph(),
             Hydride::FusedRing(ast) => ast.to_graph(),
             Hydride::Isobutane => isobutane_graph(),
+            Hydride::Aniline => aniline_graph(),
         }
     }
 }
@@ -30,3 +32,8 @@ fn isobutane_graph() -> Graph {
     let ast = parser::parse("1,1-Dimethylethane");
     Graph::from(&*ast)
 }
+
+fn aniline_graph() -> Graph {
+    let ast = parser::parse("Benzenamine");
+    Graph::from(&*ast)
+}
@@ -45,6 +45,10 @@ impl SimpleHydride {
                     (length - 1, length + hydrogens - 1),
                 ])
                 .collect(),
+            pi_bonds: Vec::new(),
+            charges: Vec::new(),
+            isotopes: Vec::new(),
+            stereo: Vec::new(),
             positions: (0..length)
                 .map(|i| (Locant::Number(i as u16 + 1), i))
                 .collect(),
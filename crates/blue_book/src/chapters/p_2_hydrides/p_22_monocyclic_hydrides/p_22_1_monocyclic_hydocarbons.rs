@@ -3,7 +3,12 @@
 use parsing::dfa;
 
 use crate::{
-    chapters::p_2_hydrides::Hydride, graph::Graph, plugin::Plugin, scanner::Token, Element, Locant,
+    chapters::p_2_hydrides::{p_21_simple_hydrides::SimpleHydride, Hydride},
+    graph::Graph,
+    parser::{self, StackItem},
+    plugin::Plugin,
+    scanner::Token,
+    Element, Locant,
 };
 
 use self::MonocyclicHydrocarbon::Benzene;
@@ -14,12 +19,17 @@ pub struct MonocyclicHydrocarbonsPlugin;
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum MonocyclicHydrocarbon {
     Benzene,
+    /// A saturated ring of this many carbon atoms, as in "cyclohexane".
+    Cycloalkane(u16),
 }
 
 impl Plugin for MonocyclicHydrocarbonsPlugin {
     fn init_tokens(&self, dfa: &mut dfa::Automaton<Token>) {
+        dfa.insert("cyclo", Token::Cyclo);
         dfa.insert("benzen", Token::Hydride(Benzene.into()));
         dfa.insert("phen", Token::Hydride(Benzene.into()));
+        // Retained name of benzenamine
+        dfa.insert("anilin", Token::Hydride(Hydride::Aniline));
     }
 }
 
@@ -33,6 +43,24 @@ impl MonocyclicHydrocarbon {
     pub fn to_graph(&self) -> Graph {
         match self {
             MonocyclicHydrocarbon::Benzene => benzene_graph(),
+            MonocyclicHydrocarbon::Cycloalkane(n) => cycloalkane_graph(*n as usize),
+        }
+    }
+}
+
+impl parser::State {
+    /// Closes an acyclic hydrocarbon into a ring when "cyclo" comes before it,
+    /// as in "cyclohexane".
+    pub fn pop_cyclo(&mut self, hydride: Hydride) -> Hydride {
+        match hydride {
+            Hydride::Simple(SimpleHydride {
+                length,
+                element: Element::Carbon,
+            }) if self.stack.last() == Some(&StackItem::Cyclo) => {
+                self.stack.pop();
+                MonocyclicHydrocarbon::Cycloalkane(length).into()
+            }
+            hydride => hydride,
         }
     }
 }
@@ -47,7 +75,53 @@ fn benzene_graph() -> Graph {
         bonds: (0..6)
             .flat_map(|i| [(i, i + 6), (i, (i + 1) % 6)])
             .collect(),
+        pi_bonds: vec![],
+        charges: vec![],
+        isotopes: vec![],
+        stereo: vec![],
         positions: (0..6).map(|i| (Locant::Number(i as u16 + 1), i)).collect(),
         free_valences: vec![],
     }
 }
+
+fn cycloalkane_graph(n: usize) -> Graph {
+    Graph {
+        atoms: []
+            .into_iter()
+            .chain((0..n).map(|_| Element::Carbon))
+            .chain((0..2 * n).map(|_| Element::Hydrogen))
+            .collect(),
+        bonds: (0..n)
+            .flat_map(|i| [(i, (i + 1) % n), (i, n + 2 * i), (i, n + 2 * i + 1)])
+            .collect(),
+        positions: (0..n).map(|i| (Locant::Number(i as u16 + 1), i)).collect(),
+        ..Default::default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{formula::Formula, graph::Graph, parser::parse};
+
+    #[test]
+    fn test_cycloalkanes() {
+        let graph = |name: &str| Graph::from(&*parse(name));
+
+        let molecule = graph("Cyclohexane");
+        assert_eq!(Formula::from(&molecule).to_string(), "C6H12");
+        assert_eq!(molecule.rings().rings.len(), 1);
+
+        for (name, smiles) in [
+            ("Cyclopropane", "C1CC1"),
+            ("Cyclohexene", "C1=CCCCC1"),
+            ("Cyclohexylbenzene", "C1CCC(CC1)c1ccccc1"),
+            ("Cyclohexanol", "OC1CCCCC1"),
+        ] {
+            assert_eq!(
+                graph(name).canonical_key(),
+                Graph::from_smiles(smiles).unwrap().canonical_key(),
+                "{name}"
+            );
+        }
+    }
+}
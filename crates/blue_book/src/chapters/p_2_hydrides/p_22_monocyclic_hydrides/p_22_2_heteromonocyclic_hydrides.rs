@@ -3,22 +3,32 @@
 use parsing::dfa;
 
 use crate::{
-    chapters::p_2_hydrides::Hydride, graph::Graph, plugin::Plugin, scanner::Token, Element, Locant,
+    chapters::{
+        p_1_general::p_14_general_rules::p_14_7_indicated_hydrogen::mancude, p_2_hydrides::Hydride,
+    },
+    graph::Graph,
+    plugin::Plugin,
+    scanner::Token,
+    Element, Locant,
 };
 
-use self::HeteromonocyclicHydride::Pyrimidine;
+use self::HeteromonocyclicHydride::{Pyran, Pyridine, Pyrimidine};
 use super::MonocyclicHydride;
 
 pub struct HeteromonocyclicHydridesPlugin;
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum HeteromonocyclicHydride {
+    Pyran,
+    Pyridine,
     Pyrimidine,
 }
 
 impl Plugin for HeteromonocyclicHydridesPlugin {
     fn init_tokens(&self, dfa: &mut dfa::Automaton<Token>) {
-        dfa.insert("pyrimidin", Token::Hydride(Pyrimidine.into()))
+        dfa.insert("pyran", Token::Hydride(Pyran.into()));
+        dfa.insert("pyridin", Token::Hydride(Pyridine.into()));
+        dfa.insert("pyrimidin", Token::Hydride(Pyrimidine.into()));
     }
 }
 
@@ -31,11 +41,24 @@ impl From<HeteromonocyclicHydride> for Hydride {
 impl HeteromonocyclicHydride {
     pub fn to_graph(&self) -> Graph {
         match self {
+            HeteromonocyclicHydride::Pyran => six_membered_ring(Element::Oxygen),
+            HeteromonocyclicHydride::Pyridine => six_membered_ring(Element::Nitrogen),
             HeteromonocyclicHydride::Pyrimidine => pyrimidine_graph(),
         }
     }
 }
 
+/// A six-membered ring with one heteroatom at position 1.
+fn six_membered_ring(heteroatom: Element) -> Graph {
+    let atoms = [heteroatom]
+        .into_iter()
+        .chain((0..5).map(|_| Element::Carbon))
+        .collect::<Vec<_>>();
+    let bonds = (0..6).map(|i| (i, (i + 1) % 6)).collect::<Vec<_>>();
+    let locants = (1..=6).map(Locant::Number).collect::<Vec<_>>();
+    mancude(&atoms, &bonds, &locants)
+}
+
 fn pyrimidine_graph() -> Graph {
     Graph {
         atoms: [
@@ -53,6 +76,10 @@ fn pyrimidine_graph() -> Graph {
             .map(|i| (i, (i + 1) % 6))
             .chain([(1, 6), (3, 7), (4, 8), (5, 9)])
             .collect(),
+        pi_bonds: vec![],
+        charges: vec![],
+        isotopes: vec![],
+        stereo: vec![],
         positions: (0..6).map(|i| (Locant::Number(i as u16 + 1), i)).collect(),
         free_valences: vec![],
     }
@@ -1,18 +1,45 @@
 //! # P-25 Fused and Bridged Fused Ring Systems
 
-use crate::graph::Graph;
+use crate::{
+    chapters::p_1_general::p_14_general_rules::p_14_7_indicated_hydrogen::mancude, graph::Graph,
+    Element, Locant,
+};
 
+pub mod p_25_1_polycyclic_hydrocarbons;
 pub mod p_25_2_heterocyclic_ring_components;
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum FusedRingSystem {
+    Hydrocarbon(p_25_1_polycyclic_hydrocarbons::PolycyclicHydrocarbon),
     Heterogeneous(p_25_2_heterocyclic_ring_components::HeterocyclicRing),
 }
 
 impl FusedRingSystem {
     pub fn to_graph(&self) -> Graph {
         match self {
+            FusedRingSystem::Hydrocarbon(ast) => ast.to_graph(),
             FusedRingSystem::Heterogeneous(ast) => ast.to_graph(),
         }
     }
 }
+
+/// The naphthalene ring system with `element` at position 1, as in quinoline
+/// and chromene, with the fusion atoms at 4a and 8a.
+pub(crate) fn naphthalene_skeleton(element: Element) -> Graph {
+    let atoms = [element]
+        .into_iter()
+        .chain((0..9).map(|_| Element::Carbon))
+        .collect::<Vec<_>>();
+    let bonds = (0..10)
+        .map(|i| (i, (i + 1) % 10))
+        .chain([(4, 9)])
+        .collect::<Vec<_>>();
+    let locants = [1, 2, 3, 4]
+        .map(Locant::Number)
+        .into_iter()
+        .chain([Locant::Letter(4, 'a', 0)])
+        .chain([5, 6, 7, 8].map(Locant::Number))
+        .chain([Locant::Letter(8, 'a', 0)])
+        .collect::<Vec<_>>();
+    mancude(&atoms, &bonds, &locants)
+}
@@ -0,0 +1,37 @@
+//! # P-25.1 Polycyclic Hydrocarbons
+
+use parsing::dfa;
+
+use self::PolycyclicHydrocarbon::Naphthalene;
+use crate::{
+    chapters::p_2_hydrides::Hydride, graph::Graph, plugin::Plugin, scanner::Token, Element,
+};
+
+use super::{naphthalene_skeleton, FusedRingSystem};
+
+pub struct PolycyclicHydrocarbonsPlugin;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PolycyclicHydrocarbon {
+    Naphthalene,
+}
+
+impl Plugin for PolycyclicHydrocarbonsPlugin {
+    fn init_tokens(&self, dfa: &mut dfa::Automaton<Token>) {
+        dfa.insert("naphthalen", Token::Hydride(Naphthalene.into()));
+    }
+}
+
+impl From<PolycyclicHydrocarbon> for Hydride {
+    fn from(ast: PolycyclicHydrocarbon) -> Self {
+        Hydride::FusedRing(FusedRingSystem::Hydrocarbon(ast))
+    }
+}
+
+impl PolycyclicHydrocarbon {
+    pub fn to_graph(&self) -> Graph {
+        match self {
+            Naphthalene => naphthalene_skeleton(Element::Carbon),
+        }
+    }
+}
@@ -2,26 +2,35 @@
 
 use parsing::dfa;
 
-use self::HeterocyclicRing::Purine;
+use self::HeterocyclicRing::{Chromene, Purine, Quinoline};
 use crate::{
-    chapters::p_2_hydrides::Hydride, graph::Graph, plugin::Plugin, scanner::Token, Element, Locant,
+    chapters::{
+        p_1_general::p_14_general_rules::p_14_7_indicated_hydrogen::indicated_hydrogen,
+        p_2_hydrides::Hydride,
+    },
+    graph::Graph,
+    plugin::Plugin,
+    scanner::Token,
+    Element, Locant,
 };
 
-use super::FusedRingSystem;
+use super::{naphthalene_skeleton, FusedRingSystem};
 
 pub struct HeterocyclicRingPlugin;
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum HeterocyclicRing {
-    Purine(u8),
+    Purine,
+    Quinoline,
+    /// 1-Benzopyran
+    Chromene,
 }
 
 impl Plugin for HeterocyclicRingPlugin {
     fn init_tokens(&self, dfa: &mut dfa::Automaton<Token>) {
-        for i in 1..=9 {
-            let key = format!("{}H-purin", i).leak();
-            dfa.insert(key, Token::Hydride(Purine(i).into()));
-        }
+        dfa.insert("purin", Token::Hydride(Purine.into()));
+        dfa.insert("quinolin", Token::Hydride(Quinoline.into()));
+        dfa.insert("chromen", Token::Hydride(Chromene.into()));
     }
 }
 
@@ -34,30 +43,24 @@ impl From<HeterocyclicRing> for Hydride {
 impl HeterocyclicRing {
     pub fn to_graph(&self) -> Graph {
         match self {
-            &Purine(isomer) => purine(isomer),
+            Purine => purine_skeleton(&[]),
+            Quinoline => naphthalene_skeleton(Element::Nitrogen),
+            Chromene => naphthalene_skeleton(Element::Oxygen),
         }
     }
 }
 
+/// Purine with the indicated hydrogen on atom `isomer`, as in 9H-purine.
 pub fn purine(isomer: u8) -> Graph {
-    let mut graph = Graph {
-        atoms: vec![
-            Element::Nitrogen,
-            Element::Carbon,
-            Element::Nitrogen,
-            Element::Carbon,
-            Element::Carbon,
-            Element::Carbon,
-            Element::Nitrogen,
-            Element::Carbon,
-            Element::Nitrogen,
-            Element::Hydrogen,
-            Element::Hydrogen,
-            Element::Hydrogen,
-            Element::Hydrogen,
-        ],
+    purine_skeleton(&[Locant::Number(isomer as u16)])
+}
+
+fn purine_skeleton(indicated: &[Locant]) -> Graph {
+    use Element::{Carbon as C, Nitrogen as N};
+
+    let skeleton = Graph {
+        atoms: vec![N, C, N, C, C, C, N, C, N],
         bonds: vec![
-            // C-C & C-N
             (0, 1),
             (1, 2),
             (2, 3),
@@ -68,17 +71,9 @@ pub fn purine(isomer: u8) -> Graph {
             (6, 7),
             (7, 8),
             (8, 3),
-            // C-H
-            (1, 9),
-            (5, 10),
-            (7, 11),
         ],
         positions: (0..9).map(|i| (Locant::Number(i as u16 + 1), i)).collect(),
-        free_valences: vec![],
+        ..Default::default()
     };
-
-    // N-H bond
-    graph.bonds.push((isomer as usize - 1, 12));
-
-    graph
+    indicated_hydrogen(indicated, skeleton).unwrap()
 }
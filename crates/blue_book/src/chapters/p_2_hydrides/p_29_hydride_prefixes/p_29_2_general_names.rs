@@ -8,6 +8,69 @@ pub struct GeneralHydridePrefixesPlugin;
 
 impl Plugin for GeneralHydridePrefixesPlugin {
     fn init_tokens(&self, dfa: &mut dfa::Automaton<Token>) {
-        dfa.insert("yl", Token::FreeValence);
+        dfa.insert("yl", Token::FreeValence(1));
+        dfa.insert("yliden", Token::FreeValence(2));
+        dfa.insert("ylidyn", Token::FreeValence(3));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        formula::Formula, graph::Graph, parser::parse, test::assert_same_compound, Locant,
+        LocantError,
+    };
+
+    #[test]
+    fn test_free_valences() {
+        let graph = |name: &str| Graph::from(&*parse(name));
+
+        // Double and triple bonds to the parent
+        assert_same_compound("2-Methylidenepropane", "2-Methylpropene");
+        assert_same_compound("1-Methylidynepropane", "Butyne");
+        assert_same_compound("Propan-2-ylbenzene", "2-Phenylpropane");
+
+        // The multiplicity of the stem is not that of the suffix
+        let group = graph("Hexyl");
+        assert_eq!(Formula::from(&group).to_string(), "C6H13");
+        assert_eq!(group.free_valences, [0]);
+
+        let group = graph("Methylidene");
+        assert_eq!(Formula::from(&group).to_string(), "CH2");
+        assert_eq!(group.free_valences, [0, 0]);
+
+        let group = graph("Ethylidene");
+        assert_eq!(Formula::from(&group).to_string(), "C2H4");
+        assert_eq!(group.free_valences, [0, 0]);
+
+        let group = graph("Methanetriyl");
+        assert_eq!(Formula::from(&group).to_string(), "CH");
+        assert_eq!(group.free_valences, [0, 0, 0]);
+
+        // One free valence at each end
+        let group = graph("Ethane-1,2-diyl");
+        assert_eq!(Formula::from(&group).to_string(), "C2H4");
+        assert_eq!(group.free_valences, [0, 1]);
+
+        let group = graph("Propane-1,3-diyl");
+        assert_eq!(Formula::from(&group).to_string(), "C3H6");
+        assert_eq!(group.free_valences, [0, 2]);
+
+        let group = graph("Benzene-1,4-diyl");
+        assert_eq!(Formula::from(&group).to_string(), "C6H4");
+        let [a, b] = group.free_valences[..] else {
+            panic!("expected two free valences: {:?}", group.free_valences);
+        };
+        assert_eq!(group.position(Locant::Number(4)).unwrap().1, b);
+        assert_eq!(group.position(Locant::Number(1)).unwrap().1, a);
+    }
+
+    #[test]
+    fn test_free_valence_errors() {
+        // Methane has four hydrogens, not the six of two triple bonds
+        assert_eq!(
+            Graph::from_ast(&parse("Methane-1,1-diylidyne")).unwrap_err(),
+            LocantError::NoHydrogen(Locant::Number(1)),
+        );
     }
 }
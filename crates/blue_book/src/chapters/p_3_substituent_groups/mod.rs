@@ -2,21 +2,23 @@
 
 use crate::{graph::Graph, Element, Locant};
 
+pub mod p_31_1_4_hydro_prefixes;
 pub mod p_33_suffixes;
 pub mod p_35_characteristic_group_prefixes;
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum CharacteristicGroup {
-    Hydro,
     Hydroxy,
     Oxo,
     Amino,
 }
 
 impl CharacteristicGroup {
+    /// The graph of the group, with its free valences. Groups that can be
+    /// substituted in turn also have a heteroatom locant, such as "N" in
+    /// "N-methylethanamine", which they keep once attached.
     pub fn to_graph(self) -> Graph {
         match self {
-            CharacteristicGroup::Hydro => hydro_graph(),
             CharacteristicGroup::Hydroxy => hydroxy_graph(),
             CharacteristicGroup::Oxo => oxo_graph(),
             CharacteristicGroup::Amino => amino_graph(),
@@ -24,20 +26,18 @@ impl CharacteristicGroup {
     }
 }
 
-fn hydro_graph() -> Graph {
-    Graph {
-        atoms: vec![Element::Hydrogen],
-        bonds: vec![],
-        positions: vec![(Locant::Number(1), 0)],
-        free_valences: vec![0],
-    }
-}
-
 fn hydroxy_graph() -> Graph {
     Graph {
         atoms: vec![Element::Oxygen, Element::Hydrogen],
         bonds: vec![(0, 1)],
-        positions: vec![(Locant::Number(1), 0)],
+        pi_bonds: vec![],
+        charges: vec![],
+        isotopes: vec![],
+        stereo: vec![],
+        positions: vec![
+            (Locant::Number(1), 0),
+            (Locant::Heteroatom(Element::Oxygen, 0), 0),
+        ],
         free_valences: vec![0],
     }
 }
@@ -46,8 +46,12 @@ fn oxo_graph() -> Graph {
     Graph {
         atoms: vec![Element::Oxygen],
         bonds: vec![],
+        pi_bonds: vec![],
+        charges: vec![],
+        isotopes: vec![],
+        stereo: vec![],
         positions: vec![(Locant::Number(1), 0)],
-        free_valences: vec![0],
+        free_valences: vec![0, 0],
     }
 }
 
@@ -55,7 +59,59 @@ fn amino_graph() -> Graph {
     Graph {
         atoms: vec![Element::Nitrogen, Element::Hydrogen, Element::Hydrogen],
         bonds: vec![(0, 1), (0, 2)],
-        positions: vec![(Locant::Number(1), 0)],
+        pi_bonds: vec![],
+        charges: vec![],
+        isotopes: vec![],
+        stereo: vec![],
+        positions: vec![
+            (Locant::Number(1), 0),
+            (Locant::Heteroatom(Element::Nitrogen, 0), 0),
+        ],
         free_valences: vec![0],
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::{formula::Formula, graph::Graph, parser::parse, Element};
+
+    #[test]
+    fn test_heteroatom_substitution() {
+        let graph = |name: &str| Graph::from(&*parse(name));
+        let nitrogen = |graph: &Graph| {
+            (0..graph.atoms.len())
+                .find(|&i| graph.atoms[i] == Element::Nitrogen)
+                .unwrap()
+        };
+
+        // The methyl group is on the nitrogen atom, not on the chain
+        let molecule = graph("N-Methylethanamine");
+        assert_eq!(Formula::from(&molecule).to_string(), "C3H9N");
+        assert_eq!(molecule.hydrogen_count(nitrogen(&molecule)), 1);
+        assert_eq!(molecule.neighbors(nitrogen(&molecule)).count(), 3);
+
+        let molecule = graph("N,N-Dimethylethanamine");
+        assert_eq!(molecule.hydrogen_count(nitrogen(&molecule)), 0);
+
+        let a = graph("N,N-Diethylaniline");
+        let b = graph("N,N-Diethylbenzenamine");
+        assert_eq!(Formula::from(&a).to_string(), "C10H15N");
+        assert_eq!(a.canonical_key(), b.canonical_key());
+
+        // Each amino group has its own locant
+        let molecule = graph("N,N'-Dimethylethane-1,2-diamine");
+        assert_eq!(Formula::from(&molecule).to_string(), "C4H12N2");
+        for i in 0..molecule.atoms.len() {
+            if molecule.atoms[i] == Element::Nitrogen {
+                assert_eq!(molecule.hydrogen_count(i), 1);
+            }
+        }
+
+        let molecule = graph("O-Methylethanol");
+        assert_eq!(Formula::from(&molecule).to_string(), "C3H8O");
+        let oxygen = (0..molecule.atoms.len())
+            .find(|&i| molecule.atoms[i] == Element::Oxygen)
+            .unwrap();
+        assert_eq!(molecule.hydrogen_count(oxygen), 0);
+    }
+}
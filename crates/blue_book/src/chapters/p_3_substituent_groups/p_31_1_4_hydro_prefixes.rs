@@ -0,0 +1,175 @@
+//! # P-31.1.4.2.4 Hydro Prefixes
+//!
+//! Hydro prefixes change the degree of hydrogenation of a parent hydride
+//! rather than substituting it: "hydro" adds a hydrogen atom to each locant,
+//! saturating a double bond, and "dehydro" takes one away. They come in pairs,
+//! as in "1,2,3,4-tetrahydronaphthalene", one pair for each double bond.
+
+use std::rc::Rc;
+
+use parsing::dfa;
+
+use crate::{
+    chapters::p_1_general::p_14_general_rules::p_14_7_indicated_hydrogen::{
+        add_hydrogen, ring_atoms,
+    },
+    graph::Graph,
+    parser::{self, StackItem, AST},
+    plugin::Plugin,
+    scanner::Token,
+    Locant, LocantError,
+};
+
+pub struct HydroPrefixesPlugin;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HydroPrefix {
+    Hydro,
+    Dehydro,
+    /// Full hydrogenation of a mancude parent, as in "perhydronaphthalene".
+    Perhydro,
+}
+
+impl Plugin for HydroPrefixesPlugin {
+    fn init_tokens(&self, dfa: &mut dfa::Automaton<Token>) {
+        dfa.insert("hydr", Token::Hydro(HydroPrefix::Hydro));
+        dfa.insert("dehydr", Token::Hydro(HydroPrefix::Dehydro));
+        dfa.insert("perhydr", Token::Hydro(HydroPrefix::Perhydro));
+    }
+}
+
+/// Adds or subtracts hydrogen atoms at `locants`, which come in pairs. A
+/// double bond at an added hydrogen is saturated, preferably the one to
+/// another of the locants; the rest of the double bonds are left to
+/// kekulization.
+pub fn hydro(prefix: HydroPrefix, locants: &[Locant], base: Graph) -> Result<Graph, LocantError> {
+    let mut molecule = base;
+    if prefix == HydroPrefix::Perhydro {
+        let ring = ring_atoms(&molecule);
+        molecule
+            .pi_bonds
+            .retain(|(a, b)| !ring.contains(a) || !ring.contains(b));
+        for i in ring {
+            for _ in 0..molecule.valence_deficit(i) {
+                add_hydrogen(&mut molecule, i);
+            }
+        }
+        return Ok(molecule);
+    }
+
+    if let (false, Some(&last)) = (locants.len().is_multiple_of(2), locants.last()) {
+        return Err(LocantError::Unpaired(last));
+    }
+    let atoms = locants
+        .iter()
+        .map(|&pos| Ok(molecule.position(pos)?.1))
+        .collect::<Result<Vec<_>, LocantError>>()?;
+
+    for (&pos, &i) in locants.iter().zip(&atoms) {
+        match prefix {
+            HydroPrefix::Hydro => {
+                if molecule.valence_deficit(i) == 0 {
+                    let partner = |&(a, b): &(usize, usize)| {
+                        if a == i {
+                            Some(b)
+                        } else if b == i {
+                            Some(a)
+                        } else {
+                            None
+                        }
+                    };
+                    let k = molecule
+                        .pi_bonds
+                        .iter()
+                        .position(|bond| partner(bond).is_some_and(|j| atoms.contains(&j)))
+                        .or_else(|| {
+                            molecule
+                                .pi_bonds
+                                .iter()
+                                .position(|bond| partner(bond).is_some())
+                        })
+                        .ok_or(LocantError::NoBond(pos))?;
+                    molecule.pi_bonds.remove(k);
+                }
+                add_hydrogen(&mut molecule, i);
+            }
+            HydroPrefix::Dehydro => {
+                let h = molecule
+                    .neighbors(i)
+                    .find(|&j| molecule.is_terminal_hydrogen(j))
+                    .ok_or(LocantError::NoHydrogen(pos))?;
+                molecule.remove_atom(h);
+            }
+            HydroPrefix::Perhydro => unreachable!(),
+        }
+    }
+
+    Ok(molecule)
+}
+
+impl parser::State {
+    /// Applies the hydro prefix on top of the stack, with its locants, to the
+    /// parent hydride.
+    pub fn pop_hydro_prefix(&mut self, molecule: Rc<AST>) -> Rc<AST> {
+        let Some(StackItem::Hydro(prefix)) = self.stack.pop() else {
+            unreachable!()
+        };
+        let mut locants = Vec::new();
+        if prefix != HydroPrefix::Perhydro {
+            locants.extend(self.pop_multiplicity_and_positions());
+            locants.reverse();
+        }
+        AST::Hydro(prefix, locants, molecule).into()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{formula::Formula, graph::Graph, parser::parse, Locant, LocantError};
+
+    #[test]
+    fn test_hydro_prefixes() {
+        let graph = |name: &str| Graph::from(&*parse(name));
+
+        // The hydrogenated ring is saturated, leaving a benzene ring
+        let mut molecule = graph("1,2,3,4-Tetrahydronaphthalene");
+        assert_eq!(Formula::from(&molecule).to_string(), "C10H12");
+        assert_eq!((0..4).map(|i| molecule.hydrogen_count(i)).sum::<usize>(), 8);
+        assert!(molecule.kekulize().is_ok());
+        assert_eq!(molecule.aromaticity().rings.len(), 1);
+
+        let mut molecule = graph("1,2-Dihydronaphthalene");
+        assert_eq!(Formula::from(&molecule).to_string(), "C10H10");
+        assert!(molecule.kekulize().is_ok());
+
+        let molecule = graph("Perhydronaphthalene");
+        assert_eq!(Formula::from(&molecule).to_string(), "C10H18");
+
+        // Double bonds written out are saturated too
+        let molecule = graph("1,2-Dihydroethene");
+        assert_eq!(Formula::from(&molecule).to_string(), "C2H6");
+        assert!(molecule.pi_bonds.is_empty());
+
+        let mut molecule = graph("1,2-Didehydrobenzene");
+        assert_eq!(Formula::from(&molecule).to_string(), "C6H4");
+        assert!(molecule.kekulize().is_ok());
+    }
+
+    #[test]
+    fn test_hydro_prefix_errors() {
+        let error = |name: &str| Graph::from_ast(&parse(name)).unwrap_err();
+
+        assert_eq!(
+            error("1,2,3-Trihydronaphthalene"),
+            LocantError::Unpaired(Locant::Number(3)),
+        );
+        assert_eq!(
+            error("1,2-Dihydroethane"),
+            LocantError::NoBond(Locant::Number(1))
+        );
+        assert_eq!(
+            error("1,1,1,1,2,2-Hexadehydroethane"),
+            LocantError::NoHydrogen(Locant::Number(1)),
+        );
+    }
+}
@@ -10,7 +10,6 @@ pub struct CharacteristicGroupPrefixesPlugin;
 
 impl Plugin for CharacteristicGroupPrefixesPlugin {
     fn init_tokens(&self, dfa: &mut dfa::Automaton<Token>) {
-        dfa.insert("hydr", Token::Prefix(CharacteristicGroup::Hydro));
         dfa.insert("oxy", Token::Prefix(CharacteristicGroup::Hydroxy));
         dfa.insert("hydroxy", Token::Prefix(CharacteristicGroup::Hydroxy));
         dfa.insert("amino", Token::Prefix(CharacteristicGroup::Amino));
@@ -0,0 +1,3 @@
+//! # P-6 Applications to Specific Classes of Compounds
+
+pub mod p_65_acids_and_derivatives;
@@ -0,0 +1,4 @@
+//! # P-65 Acids and Derivatives
+
+pub mod p_65_1_1_carboxylic_acids;
+pub mod p_65_1_7_acyl_groups;
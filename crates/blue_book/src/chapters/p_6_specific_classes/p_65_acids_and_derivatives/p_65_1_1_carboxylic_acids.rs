@@ -0,0 +1,64 @@
+//! # P-65.1.1 Carboxylic Acids
+
+use parsing::dfa;
+
+use crate::{
+    parser::{self, AST},
+    plugin::Plugin,
+    scanner::Token,
+};
+
+use super::p_65_1_7_acyl_groups::AcylSuffix;
+
+pub struct CarboxylicAcidsPlugin;
+
+impl Plugin for CarboxylicAcidsPlugin {
+    fn init_tokens(&self, dfa: &mut dfa::Automaton<Token>) {
+        // The acyl groups are named after the acids, with "oyl" for "oic acid"
+        // and "carbonyl" for "carboxylic acid"
+        dfa.insert("oic acid", Token::Acid(AcylSuffix::Oyl));
+        dfa.insert("carboxylic acid", Token::Acid(AcylSuffix::Carbonyl));
+    }
+}
+
+impl parser::State {
+    /// Turns the parent hydride into a carboxylic acid at the locants of the
+    /// suffix, as in "butanedioic acid", or at its first atom.
+    pub fn pop_acid(&mut self, suffix: AcylSuffix) -> AST {
+        let mut positions = self.pop_multiplicity_and_positions().collect::<Vec<_>>();
+        positions.reverse();
+        let base = self.pop_molecule();
+        AST::Acid(suffix, positions, base)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{formula::Formula, graph::Graph, parser::parse, test::assert_same_compound};
+
+    #[test]
+    fn test_carboxylic_acids() {
+        let graph = |name: &str| Graph::from(&*parse(name));
+
+        let acid = graph("Ethanoic acid");
+        assert_eq!(Formula::from(&acid).to_string(), "C2H4O2");
+        assert_eq!(
+            acid.canonical_key(),
+            Graph::from_smiles("CC(=O)O").unwrap().canonical_key(),
+        );
+
+        assert_same_compound("Propanoic acid", "1-Hydroxypropan-1-one");
+        assert_same_compound("Benzenecarboxylic acid", "Hydroxy(phenyl)methanone");
+        assert_same_compound("2-Aminoethanoic acid", "Glycine");
+        assert_same_compound("2-Aminopropanoic acid", "Alanine");
+
+        let acid = graph("Butanedioic acid");
+        assert_eq!(Formula::from(&acid).to_string(), "C4H6O4");
+        assert_eq!(
+            acid.canonical_key(),
+            Graph::from_smiles("OC(=O)CCC(=O)O")
+                .unwrap()
+                .canonical_key(),
+        );
+    }
+}
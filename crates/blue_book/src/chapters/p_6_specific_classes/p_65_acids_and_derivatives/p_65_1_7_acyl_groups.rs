@@ -0,0 +1,147 @@
+//! # P-65.1.7 Acyl Groups
+
+use parsing::dfa;
+
+use crate::{
+    chapters::{
+        p_2_hydrides::p_22_monocyclic_hydrides::p_22_1_monocyclic_hydocarbons::MonocyclicHydrocarbon,
+        p_3_substituent_groups::CharacteristicGroup,
+    },
+    graph::{self, Graph},
+    parser::{self, AST},
+    plugin::Plugin,
+    scanner::Token,
+    Locant,
+};
+
+pub struct AcylGroupsPlugin;
+
+/// The endings that turn a parent hydride into an acyl group.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AcylSuffix {
+    /// The carbon atom at the locant becomes the carbonyl carbon, as in
+    /// "propanoyl".
+    Oyl,
+    /// A carbonyl group is attached at the locant, as in "benzenecarbonyl".
+    Carbonyl,
+}
+
+/// Retained names of acyl groups.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AcylGroup {
+    Formyl,
+    Acetyl,
+    Benzoyl,
+    Carbamoyl,
+    Oxalyl,
+}
+
+impl Plugin for AcylGroupsPlugin {
+    fn init_tokens(&self, dfa: &mut dfa::Automaton<Token>) {
+        dfa.insert("oyl", Token::Acyl(AcylSuffix::Oyl));
+        dfa.insert("carbonyl", Token::Acyl(AcylSuffix::Carbonyl));
+
+        dfa.insert("formyl", Token::AcylGroup(AcylGroup::Formyl));
+        dfa.insert("acetyl", Token::AcylGroup(AcylGroup::Acetyl));
+        dfa.insert("benzoyl", Token::AcylGroup(AcylGroup::Benzoyl));
+        dfa.insert("carbamoyl", Token::AcylGroup(AcylGroup::Carbamoyl));
+        dfa.insert("oxalyl", Token::AcylGroup(AcylGroup::Oxalyl));
+    }
+}
+
+impl AcylGroup {
+    /// The graph of the group, built as the systematic name it stands for:
+    /// "methanoyl", "ethanoyl", "benzenecarbonyl", "aminomethanoyl" and
+    /// "ethanedioyl".
+    pub fn to_graph(self) -> Graph {
+        let one = [Locant::Unspecified];
+        let (suffix, positions, base) = match self {
+            AcylGroup::Formyl => (AcylSuffix::Oyl, &one[..], graph::alkane(1)),
+            AcylGroup::Acetyl => (AcylSuffix::Oyl, &one[..], graph::alkane(2)),
+            AcylGroup::Benzoyl => (
+                AcylSuffix::Carbonyl,
+                &one[..],
+                MonocyclicHydrocarbon::Benzene.to_graph(),
+            ),
+            AcylGroup::Carbamoyl => {
+                let amino = CharacteristicGroup::Amino.to_graph();
+                let base = graph::substitute(Locant::Unspecified, amino, graph::alkane(1));
+                (AcylSuffix::Oyl, &one[..], base.unwrap())
+            }
+            AcylGroup::Oxalyl => (
+                AcylSuffix::Oyl,
+                &[Locant::Unspecified; 2][..],
+                graph::alkane(2),
+            ),
+        };
+        graph::acyl(suffix, positions, base).unwrap()
+    }
+}
+
+impl parser::State {
+    /// Turns the parent hydride into an acyl group at the locants of the
+    /// suffix, as in "butanedioyl", or at its first atom.
+    pub fn pop_acyl(&mut self, suffix: AcylSuffix) -> AST {
+        let mut positions = self.pop_multiplicity_and_positions().collect::<Vec<_>>();
+        positions.reverse();
+        let base = self.pop_molecule();
+        AST::Acyl(suffix, positions, base)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        formula::Formula, graph::Graph, parser::parse, test::assert_same_compound, Element, Locant,
+        LocantError,
+    };
+
+    #[test]
+    fn test_acyl_groups() {
+        let graph = |name: &str| Graph::from(&*parse(name));
+
+        assert_same_compound("Propanoylbenzene", "1-Phenylpropan-1-one");
+        assert_same_compound("Ethanecarbonylbenzene", "1-Phenylpropan-1-one");
+        assert_same_compound("Acetylbenzene", "1-Phenylethan-1-one");
+        assert_same_compound("Acetylbenzene", "Ethanoylbenzene");
+        assert_same_compound("Benzoylbenzene", "Diphenylmethanone");
+        assert_same_compound("Formylbenzene", "Methanoylbenzene");
+        assert_same_compound("Carbamoylbenzene", "Amino(phenyl)methanone");
+        assert_same_compound("Oxalyl", "Ethanedioyl");
+
+        // A carbonyl group on a ring
+        assert_same_compound("Cyclohexanecarbonylbenzene", "Cyclohexyl(phenyl)methanone");
+        assert_same_compound("Cyclohexanecarbonyl", "Cyclohexylmethanoyl");
+
+        let molecule = graph("Carbamoylbenzene");
+        assert_eq!(Formula::from(&molecule).to_string(), "C7H7NO");
+        let nitrogen = (0..molecule.atoms.len())
+            .find(|&i| molecule.atoms[i] == Element::Nitrogen)
+            .unwrap();
+        assert_eq!(molecule.hydrogen_count(nitrogen), 2);
+
+        // The carbonyl carbon carries the free valence
+        let formyl = graph("Formyl");
+        assert_eq!(formyl.free_valences.len(), 1);
+        let carbon = formyl.free_valences[0];
+        assert_eq!(formyl.atoms[carbon], Element::Carbon);
+        let oxygen = (0..formyl.atoms.len())
+            .find(|&i| formyl.atoms[i] == Element::Oxygen)
+            .unwrap();
+        assert_eq!(formyl.bond_order(carbon, oxygen), 2);
+
+        // Both ends of the chain are acyl groups
+        let oxalyl = graph("Oxalyl");
+        assert_eq!(Formula::from(&oxalyl).to_string(), "C2O2");
+        assert_eq!(oxalyl.free_valences.len(), 2);
+    }
+
+    #[test]
+    fn test_acyl_group_errors() {
+        // The middle carbon of propane has only two hydrogens to replace
+        assert_eq!(
+            Graph::from_ast(&parse("Propan-2-oyl")).unwrap_err(),
+            LocantError::NoHydrogen(Locant::Number(2)),
+        );
+    }
+}
@@ -0,0 +1,405 @@
+//! # P-8 Isotopically Modified Compounds
+
+use std::rc::Rc;
+
+use crate::{
+    graph::Graph,
+    parser::{self, StackItem, AST},
+    Element, Locant, LocantError,
+};
+
+/// One nuclide of an isotope descriptor, such as "1-¹³C" or "²H₃": `count`
+/// atoms of `element` have the mass number `mass_number`. The atoms are the
+/// one at the locant, or for hydrogen the hydrogens on the atom at the locant.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Nuclide {
+    pub locant: Locant,
+    pub element: Element,
+    pub mass_number: u16,
+    pub count: u16,
+}
+
+const SUPERSCRIPTS: [char; 10] = ['⁰', '¹', '²', '³', '⁴', '⁵', '⁶', '⁷', '⁸', '⁹'];
+const SUBSCRIPTS: [char; 10] = ['₀', '₁', '₂', '₃', '₄', '₅', '₆', '₇', '₈', '₉'];
+
+/// Scans an isotope descriptor at the start of `input`: nuclides in
+/// parentheses for isotopically substituted compounds (P-82), as in
+/// "(²H₃)methanol", or in square brackets for specifically labelled compounds
+/// (P-83), as in "[1-¹⁴C]ethanol". Mass numbers are written as superscripts
+/// and counts as subscripts, or, if `plain` is set, both as digits, as in
+/// "(2H3)methanol".
+///
+/// Returns the nuclides, one for each locant, and the length of the
+/// descriptor.
+pub(crate) fn scan_descriptor(input: &str, plain: bool) -> Option<(Vec<Nuclide>, usize)> {
+    let close = match input.chars().next()? {
+        '(' => ')',
+        '[' => ']',
+        _ => return None,
+    };
+    let mut rest = &input[1..];
+
+    let mut nuclides = Vec::new();
+    loop {
+        // Locants such as "1,2-" come before the nuclide
+        let mut locants = Vec::new();
+        let mut tail = rest;
+        while let Some((locant, after)) = number(tail, &ascii_digit) {
+            if let Some(after) = after.strip_prefix(',') {
+                locants.push(locant);
+                tail = after;
+            } else if let Some(after) = after.strip_prefix('-') {
+                locants.push(locant);
+                rest = after;
+                break;
+            } else {
+                locants.clear();
+                break;
+            }
+        }
+
+        let (mass_number, tail) = match number(rest, &superscript) {
+            Some(scanned) => scanned,
+            None if plain => number(rest, &ascii_digit)?,
+            None => return None,
+        };
+        let (element, tail) = element(tail)?;
+        rest = tail;
+
+        let count = match number(rest, &subscript)
+            .or_else(|| number(rest, &ascii_digit).filter(|_| plain))
+        {
+            Some((count, tail)) => {
+                rest = tail;
+                count
+            }
+            None => 1,
+        };
+
+        if locants.is_empty() {
+            nuclides.push(Nuclide {
+                locant: Locant::Unspecified,
+                element,
+                mass_number,
+                count,
+            });
+        } else {
+            // As in "1,2-¹³C₂", one atom at each locant
+            nuclides.extend(locants.into_iter().map(|locant| Nuclide {
+                locant: Locant::Number(locant),
+                element,
+                mass_number,
+                count: 1,
+            }));
+        }
+
+        match rest.strip_prefix(',') {
+            Some(tail) => rest = tail,
+            None => {
+                rest = rest.strip_prefix(close)?;
+                break;
+            }
+        }
+    }
+
+    Some((nuclides, input.len() - rest.len()))
+}
+
+fn ascii_digit(c: char) -> Option<u16> {
+    c.to_digit(10).map(|digit| digit as u16)
+}
+
+fn superscript(c: char) -> Option<u16> {
+    SUPERSCRIPTS
+        .iter()
+        .position(|&s| s == c)
+        .map(|digit| digit as u16)
+}
+
+fn subscript(c: char) -> Option<u16> {
+    SUBSCRIPTS
+        .iter()
+        .position(|&s| s == c)
+        .map(|digit| digit as u16)
+}
+
+/// Reads a number written with the given digits, and returns it with the rest
+/// of the input.
+fn number<'a>(input: &'a str, digit: &dyn Fn(char) -> Option<u16>) -> Option<(u16, &'a str)> {
+    let mut value: u16 = 0;
+    let mut len = 0;
+    for c in input.chars() {
+        let Some(d) = digit(c) else {
+            break;
+        };
+        value = value.checked_mul(10)?.checked_add(d)?;
+        len += c.len_utf8();
+    }
+    (len > 0).then_some((value, &input[len..]))
+}
+
+/// Reads an element symbol, as in "C" or "Cl", and returns it with the rest of
+/// the input.
+fn element(input: &str) -> Option<(Element, &str)> {
+    let mut chars = input.chars();
+    if !chars.next()?.is_ascii_uppercase() {
+        return None;
+    }
+    let len = match chars.next() {
+        Some(c) if c.is_ascii_lowercase() => 2,
+        _ => 1,
+    };
+    let element = Element::from_symbol(&input[..len])?;
+    Some((element, &input[len..]))
+}
+
+/// The mass of a nuclide, in daltons. Mass numbers outside the table have the
+/// monoisotopic mass of the element, if it is of that nuclide, or otherwise
+/// the mass number itself.
+pub fn nuclide_mass(element: Element, mass_number: u16) -> f64 {
+    // https://www.ciaaw.org/atomic-masses.htm
+    match (element, mass_number) {
+        (Element::Hydrogen, 1) => 1.007_825_032,
+        (Element::Hydrogen, 2) => 2.014_101_778,
+        (Element::Hydrogen, 3) => 3.016_049_281,
+        (Element::Carbon, 11) => 11.011_433_6,
+        (Element::Carbon, 12) => 12.0,
+        (Element::Carbon, 13) => 13.003_354_835,
+        (Element::Carbon, 14) => 14.003_241_988,
+        (Element::Nitrogen, 13) => 13.005_738_61,
+        (Element::Nitrogen, 14) => 14.003_074_004,
+        (Element::Nitrogen, 15) => 15.000_108_899,
+        (Element::Oxygen, 15) => 15.003_065_6,
+        (Element::Oxygen, 16) => 15.994_914_620,
+        (Element::Oxygen, 17) => 16.999_131_757,
+        (Element::Oxygen, 18) => 17.999_159_613,
+        (Element::Fluorine, 18) => 18.000_937_3,
+        (Element::Fluorine, 19) => 18.998_403_163,
+        (Element::Phosphorus, 31) => 30.973_761_998,
+        (Element::Phosphorus, 32) => 31.973_907_643,
+        (Element::Phosphorus, 33) => 32.971_725_69,
+        (Element::Sulfur, 32) => 31.972_071_174,
+        (Element::Sulfur, 33) => 32.971_458_910,
+        (Element::Sulfur, 34) => 33.967_867_00,
+        (Element::Sulfur, 35) => 34.969_032_32,
+        (Element::Chlorine, 35) => 34.968_852_68,
+        (Element::Chlorine, 36) => 35.968_306_82,
+        (Element::Chlorine, 37) => 36.965_902_60,
+        (Element::Bromine, 79) => 78.918_337_6,
+        (Element::Bromine, 81) => 80.916_289_7,
+        (Element::Iodine, 123) => 122.905_589,
+        (Element::Iodine, 125) => 124.904_630_2,
+        (Element::Iodine, 127) => 126.904_472,
+        (Element::Iodine, 131) => 130.906_124_6,
+        _ if element.monoisotopic_mass().round() == mass_number as f64 => {
+            element.monoisotopic_mass()
+        }
+        _ => mass_number as f64,
+    }
+}
+
+/// Gives atoms of `molecule` the mass number of the nuclide: the atom at its
+/// locant, or the hydrogens on it. Without a locant, the atoms are taken in
+/// order of their locants and then of the atoms, skipping any already
+/// labelled. Fails if there are too few such atoms.
+pub fn label(nuclide: Nuclide, molecule: Graph) -> Result<Graph, LocantError> {
+    let mut molecule = molecule;
+
+    let atoms = if nuclide.locant == Locant::Unspecified {
+        let mut atoms = molecule
+            .positions
+            .iter()
+            .map(|&(_, i)| i)
+            .collect::<Vec<_>>();
+        let rest = (0..molecule.atoms.len())
+            .filter(|i| !atoms.contains(i))
+            .collect::<Vec<_>>();
+        atoms.extend(rest);
+        atoms
+    } else {
+        vec![molecule.position(nuclide.locant)?.1]
+    };
+
+    let mut candidates = Vec::new();
+    for i in atoms {
+        if nuclide.element == Element::Hydrogen && molecule.atoms[i] != Element::Hydrogen {
+            candidates.extend(
+                molecule
+                    .neighbors(i)
+                    .filter(|&j| molecule.atoms[j] == Element::Hydrogen),
+            );
+        } else if molecule.atoms[i] == nuclide.element {
+            candidates.push(i);
+        }
+    }
+
+    let mut remaining = nuclide.count;
+    for i in candidates {
+        if remaining == 0 {
+            break;
+        }
+        if molecule.mass_number(i).is_none() {
+            molecule.isotopes.push((i, nuclide.mass_number));
+            remaining -= 1;
+        }
+    }
+    if remaining > 0 {
+        return Err(LocantError::NoNuclide(nuclide.locant));
+    }
+
+    Ok(molecule)
+}
+
+impl parser::State {
+    /// Applies the nuclides of an isotope descriptor just below the top of the
+    /// stack to `molecule`.
+    pub fn pop_nuclides(&mut self, molecule: Rc<AST>) -> Rc<AST> {
+        let mut molecule = molecule;
+        while let Some(&StackItem::Nuclide(nuclide)) = self.stack.last() {
+            self.stack.pop();
+            molecule = AST::Isotopic(nuclide, molecule).into();
+        }
+        molecule
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{formula::Formula, graph::Graph, parser::parse, Element, Locant, LocantError};
+
+    use super::{scan_descriptor, Nuclide};
+
+    #[test]
+    fn test_scan_descriptor() {
+        let nuclide = |locant, element, mass_number, count| Nuclide {
+            locant,
+            element,
+            mass_number,
+            count,
+        };
+
+        assert_eq!(
+            scan_descriptor("(²H₃)methanol", false),
+            Some((
+                vec![nuclide(Locant::Unspecified, Element::Hydrogen, 2, 3)],
+                "(²H₃)".len(),
+            )),
+        );
+        assert_eq!(
+            scan_descriptor("(1-¹³C)ethanoic acid", false),
+            Some((
+                vec![nuclide(Locant::Number(1), Element::Carbon, 13, 1)],
+                "(1-¹³C)".len(),
+            )),
+        );
+        assert_eq!(
+            scan_descriptor("[2-¹⁴C]glycine", false),
+            Some((
+                vec![nuclide(Locant::Number(2), Element::Carbon, 14, 1)],
+                "[2-¹⁴C]".len(),
+            )),
+        );
+        assert_eq!(
+            scan_descriptor("(2H3)methanol", true),
+            Some((
+                vec![nuclide(Locant::Unspecified, Element::Hydrogen, 2, 3)],
+                "(2H3)".len(),
+            )),
+        );
+        assert_eq!(
+            scan_descriptor("(1,2-¹³C₂,¹⁸O)ethanol", false),
+            Some((
+                vec![
+                    nuclide(Locant::Number(1), Element::Carbon, 13, 1),
+                    nuclide(Locant::Number(2), Element::Carbon, 13, 1),
+                    nuclide(Locant::Unspecified, Element::Oxygen, 18, 1),
+                ],
+                "(1,2-¹³C₂,¹⁸O)".len(),
+            )),
+        );
+
+        assert_eq!(scan_descriptor("(2H3)methanol", false), None);
+        assert_eq!(scan_descriptor("(tert-butylamino)", true), None);
+    }
+
+    #[test]
+    fn test_parse_isotopic() {
+        for name in ["(²H₃)Methanol", "(2H3)methanol"] {
+            let graph = Graph::from(&*parse(name));
+            let formula = Formula::from(&graph);
+            assert_eq!(formula.to_string(), "CH[2H]3O");
+            assert!((formula.monoisotopic_mass() - 35.045_045).abs() < 0.0001);
+
+            // The deuterium atoms are all on the carbon atom
+            for &(i, _) in &graph.isotopes {
+                let j = graph.neighbors(i).next().unwrap();
+                assert_eq!(graph.atoms[j], Element::Carbon);
+            }
+        }
+
+        let graph = Graph::from(&*parse("(1-¹³C)ethanol"));
+        let &[(i, 13)] = graph.isotopes.as_slice() else {
+            panic!("expected one carbon-13 atom: {:?}", graph.isotopes);
+        };
+        assert!(graph
+            .neighbors(i)
+            .any(|j| graph.atoms[j] == Element::Oxygen));
+
+        let graph = Graph::from(&*parse("[2-¹⁴C]ethanol"));
+        let &[(i, 14)] = graph.isotopes.as_slice() else {
+            panic!("expected one carbon-14 atom: {:?}", graph.isotopes);
+        };
+        assert!(graph
+            .neighbors(i)
+            .all(|j| graph.atoms[j] != Element::Oxygen));
+        let formula = Formula::from(&graph);
+        assert_eq!(formula.to_string(), "C[14C]H6O");
+
+        // The carboxy carbon of the acid
+        let graph = Graph::from(&*parse("(1-¹³C)ethanoic acid"));
+        let &[(i, 13)] = graph.isotopes.as_slice() else {
+            panic!("expected one carbon-13 atom: {:?}", graph.isotopes);
+        };
+        assert_eq!(
+            graph
+                .neighbors(i)
+                .filter(|&j| graph.atoms[j] == Element::Oxygen)
+                .count(),
+            2,
+        );
+        assert_eq!(
+            graph.canonical_key(),
+            Graph::from_smiles("C[13C](=O)O").unwrap().canonical_key(),
+        );
+
+        // The α-carbon of the amino acid
+        let graph = Graph::from(&*parse("[2-¹⁴C]glycine"));
+        assert_eq!(Formula::from(&graph).to_string(), "C[14C]H5NO2");
+        assert_eq!(
+            graph.canonical_key(),
+            Graph::from_smiles("N[14CH2]C(=O)O")
+                .unwrap()
+                .canonical_key(),
+        );
+        assert_ne!(
+            graph.canonical_key(),
+            Graph::from(&*parse("[1-¹⁴C]glycine")).canonical_key(),
+        );
+    }
+
+    #[test]
+    fn test_label_errors() {
+        let error = |name: &str| Graph::from_ast(&parse(name)).unwrap_err();
+
+        // Methanol has only four hydrogens
+        assert_eq!(
+            error("(²H₉)methanol"),
+            LocantError::NoNuclide(Locant::Unspecified),
+        );
+        // Carbon 1 of ethanol is not nitrogen
+        assert_eq!(
+            error("(1-¹⁵N)ethanol"),
+            LocantError::NoNuclide(Locant::Number(1)),
+        );
+    }
+}
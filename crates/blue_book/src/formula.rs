@@ -0,0 +1,220 @@
+use std::{collections::BTreeMap, fmt, str::FromStr};
+
+use crate::{chapters::p_8_isotopically_modified_compounds::nuclide_mass, graph::Graph, Element};
+
+/// A molecular formula. Iterating over `atom_counts` visits the elements in
+/// Hill order.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct Formula {
+    pub atom_counts: BTreeMap<Element, usize>,
+    /// The atoms of `atom_counts` that are specific isotopes, by element and
+    /// mass number.
+    pub isotopes: BTreeMap<(Element, u16), usize>,
+}
+
+impl Formula {
+    pub fn count(&self, element: Element) -> usize {
+        self.atom_counts.get(&element).copied().unwrap_or(0)
+    }
+
+    pub fn atom_count(&self) -> usize {
+        self.atom_counts.values().sum()
+    }
+
+    /// The number of atoms of `element` that are not specific isotopes.
+    fn natural_count(&self, element: Element) -> usize {
+        let isotopic = self
+            .isotopes
+            .range((element, 0)..=(element, u16::MAX))
+            .map(|(_, &count)| count)
+            .sum::<usize>();
+        self.count(element) - isotopic
+    }
+
+    /// The mass of the specific isotopes, in daltons.
+    fn isotopic_mass(&self) -> f64 {
+        self.isotopes
+            .iter()
+            .map(|(&(element, mass_number), &count)| {
+                nuclide_mass(element, mass_number) * count as f64
+            })
+            .sum()
+    }
+
+    /// The average molecular weight, in grams per mole.
+    pub fn molecular_weight(&self) -> f64 {
+        let natural = self
+            .atom_counts
+            .keys()
+            .map(|&element| element.atomic_mass() * self.natural_count(element) as f64)
+            .sum::<f64>();
+        natural + self.isotopic_mass()
+    }
+
+    /// The mass of the molecule made up of the most abundant isotope of each
+    /// element, or of the specific isotopes, in daltons.
+    pub fn monoisotopic_mass(&self) -> f64 {
+        let natural = self
+            .atom_counts
+            .keys()
+            .map(|&element| element.monoisotopic_mass() * self.natural_count(element) as f64)
+            .sum::<f64>();
+        natural + self.isotopic_mass()
+    }
+}
+
+impl From<&Graph> for Formula {
+    fn from(graph: &Graph) -> Self {
+        let mut atom_counts = BTreeMap::new();
+        for &element in &graph.atoms {
+            *atom_counts.entry(element).or_default() += 1;
+        }
+        let mut isotopes = BTreeMap::new();
+        for &(i, mass_number) in &graph.isotopes {
+            *isotopes.entry((graph.atoms[i], mass_number)).or_default() += 1;
+        }
+        Self {
+            atom_counts,
+            isotopes,
+        }
+    }
+}
+
+// https://en.wikipedia.org/wiki/Chemical_formula#Hill_system
+// Specific isotopes follow the other atoms of their element, as in "CH[2H]3O".
+impl fmt::Display for Formula {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let mut atom_counts = self.atom_counts.iter().collect::<Vec<_>>();
+        if !self.atom_counts.contains_key(&Element::Carbon) {
+            // Without carbon, hydrogen is sorted alphabetically with the rest
+            atom_counts.sort_by_key(|(element, _)| element.symbol());
+        }
+
+        let write_count = |f: &mut fmt::Formatter, count: usize| {
+            if count != 1 {
+                write!(f, "{count}")?;
+            }
+            Ok(())
+        };
+        for (&element, _) in atom_counts {
+            let count = self.natural_count(element);
+            if count > 0 {
+                write!(f, "{}", element.symbol())?;
+                write_count(f, count)?;
+            }
+            for (&(_, mass_number), &count) in
+                self.isotopes.range((element, 0)..=(element, u16::MAX))
+            {
+                write!(f, "[{mass_number}{}]", element.symbol())?;
+                write_count(f, count)?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl FromStr for Formula {
+    type Err = &'static str;
+
+    fn from_str(mut s: &str) -> Result<Self, Self::Err> {
+        let mut atom_counts = BTreeMap::new();
+        let mut isotopes = BTreeMap::new();
+
+        while !s.is_empty() {
+            // A specific isotope, as in "[13C]"
+            let mut mass_number = None;
+            let symbol;
+            if let Some(rest) = s.strip_prefix('[') {
+                let (isotope, rest) = rest.split_once(']').ok_or("Unclosed isotope")?;
+                let len = isotope
+                    .find(|c: char| !c.is_ascii_digit())
+                    .unwrap_or(isotope.len());
+                mass_number = Some(isotope[..len].parse().map_err(|_| "Invalid mass number")?);
+                symbol = &isotope[len..];
+                s = rest;
+            } else {
+                let len = s
+                    .char_indices()
+                    .skip(1)
+                    .find(|&(_, c)| !c.is_ascii_lowercase())
+                    .map_or(s.len(), |(i, _)| i);
+                symbol = &s[..len];
+                s = &s[len..];
+            }
+            let element = symbol.parse()?;
+
+            let len = s.find(|c: char| !c.is_ascii_digit()).unwrap_or(s.len());
+            let count = if len == 0 {
+                1
+            } else {
+                s[..len].parse().map_err(|_| "Invalid atom count")?
+            };
+            s = &s[len..];
+
+            *atom_counts.entry(element).or_default() += count;
+            if let Some(mass_number) = mass_number {
+                *isotopes.entry((element, mass_number)).or_default() += count;
+            }
+        }
+
+        Ok(Self {
+            atom_counts,
+            isotopes,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        graph::Graph,
+        parser::parse,
+        test::{CAFFEINE, ISOPROPANOL},
+        Element,
+    };
+
+    use super::Formula;
+
+    #[test]
+    fn test_formula_from_graph() {
+        let formula = Formula::from(&Graph::from(&*parse(CAFFEINE)));
+        assert_eq!(formula.to_string(), "C8H10N4O2");
+        assert_eq!(formula.count(Element::Nitrogen), 4);
+        assert_eq!(formula.atom_count(), 24);
+
+        let formula = Formula::from(&Graph::from(&*parse(ISOPROPANOL)));
+        assert_eq!(formula.to_string(), "C3H8O");
+    }
+
+    #[test]
+    fn test_parse_formula() {
+        for s in ["C8H10N4O2", "C13H21NO3", "CH4", "ClH", "H2O", "Br2"] {
+            let formula: Formula = s.parse().unwrap();
+            assert_eq!(formula.to_string(), s);
+        }
+
+        let formula: Formula = "HCl".parse().unwrap();
+        assert_eq!(formula.to_string(), "ClH");
+
+        for s in ["CH[2H]3O", "[13C]H4", "C[14C]H6O", "Cl[37Cl]"] {
+            let formula: Formula = s.parse().unwrap();
+            assert_eq!(formula.to_string(), s);
+        }
+
+        assert!("Xx2".parse::<Formula>().is_err());
+        assert!("[13C".parse::<Formula>().is_err());
+        assert!("2C".parse::<Formula>().is_err());
+    }
+
+    #[test]
+    fn test_masses() {
+        let formula: Formula = "C8H10N4O2".parse().unwrap();
+        assert!((formula.molecular_weight() - 194.194).abs() < 0.01);
+        assert!((formula.monoisotopic_mass() - 194.080376).abs() < 0.0001);
+
+        let formula: Formula = "CH[2H]3O".parse().unwrap();
+        assert!((formula.molecular_weight() - 35.060).abs() < 0.01);
+        assert!((formula.monoisotopic_mass() - 35.045045).abs() < 0.0001);
+    }
+}
@@ -0,0 +1,343 @@
+//! Kekulé structure assignment and Hückel aromaticity perception.
+//!
+//! The ring builders describe mancude rings by their sigma bonds alone, leaving
+//! each ring atom one bond short of its standard bonding number. Kekulization
+//! pairs those atoms up with pi bonds.
+
+use super::Graph;
+use crate::Element;
+
+/// The atoms, bonds and ring sets that satisfy Hückel's 4n + 2 rule.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct Aromaticity {
+    /// Each aromatic ring or fused ring set, as sorted atom indices.
+    pub rings: Vec<Vec<usize>>,
+    /// Sorted atom indices.
+    pub atoms: Vec<usize>,
+    /// Sorted `(a, b)` pairs with `a < b`.
+    pub bonds: Vec<(usize, usize)>,
+}
+
+impl Aromaticity {
+    pub fn is_aromatic_atom(&self, i: usize) -> bool {
+        self.atoms.binary_search(&i).is_ok()
+    }
+
+    pub fn is_aromatic_bond(&self, a: usize, b: usize) -> bool {
+        self.bonds.binary_search(&(a.min(b), a.max(b))).is_ok()
+    }
+}
+
+impl Graph {
+    /// How many more bonds atom `i` needs to reach its standard bonding
+    /// number, counting each free valence as a bond.
+    pub fn valence_deficit(&self, i: usize) -> usize {
+        let sigma = self.neighbors(i).count();
+        let pi = self
+            .pi_bonds
+            .iter()
+            .filter(|&&(a, b)| a == i || b == i)
+            .count();
+        let free = self.free_valences.iter().filter(|&&j| j == i).count();
+        self.bonding_number(i).saturating_sub(sigma + pi + free)
+    }
+
+    /// The standard bonding number of atom `i`, adjusted for its charge: a
+    /// charged atom bonds like the neutral atom with the same number of
+    /// valence electrons, as in ammonium or alkoxide ions.
+    pub fn bonding_number(&self, i: usize) -> usize {
+        let element = self.atoms[i];
+        let standard = element.standard_bonding_number() as i32;
+        let charge = self.charge(i) as i32;
+        let bonding_number = if element.group() >= 15 {
+            standard + charge
+        } else if matches!(element.group(), 1 | 14) {
+            standard - charge.abs()
+        } else {
+            standard - charge
+        };
+        bonding_number.max(0) as usize
+    }
+
+    /// Adds pi bonds between neighbouring atoms that are short of their
+    /// standard bonding number, so that every such atom is satisfied. Leaves
+    /// the graph untouched if that is not possible.
+    pub fn kekulize(&mut self) -> Result<(), &'static str> {
+        let mut graph = self.clone();
+
+        // Each pass places at most one pi bond per atom, so triple bonds take
+        // two passes
+        loop {
+            let candidates = (0..graph.atoms.len())
+                .filter(|&i| graph.atoms[i] != Element::Hydrogen && graph.valence_deficit(i) > 0)
+                .collect::<Vec<_>>();
+            if candidates.is_empty() {
+                break;
+            }
+
+            let mut partners = vec![None; graph.atoms.len()];
+            if !perfect_matching(&graph, &candidates, &mut partners) {
+                return Err("No Kekulé structure exists");
+            }
+            for &i in &candidates {
+                let j = partners[i].unwrap();
+                if i < j {
+                    graph.pi_bonds.push((i, j));
+                }
+            }
+        }
+
+        *self = graph;
+        Ok(())
+    }
+
+    /// Finds the rings, and sets of fused rings, whose pi electrons satisfy
+    /// Hückel's rule. Mancude rings without pi bonds are kekulized first.
+    pub fn aromaticity(&self) -> Aromaticity {
+        let mut graph = self.clone();
+        // Rings that cannot be kekulized keep their missing bonds, and so are
+        // found to be non-aromatic below
+        let _ = graph.kekulize();
+
+        let rings = graph.rings();
+        let mut ring_sets = rings
+            .rings
+            .iter()
+            .map(|ring| vec![ring.clone()])
+            .collect::<Vec<_>>();
+        for system in rings.ring_systems() {
+            // Pairs of fused rings, as in azulene, then the whole system
+            for (k, &r) in system.iter().enumerate() {
+                for &s in &system[k + 1..] {
+                    let a = &rings.rings[r];
+                    let b = &rings.rings[s];
+                    let fused = (0..a.len()).any(|p| {
+                        let (c, d) = (a[p], a[(p + 1) % a.len()]);
+                        rings.is_ring_bond(c, d) && b.contains(&c) && b.contains(&d)
+                    });
+                    if fused {
+                        ring_sets.push(vec![a.clone(), b.clone()]);
+                    }
+                }
+            }
+            if system.len() > 2 {
+                ring_sets.push(system.iter().map(|&r| rings.rings[r].clone()).collect());
+            }
+        }
+
+        let mut aromaticity = Aromaticity::default();
+        for ring_set in ring_sets {
+            let mut atoms = ring_set.iter().flatten().copied().collect::<Vec<_>>();
+            atoms.sort();
+            atoms.dedup();
+            let bonds = ring_set
+                .iter()
+                .flat_map(|ring| {
+                    (0..ring.len()).map(|k| {
+                        let (a, b) = (ring[k], ring[(k + 1) % ring.len()]);
+                        (a.min(b), a.max(b))
+                    })
+                })
+                .collect::<Vec<_>>();
+
+            if !graph.is_huckel_aromatic(&atoms) {
+                continue;
+            }
+            aromaticity.atoms.extend(&atoms);
+            aromaticity.bonds.extend(bonds);
+            aromaticity.rings.push(atoms);
+        }
+        aromaticity.rings.sort();
+        aromaticity.rings.dedup();
+        aromaticity.atoms.sort();
+        aromaticity.atoms.dedup();
+        aromaticity.bonds.sort();
+        aromaticity.bonds.dedup();
+        aromaticity
+    }
+
+    fn is_huckel_aromatic(&self, atoms: &[usize]) -> bool {
+        let mut electrons = 0;
+        for &i in atoms {
+            match self.pi_electrons(i, atoms) {
+                Some(n) => electrons += n,
+                None => return false,
+            }
+        }
+        electrons % 4 == 2
+    }
+
+    /// The number of electrons atom `i` contributes to the pi system of the
+    /// ring atoms `ring`, or `None` if it cannot take part in one.
+    fn pi_electrons(&self, i: usize, ring: &[usize]) -> Option<usize> {
+        let pi_partners = self
+            .pi_bonds
+            .iter()
+            .filter_map(|&(a, b)| {
+                if a == i {
+                    Some(b)
+                } else if b == i {
+                    Some(a)
+                } else {
+                    None
+                }
+            })
+            .collect::<Vec<_>>();
+
+        match pi_partners.as_slice() {
+            &[j] if ring.contains(&j) => Some(1),
+            // An exocyclic double bond to a heteroatom, as in a carbonyl group,
+            // takes the electrons out of the ring
+            &[j] if self.atoms[j] != Element::Carbon => Some(0),
+            [_, ..] => None,
+            [] => {
+                if self.valence_deficit(i) > 0 {
+                    return None;
+                }
+                // A lone pair in a p orbital, or an empty p orbital for boron
+                // and for a carbocation, as in the tropylium ion. A carbanion
+                // has a lone pair, as in the cyclopentadienide ion.
+                match (self.atoms[i].group(), self.charge(i)) {
+                    (13, _) | (14, 1) => Some(0),
+                    (15 | 16, _) | (14, -1) => Some(2),
+                    _ => None,
+                }
+            }
+        }
+    }
+}
+
+/// Backtracking search for a perfect matching of `candidates` along bonds,
+/// always extending the atom with the fewest options first.
+pub(crate) fn perfect_matching(
+    graph: &Graph,
+    candidates: &[usize],
+    partners: &mut [Option<usize>],
+) -> bool {
+    let is_candidate = |i: usize| candidates.contains(&i);
+    let options = |i: usize, partners: &[Option<usize>]| {
+        let mut options = graph
+            .neighbors(i)
+            .filter(|&j| is_candidate(j) && partners[j].is_none())
+            .collect::<Vec<_>>();
+        options.sort();
+        options.dedup();
+        options
+    };
+
+    let next = candidates
+        .iter()
+        .copied()
+        .filter(|&i| partners[i].is_none())
+        .min_by_key(|&i| options(i, partners).len());
+    let Some(i) = next else {
+        return true;
+    };
+
+    for j in options(i, partners) {
+        partners[i] = Some(j);
+        partners[j] = Some(i);
+        if perfect_matching(graph, candidates, partners) {
+            return true;
+        }
+        partners[i] = None;
+        partners[j] = None;
+    }
+    false
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        chapters::p_2_hydrides::{
+            p_22_monocyclic_hydrides::p_22_1_monocyclic_hydocarbons::MonocyclicHydrocarbon,
+            p_25_fused_ring_systems::p_25_2_heterocyclic_ring_components::purine, Hydride,
+        },
+        graph::Graph,
+        parser::parse,
+        test::{CAFFEINE, ISOBUTANE, THYMINE},
+    };
+
+    #[test]
+    fn test_kekulize_benzene() {
+        let mut graph = Hydride::from(MonocyclicHydrocarbon::Benzene).to_graph();
+        graph.kekulize().unwrap();
+
+        assert_eq!(graph.pi_bonds.len(), 3);
+        for i in 0..6 {
+            assert_eq!(graph.valence_deficit(i), 0);
+        }
+    }
+
+    #[test]
+    fn test_kekulize_purine() {
+        for isomer in [1, 3, 7, 9] {
+            let mut graph = purine(isomer);
+            graph.kekulize().unwrap();
+            assert_eq!(graph.pi_bonds.len(), 4, "{isomer}H-purine");
+        }
+    }
+
+    #[test]
+    fn test_kekulize_failure() {
+        // Without the indicated hydrogen, purine has an odd number of atoms to
+        // pair up
+        let mut graph = purine(1);
+        graph.bonds.retain(|&(a, b)| (a, b) != (0, 12));
+        graph.atoms.pop();
+        let before = graph.clone();
+
+        assert!(graph.kekulize().is_err());
+        assert_eq!(graph.pi_bonds, before.pi_bonds);
+    }
+
+    #[test]
+    fn test_aromaticity() {
+        let graph = Hydride::from(MonocyclicHydrocarbon::Benzene).to_graph();
+        let aromaticity = graph.aromaticity();
+        assert_eq!(aromaticity.atoms, (0..6).collect::<Vec<_>>());
+        assert!(aromaticity.is_aromatic_bond(5, 0));
+        assert!(!aromaticity.is_aromatic_bond(0, 6));
+
+        // Both rings of purine, including the pyrrole-type nitrogen
+        let aromaticity = purine(9).aromaticity();
+        assert_eq!(aromaticity.atoms, (0..9).collect::<Vec<_>>());
+
+        let graph = Graph::from(&*parse(CAFFEINE));
+        assert_eq!(graph.aromaticity().atoms, (0..9).collect::<Vec<_>>());
+
+        let graph = Graph::from(&*parse(THYMINE));
+        assert_eq!(graph.aromaticity().rings.len(), 1);
+
+        let graph = Graph::from(&*parse(ISOBUTANE));
+        assert_eq!(graph.aromaticity(), Default::default());
+    }
+
+    #[test]
+    fn test_charged_rings() {
+        // Six pi electrons over seven atoms, with an empty p orbital on the
+        // carbocation
+        let tropylium = Graph::from_smiles("[CH+]1C=CC=CC=C1").unwrap();
+        assert_eq!(tropylium.aromaticity().atoms, (0..7).collect::<Vec<_>>());
+        assert_eq!(tropylium.smiles(), "[cH+]1cccccc1");
+        let round_trip = Graph::from_smiles("[cH+]1cccccc1").unwrap();
+        assert_eq!(round_trip.canonical_key(), tropylium.canonical_key());
+
+        // Six pi electrons over five atoms, with a lone pair on the carbanion
+        let cyclopentadienide = Graph::from_smiles("[CH-]1C=CC=C1").unwrap();
+        assert_eq!(
+            cyclopentadienide.aromaticity().atoms,
+            (0..5).collect::<Vec<_>>()
+        );
+        assert_eq!(cyclopentadienide.smiles(), "[cH-]1cccc1");
+        let round_trip = Graph::from_smiles("[cH-]1cccc1").unwrap();
+        assert_eq!(
+            round_trip.canonical_key(),
+            cyclopentadienide.canonical_key()
+        );
+
+        // Four pi electrons, antiaromatic
+        let cyclopentadienylium = Graph::from_smiles("[CH+]1C=CC=C1").unwrap();
+        assert_eq!(cyclopentadienylium.aromaticity(), Default::default());
+    }
+}
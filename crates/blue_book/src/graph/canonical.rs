@@ -0,0 +1,669 @@
+//! Canonical atom ranking, so that a molecule gets the same atom order and key
+//! whichever name or atom order it was built from.
+//!
+//! Atoms are first ranked by their invariants, and the ranks refined by those
+//! of their neighbours until they stop changing, as in Morgan's algorithm.
+//! Atoms that are still tied are then told apart in every possible order,
+//! keeping whichever labelling gives the smallest list of bonds and
+//! stereocentres. Labellings that turn out to be the same give an automorphism
+//! of the molecule, which is used to skip the branches symmetric to one
+//! already searched, as in McKay's canonical labelling algorithm.
+
+use std::fmt::Write;
+
+use super::{
+    stereo::{is_even_permutation, Chirality, Stereo},
+    Graph,
+};
+use crate::Element;
+
+/// A bond order in the canonical key, with aromatic bonds given their own
+/// order so that every Kekulé structure gets the same key.
+const AROMATIC: u8 = 4;
+
+/// A bond as `(a, b, order)`.
+type Bond = (usize, usize, u8);
+
+/// The configuration of a stereocentre or stereogenic double bond, by the
+/// ranks of its atoms.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+enum Parity {
+    /// A centre, and whether its neighbours in rank order are clockwise.
+    Centre(usize, bool),
+    /// The ends of a double bond, and whether their lowest ranked neighbours
+    /// are on the same side.
+    DoubleBond(usize, usize, bool),
+}
+
+/// A stereocentre or stereogenic double bond among the atoms being ranked,
+/// with `None` for folded hydrogens.
+enum StereoUnit {
+    Centre {
+        centre: usize,
+        neighbors: [Option<usize>; 4],
+        clockwise: bool,
+    },
+    DoubleBond {
+        ends: [usize; 2],
+        /// The neighbour on each end that `together` refers to.
+        given: [Option<usize>; 2],
+        /// The other neighbours of each end.
+        substituents: [Vec<Option<usize>>; 2],
+        together: bool,
+    },
+}
+
+struct Labelling {
+    /// The heavy atoms, and hydrogens that are not folded into a hydrogen
+    /// count, in canonical order.
+    heavy_atoms: Vec<usize>,
+    /// Sorted `(a, b, order)` bonds between ranks in `heavy_atoms`, with
+    /// `a < b`.
+    bonds: Vec<Bond>,
+    parities: Vec<Parity>,
+    hydrogen_counts: Vec<usize>,
+}
+
+impl Graph {
+    /// The rank of each atom in the canonical order. Terminal hydrogens come
+    /// after every other atom, ordered by the atom they are bonded to.
+    pub fn canonical_ranks(&self) -> Vec<usize> {
+        let mut ranks = vec![0; self.atoms.len()];
+        for (rank, i) in self.canonical_order().into_iter().enumerate() {
+            ranks[i] = rank;
+        }
+        ranks
+    }
+
+    /// The atom indices, in canonical order.
+    pub fn canonical_order(&self) -> Vec<usize> {
+        let labelling = self.canonical_labelling();
+
+        let mut order = labelling.heavy_atoms.clone();
+        for &i in &labelling.heavy_atoms {
+            order.extend(self.neighbors(i).filter(|&j| self.is_folded_hydrogen(j)));
+        }
+        order
+    }
+
+    /// A string that identifies the molecule, listing each atom with its mass
+    /// number, hydrogens, charge and free valences in canonical order, then
+    /// the bonds between them and the configuration of each stereocentre and
+    /// stereogenic double bond. Two graphs have the same key exactly when
+    /// they are the same molecule, up to the choice of Kekulé structure for
+    /// aromatic rings.
+    pub fn canonical_key(&self) -> String {
+        let labelling = self.canonical_labelling();
+
+        let mut key = String::new();
+        for (rank, &i) in labelling.heavy_atoms.iter().enumerate() {
+            if rank > 0 {
+                key.push('.');
+            }
+            if let Some(mass_number) = self.mass_number(i) {
+                write!(key, "{mass_number}").unwrap();
+            }
+            key.push_str(self.atoms[i].symbol());
+            match labelling.hydrogen_counts[rank] {
+                0 => {}
+                1 => key.push('H'),
+                n => write!(key, "H{n}").unwrap(),
+            }
+            match self.charge(i) {
+                0 => {}
+                charge => write!(key, "{charge:+}").unwrap(),
+            }
+            for _ in self.free_valences.iter().filter(|&&j| j == i) {
+                key.push('*');
+            }
+        }
+
+        key.push('/');
+        for (k, &(a, b, order)) in labelling.bonds.iter().enumerate() {
+            if k > 0 {
+                key.push(',');
+            }
+            let symbol = match order {
+                1 => '-',
+                2 => '=',
+                3 => '#',
+                AROMATIC => ':',
+                _ => '~',
+            };
+            write!(key, "{a}{symbol}{b}").unwrap();
+        }
+
+        if !labelling.parities.is_empty() {
+            key.push('/');
+        }
+        for (k, &parity) in labelling.parities.iter().enumerate() {
+            if k > 0 {
+                key.push(',');
+            }
+            match parity {
+                Parity::Centre(a, clockwise) => {
+                    write!(key, "{a}{}", if clockwise { "@@" } else { "@" }).unwrap()
+                }
+                Parity::DoubleBond(a, b, together) => {
+                    write!(key, "{a}={b}{}", if together { 'c' } else { 't' }).unwrap()
+                }
+            }
+        }
+
+        key
+    }
+
+    /// A 64-bit FNV-1a hash of [`Graph::canonical_key`], which is stable
+    /// across runs and platforms.
+    pub fn canonical_hash(&self) -> u64 {
+        const OFFSET_BASIS: u64 = 0xcbf29ce484222325;
+        const PRIME: u64 = 0x100000001b3;
+
+        self.canonical_key()
+            .bytes()
+            .fold(OFFSET_BASIS, |hash, byte| {
+                (hash ^ byte as u64).wrapping_mul(PRIME)
+            })
+    }
+
+    /// A hydrogen atom bonded to a single atom other than hydrogen, which is
+    /// folded into that atom's hydrogen count.
+    pub fn is_terminal_hydrogen(&self, i: usize) -> bool {
+        if self.atoms[i] != Element::Hydrogen || self.free_valences.contains(&i) {
+            return false;
+        }
+        let mut neighbors = self.neighbors(i);
+        match (neighbors.next(), neighbors.next()) {
+            (Some(j), None) => self.atoms[j] != Element::Hydrogen,
+            _ => false,
+        }
+    }
+
+    /// A terminal hydrogen that is not a specific isotope, which the
+    /// canonical key and SMILES count rather than write out.
+    pub fn is_folded_hydrogen(&self, i: usize) -> bool {
+        self.is_terminal_hydrogen(i) && self.mass_number(i).is_none()
+    }
+
+    fn canonical_labelling(&self) -> Labelling {
+        let mut graph = self.clone();
+        // Graphs without a Kekulé structure keep their single bonds
+        let _ = graph.kekulize();
+        let aromaticity = graph.aromaticity();
+
+        let heavy_atoms = (0..self.atoms.len())
+            .filter(|&i| !self.is_folded_hydrogen(i))
+            .collect::<Vec<_>>();
+        let index = |i: usize| heavy_atoms.binary_search(&i).ok();
+
+        let mut neighbors = vec![Vec::new(); heavy_atoms.len()];
+        let mut bonds = Vec::new();
+        for &(a, b) in &graph.bonds {
+            let (Some(x), Some(y)) = (index(a), index(b)) else {
+                continue;
+            };
+            let order = if aromaticity.is_aromatic_bond(a, b) {
+                AROMATIC
+            } else {
+                graph.bond_order(a, b)
+            };
+            neighbors[x].push((y, order));
+            neighbors[y].push((x, order));
+            bonds.push((x, y, order));
+        }
+
+        let stereo = self
+            .stereo
+            .iter()
+            .filter_map(|&stereo| match stereo {
+                Stereo::Tetrahedral {
+                    centre,
+                    neighbors,
+                    chirality,
+                } => Some(StereoUnit::Centre {
+                    centre: index(centre)?,
+                    neighbors: neighbors.map(index),
+                    clockwise: chirality == Chirality::Clockwise,
+                }),
+                Stereo::DoubleBond {
+                    atoms: [a, b, c, d],
+                    together,
+                } => {
+                    let substituents = |end: usize, other: usize| {
+                        self.neighbors(end)
+                            .filter(|&j| j != other)
+                            .map(index)
+                            .collect::<Vec<_>>()
+                    };
+                    Some(StereoUnit::DoubleBond {
+                        ends: [index(b)?, index(c)?],
+                        given: [index(a), index(d)],
+                        substituents: [substituents(b, c), substituents(c, b)],
+                        together,
+                    })
+                }
+            })
+            .collect::<Vec<_>>();
+        let mut is_stereogenic = vec![false; heavy_atoms.len()];
+        for unit in &stereo {
+            match unit {
+                &StereoUnit::Centre { centre, .. } => is_stereogenic[centre] = true,
+                StereoUnit::DoubleBond { ends, .. } => {
+                    for &x in ends {
+                        is_stereogenic[x] = true;
+                    }
+                }
+            }
+        }
+
+        let hydrogen_counts = heavy_atoms
+            .iter()
+            .map(|&i| {
+                self.neighbors(i)
+                    .filter(|&j| self.is_folded_hydrogen(j))
+                    .count()
+            })
+            .collect::<Vec<_>>();
+        let invariants = heavy_atoms
+            .iter()
+            .enumerate()
+            .map(|(x, &i)| {
+                let free_valences = self.free_valences.iter().filter(|&&j| j == i).count();
+                (
+                    self.atoms[i],
+                    self.mass_number(i),
+                    neighbors[x].len(),
+                    hydrogen_counts[x],
+                    self.charge(i),
+                    free_valences,
+                    is_stereogenic[x],
+                )
+            })
+            .collect::<Vec<_>>();
+
+        let mut search = Search {
+            neighbors: &neighbors,
+            bonds: &bonds,
+            stereo: &stereo,
+            path: Vec::new(),
+            first: None,
+            best: None,
+            automorphisms: Vec::new(),
+        };
+        search.search(dense_ranks(&invariants));
+        let Leaf {
+            ranks,
+            bonds,
+            parities,
+            ..
+        } = search.best.unwrap_or_default();
+
+        let mut order = (0..heavy_atoms.len()).collect::<Vec<_>>();
+        order.sort_by_key(|&x| ranks[x]);
+        Labelling {
+            heavy_atoms: order.iter().map(|&x| heavy_atoms[x]).collect(),
+            bonds,
+            parities,
+            hydrogen_counts: order.iter().map(|&x| hydrogen_counts[x]).collect(),
+        }
+    }
+}
+
+/// A complete labelling reached by the search.
+#[derive(Debug, Default, Clone)]
+struct Leaf {
+    /// The atoms picked out to break ties on the way here.
+    path: Vec<usize>,
+    ranks: Vec<usize>,
+    bonds: Vec<Bond>,
+    parities: Vec<Parity>,
+}
+
+impl Leaf {
+    fn is_same_molecule(&self, other: &Leaf) -> bool {
+        (&self.bonds, &self.parities) == (&other.bonds, &other.parities)
+    }
+
+    fn is_better(&self, other: &Leaf) -> bool {
+        (&self.bonds, &self.parities) < (&other.bonds, &other.parities)
+    }
+
+    /// The automorphism that takes each atom of `other` to the atom with the
+    /// same rank here.
+    fn automorphism_from(&self, other: &Leaf) -> Vec<usize> {
+        let mut atom_at = vec![0; self.ranks.len()];
+        for (x, &rank) in self.ranks.iter().enumerate() {
+            atom_at[rank] = x;
+        }
+        other.ranks.iter().map(|&rank| atom_at[rank]).collect()
+    }
+}
+
+struct Search<'a> {
+    neighbors: &'a [Vec<(usize, u8)>],
+    bonds: &'a [Bond],
+    stereo: &'a [StereoUnit],
+    path: Vec<usize>,
+    first: Option<Leaf>,
+    best: Option<Leaf>,
+    automorphisms: Vec<Vec<usize>>,
+}
+
+impl Search<'_> {
+    /// Refines `ranks`, then breaks the first tie in each possible way,
+    /// keeping the smallest labelling in `best`. Returns the depth to go back
+    /// to when the rest of this branch is symmetric to one already searched.
+    fn search(&mut self, ranks: Vec<usize>) -> Option<usize> {
+        let ranks = refine(self.neighbors, ranks);
+
+        let mut counts = vec![0; ranks.len()];
+        for &rank in &ranks {
+            counts[rank] += 1;
+        }
+        let Some(tied) = counts.iter().position(|&count| count > 1) else {
+            return self.leaf(ranks);
+        };
+
+        let depth = self.path.len();
+        let mut tried = Vec::new();
+        for chosen in (0..ranks.len()).filter(|&x| ranks[x] == tied) {
+            if self.is_symmetric(&tried, chosen) {
+                continue;
+            }
+
+            // Put the chosen atom ahead of the others it is tied with
+            let split = ranks
+                .iter()
+                .enumerate()
+                .map(|(x, &rank)| 2 * rank + usize::from(rank == tied && x != chosen))
+                .collect::<Vec<_>>();
+            self.path.push(chosen);
+            let target = self.search(dense_ranks(&split));
+            self.path.pop();
+            tried.push(chosen);
+
+            if let Some(target) = target.filter(|&target| target < depth) {
+                return Some(target);
+            }
+        }
+        None
+    }
+
+    fn leaf(&mut self, ranks: Vec<usize>) -> Option<usize> {
+        let mut bonds = self
+            .bonds
+            .iter()
+            .map(|&(a, b, order)| {
+                let (a, b) = (ranks[a], ranks[b]);
+                (a.min(b), a.max(b), order)
+            })
+            .collect::<Vec<_>>();
+        bonds.sort();
+        let parities = parities(self.stereo, &ranks);
+        let leaf = Leaf {
+            path: self.path.clone(),
+            ranks,
+            bonds,
+            parities,
+        };
+
+        let (Some(first), Some(best)) = (&self.first, &self.best) else {
+            self.first = Some(leaf.clone());
+            self.best = Some(leaf);
+            return None;
+        };
+        // The same labelling again means the rest of this branch mirrors the
+        // one that led to the earlier leaf, from where the two paths part
+        for other in [first, best] {
+            if leaf.is_same_molecule(other) {
+                let automorphism = leaf.automorphism_from(other);
+                let common = (leaf.path.iter())
+                    .zip(&other.path)
+                    .take_while(|(a, b)| a == b)
+                    .count();
+                self.automorphisms.push(automorphism);
+                return Some(common);
+            }
+        }
+        if leaf.is_better(best) {
+            self.best = Some(leaf);
+        }
+        None
+    }
+
+    /// Whether an automorphism found so far, fixing the atoms picked out on
+    /// the current path, takes one of the `tried` atoms to `chosen`.
+    fn is_symmetric(&self, tried: &[usize], chosen: usize) -> bool {
+        if tried.is_empty() {
+            return false;
+        }
+        let mut orbits = (0..self.neighbors.len()).collect::<Vec<_>>();
+        fn root(orbits: &mut [usize], mut x: usize) -> usize {
+            while orbits[x] != x {
+                orbits[x] = orbits[orbits[x]];
+                x = orbits[x];
+            }
+            x
+        }
+        for automorphism in &self.automorphisms {
+            if self.path.iter().any(|&x| automorphism[x] != x) {
+                continue;
+            }
+            for (x, &y) in automorphism.iter().enumerate() {
+                let (a, b) = (root(&mut orbits, x), root(&mut orbits, y));
+                orbits[a] = b;
+            }
+        }
+        let chosen = root(&mut orbits, chosen);
+        tried.iter().any(|&x| root(&mut orbits, x) == chosen)
+    }
+}
+
+/// The configuration of each stereocentre and stereogenic double bond under
+/// `ranks`, sorted.
+fn parities(stereo: &[StereoUnit], ranks: &[usize]) -> Vec<Parity> {
+    // Folded hydrogens come after every ranked atom
+    let rank = |x: Option<usize>| x.map_or(usize::MAX, |x| ranks[x]);
+    let mut parities = stereo
+        .iter()
+        .map(|unit| match unit {
+            &StereoUnit::Centre {
+                centre,
+                neighbors,
+                clockwise,
+            } => {
+                let given = neighbors.map(rank);
+                let mut sorted = given;
+                sorted.sort();
+                let even = is_even_permutation(&given, &sorted);
+                Parity::Centre(ranks[centre], clockwise == even)
+            }
+            StereoUnit::DoubleBond {
+                ends,
+                given,
+                substituents,
+                together,
+            } => {
+                let mut together = *together;
+                for end in 0..2 {
+                    let lowest = substituents[end].iter().copied().min_by_key(|&x| rank(x));
+                    if lowest.is_some_and(|lowest| rank(lowest) != rank(given[end])) {
+                        together = !together;
+                    }
+                }
+                let (a, b) = (ranks[ends[0]], ranks[ends[1]]);
+                Parity::DoubleBond(a.min(b), a.max(b), together)
+            }
+        })
+        .collect::<Vec<_>>();
+    parities.sort();
+    parities
+}
+
+/// Repeatedly ranks atoms by their own rank and their neighbours' ranks, until
+/// no more ties are broken.
+fn refine(neighbors: &[Vec<(usize, u8)>], mut ranks: Vec<usize>) -> Vec<usize> {
+    let mut classes = class_count(&ranks);
+    loop {
+        let signatures = (0..ranks.len())
+            .map(|x| {
+                let mut neighborhood = neighbors[x]
+                    .iter()
+                    .map(|&(y, order)| (ranks[y], order))
+                    .collect::<Vec<_>>();
+                neighborhood.sort();
+                (ranks[x], neighborhood)
+            })
+            .collect::<Vec<_>>();
+        ranks = dense_ranks(&signatures);
+
+        let new_classes = class_count(&ranks);
+        if new_classes == classes {
+            return ranks;
+        }
+        classes = new_classes;
+    }
+}
+
+fn class_count(ranks: &[usize]) -> usize {
+    ranks.iter().max().map_or(0, |&max| max + 1)
+}
+
+/// Numbers the distinct values from zero upwards, in ascending order.
+fn dense_ranks<T: Ord>(values: &[T]) -> Vec<usize> {
+    let mut sorted = values.iter().collect::<Vec<_>>();
+    sorted.sort();
+    sorted.dedup();
+    values
+        .iter()
+        .map(|value| sorted.binary_search(&value).unwrap())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        chapters::{
+            p_2_hydrides::{
+                p_22_monocyclic_hydrides::p_22_1_monocyclic_hydocarbons::MonocyclicHydrocarbon,
+                Hydride,
+            },
+            p_3_substituent_groups::CharacteristicGroup,
+        },
+        graph::Graph,
+        parser::parse,
+        test::{CAFFEINE, DOPAMINE, ISOBUTANE, ISOPROPANOL},
+    };
+
+    /// Moves atom `i` to index `permutation[i]`.
+    fn permute(graph: &Graph, permutation: &[usize]) -> Graph {
+        let mut permuted = graph.clone();
+        for (i, &element) in graph.atoms.iter().enumerate() {
+            permuted.atoms[permutation[i]] = element;
+        }
+        let relabel = |&(a, b): &(usize, usize)| (permutation[b], permutation[a]);
+        permuted.bonds = graph.bonds.iter().rev().map(relabel).collect();
+        permuted.pi_bonds = graph.pi_bonds.iter().map(relabel).collect();
+        permuted.positions = Vec::new();
+        permuted.free_valences = graph
+            .free_valences
+            .iter()
+            .map(|&i| permutation[i])
+            .collect();
+        permuted
+    }
+
+    #[test]
+    fn test_same_compound() {
+        let a = Graph::from(&*parse(ISOPROPANOL));
+        let b = Graph::from(&*parse("2-propanol"));
+        assert_eq!(a.canonical_key(), "CH3.CH3.CH.OH/0-2,1-2,2-3");
+        assert_eq!(a.canonical_key(), b.canonical_key());
+        assert_eq!(a.canonical_hash(), b.canonical_hash());
+
+        let a = Graph::from(&*parse("Propan-1-ol"));
+        assert_ne!(a.canonical_key(), b.canonical_key());
+
+        let a = Graph::from(&*parse(ISOBUTANE));
+        let b = Graph::from(&*parse("Butane"));
+        assert_ne!(a.canonical_hash(), b.canonical_hash());
+    }
+
+    #[test]
+    fn test_atom_order() {
+        for name in [ISOPROPANOL, DOPAMINE, CAFFEINE] {
+            let graph = Graph::from(&*parse(name));
+            let n = graph.atoms.len();
+            for step in [1, n - 1, 7] {
+                let permutation = (0..n).map(|i| (i * step + 3) % n).collect::<Vec<_>>();
+                let permuted = permute(&graph, &permutation);
+                assert_eq!(graph.canonical_key(), permuted.canonical_key(), "{name}");
+            }
+        }
+    }
+
+    #[test]
+    fn test_kekule_structures() {
+        let mut a = Hydride::from(MonocyclicHydrocarbon::Benzene).to_graph();
+        let mut b = a.clone();
+        a.pi_bonds = vec![(0, 1), (2, 3), (4, 5)];
+        b.pi_bonds = vec![(1, 2), (3, 4), (5, 0)];
+        assert_eq!(a.canonical_key(), b.canonical_key());
+    }
+
+    #[test]
+    fn test_symmetric_molecules() {
+        // Nine tert-butyl groups on a chain, with 6⁹ · 2 ways to order their
+        // methyl groups that all give the same labelling
+        let smiles = format!("C{}C", "C(C(C)(C)C)".repeat(9));
+        let graph = Graph::from_smiles(&smiles).unwrap();
+        let n = graph.atoms.len();
+        let permutation = (0..n).map(|i| (i * 7 + 3) % n).collect::<Vec<_>>();
+        assert_eq!(
+            graph.canonical_key(),
+            permute(&graph, &permutation).canonical_key()
+        );
+
+        let graph = Graph::from_smiles("C1CCCCCCCCCCCCCCCCCCC1").unwrap();
+        assert_eq!(graph.canonical_key().matches('-').count(), 20);
+    }
+
+    #[test]
+    fn test_stereoisomers() {
+        let key = |smiles: &str| Graph::from_smiles(smiles).unwrap().canonical_key();
+
+        // L-alanine written from either end, and D-alanine
+        assert_eq!(key("C[C@H](N)C(=O)O"), key("N[C@@H](C)C(=O)O"));
+        assert_eq!(key("C[C@H](N)C(=O)O"), key("OC(=O)[C@@H](N)C"));
+        assert_ne!(key("C[C@H](N)C(=O)O"), key("C[C@@H](N)C(=O)O"));
+        assert_ne!(key("C[C@H](N)C(=O)O"), key("CC(N)C(=O)O"));
+
+        // trans- and cis-but-2-ene
+        assert_eq!(key("C/C=C/C"), key("C\\C=C\\C"));
+        assert_ne!(key("C/C=C/C"), key("C/C=C\\C"));
+        assert_eq!(key("C/C=C\\C"), key("C\\C=C/C"));
+    }
+
+    #[test]
+    fn test_isotopologues() {
+        let key = |name: &str| Graph::from(&*parse(name)).canonical_key();
+
+        assert_ne!(key("(²H₃)Methanol"), key("Methanol"));
+        assert_ne!(key("(²H₃)Methanol"), key("(²H₄)Methanol"));
+        assert_ne!(key("(1-¹³C)Ethanol"), key("(2-¹³C)Ethanol"));
+        assert_ne!(key("(1-¹³C)Ethanol"), key("Ethanol"));
+        assert_eq!(key("(1-¹³C)Ethanol"), key("(1-¹³C)Ethan-1-ol"));
+    }
+
+    #[test]
+    fn test_canonical_order() {
+        let graph = CharacteristicGroup::Hydroxy.to_graph();
+        assert_eq!(graph.canonical_order().len(), graph.atoms.len());
+
+        let graph = Graph::from(&*parse(DOPAMINE));
+        let ranks = graph.canonical_ranks();
+        let mut sorted = ranks.clone();
+        sorted.sort();
+        assert_eq!(sorted, (0..graph.atoms.len()).collect::<Vec<_>>());
+    }
+}
@@ -2,35 +2,100 @@ use std::fmt;
 
 use petgraph::graph::UnGraph;
 
-use crate::{parser::AST, Element, Locant};
+use crate::{
+    chapters::{
+        p_1_general::{
+            p_13_operations::p_13_2_subtractive_operations::subtract,
+            p_14_general_rules::p_14_7_indicated_hydrogen::{add_hydrogen, indicated_hydrogen},
+        },
+        p_3_substituent_groups::p_31_1_4_hydro_prefixes::hydro,
+        p_6_specific_classes::p_65_acids_and_derivatives::p_65_1_7_acyl_groups::AcylSuffix,
+        p_8_isotopically_modified_compounds::label,
+    },
+    parser::AST,
+    Element, Locant, LocantError,
+};
+
+use self::stereo::Stereo;
+
+pub mod aromaticity;
+pub mod canonical;
+pub mod rings;
+pub mod stereo;
 
 #[derive(Debug, Default, Clone)]
 pub struct Graph {
     pub atoms: Vec<Element>,
     pub bonds: Vec<(usize, usize)>,
+    /// Extra bonds layered on top of `bonds`: one entry per unit of bond order
+    /// above one, so a double bond has one entry and a triple bond has two.
+    pub pi_bonds: Vec<(usize, usize)>,
+    /// The formal charge of each charged atom.
+    pub charges: Vec<(usize, i8)>,
+    /// The mass number of each atom that is a specific isotope.
+    pub isotopes: Vec<(usize, u16)>,
+    pub stereo: Vec<Stereo>,
     pub positions: Vec<(Locant, usize)>,
     pub free_valences: Vec<usize>,
 }
 
 impl From<&AST> for Graph {
+    /// # Panics
+    ///
+    /// If a locant of the name has no atom in the structure; see
+    /// [`Graph::from_ast`].
     fn from(value: &AST) -> Self {
-        match value {
+        Graph::from_ast(value).unwrap_or_else(|err| panic!("{err}"))
+    }
+}
+
+impl Graph {
+    /// Builds the structure of a parsed name, failing where a locant has no
+    /// atom, or no bond, of its own in it.
+    pub fn from_ast(value: &AST) -> Result<Graph, LocantError> {
+        Ok(match value {
             AST::Hydride(hydride) => hydride.to_graph(),
-            AST::Group(base) => {
-                let base = Graph::from(&**base);
-                free_valence(base)
+            &AST::Group(order, ref positions, ref base) => {
+                let base = Graph::from_ast(base)?;
+                free_valence(order, positions, base)?
             }
             AST::CharacteristicGroup(group) => group.to_graph(),
-            &AST::Unsaturated(n, ref base) => {
-                let base = Graph::from(&**base);
-                unsaturate(n as usize, base)
+            &AST::Unsaturated(n, ref positions, ref base) => {
+                let base = Graph::from_ast(base)?;
+                unsaturate(n as usize, positions, base)?
             }
             &AST::Substitution(pos, ref group, ref base) => {
-                let group = Graph::from(&**group);
-                let base = Graph::from(&**base);
-                substitute(pos, group, base)
+                let group = Graph::from_ast(group)?;
+                let base = Graph::from_ast(base)?;
+                substitute(pos, group, base)?
             }
-        }
+            &AST::Isotopic(nuclide, ref base) => {
+                let base = Graph::from_ast(base)?;
+                label(nuclide, base)?
+            }
+            AST::IndicatedHydrogen(indicated, base) => {
+                let base = Graph::from_ast(base)?;
+                indicated_hydrogen(indicated, base)?
+            }
+            &AST::Hydro(prefix, ref locants, ref base) => {
+                let base = Graph::from_ast(base)?;
+                hydro(prefix, locants, base)?
+            }
+            &AST::Acyl(suffix, ref positions, ref base) => {
+                let base = Graph::from_ast(base)?;
+                acyl(suffix, positions, base)?
+            }
+            AST::AcylGroup(group) => group.to_graph(),
+            &AST::Acid(suffix, ref positions, ref base) => {
+                let base = Graph::from_ast(base)?;
+                acid(suffix, positions, base)?
+            }
+            AST::AminoAcid(amino_acid) => amino_acid.to_graph(),
+            &AST::Subtractive(prefix, ref locants, ref base) => {
+                let base = Graph::from_ast(base)?;
+                subtract(prefix, locants, base)?
+            }
+        })
     }
 }
 
@@ -47,57 +112,206 @@ pub fn alkane(n: usize) -> Graph {
             .chain((0..n).flat_map(|i| [(i, n + 2 * i), (i, n + 2 * i + 1)])) // Regular C-H bonds
             .chain([(0, 3 * n), (n - 1, 3 * n + 1)].iter().copied()) // End C-H bonds
             .collect(),
+        pi_bonds: Vec::new(),
+        charges: Vec::new(),
+        isotopes: Vec::new(),
+        stereo: Vec::new(),
         positions: (0..n).map(|i| (Locant::Number(i as u16 + 1), i)).collect(),
         free_valences: Vec::new(),
     }
 }
 
-pub fn free_valence(base: Graph) -> Graph {
+/// Takes `order` hydrogen atoms off the atom at each of `positions`, leaving
+/// free valences in their place: one for "yl", two for "ylidene" and three
+/// for "ylidyne".
+pub fn free_valence(order: u8, positions: &[Locant], base: Graph) -> Result<Graph, LocantError> {
     let mut molecule = base;
-    let &(_, i) = molecule.positions.first().unwrap();
+    // The heteroatoms of a substituent group are not locants of its parent
+    molecule
+        .positions
+        .retain(|(pos, _)| !matches!(pos, Locant::Heteroatom(..)));
 
-    let neighboring_hydrogen = molecule
-        .neighbors(i)
-        .find(|&j| molecule.atoms[j] == Element::Hydrogen);
-    if let Some(neighboring_hydrogen) = neighboring_hydrogen {
-        molecule.remove_atom(neighboring_hydrogen);
-        molecule.free_valences.push(i);
-    } else {
-        let neighbor = molecule.neighbors(i).next().unwrap();
-        molecule.remove_atom(neighbor);
-        molecule.free_valences.push(i);
+    for &pos in positions {
+        for _ in 0..order {
+            let &(_, i) = molecule.position(pos)?;
+            let neighboring_hydrogen = molecule
+                .neighbors(i)
+                .find(|&j| molecule.atoms[j] == Element::Hydrogen);
+            if let Some(neighboring_hydrogen) = neighboring_hydrogen {
+                molecule.remove_atom(neighboring_hydrogen);
+            } else {
+                let neighbor = molecule
+                    .neighbors(i)
+                    .next()
+                    .ok_or(LocantError::NoHydrogen(pos))?;
+                molecule.remove_atom(neighbor);
+            }
+            let &(_, i) = molecule.position(pos)?;
+            molecule.free_valences.push(i);
+        }
     }
 
+    Ok(molecule)
+}
+
+/// Turns the parent hydride into an acyl group at each of `positions`, with a
+/// free valence on the carbonyl carbon, as in "propanoyl" or
+/// "benzenecarbonyl".
+pub fn acyl(suffix: AcylSuffix, positions: &[Locant], base: Graph) -> Result<Graph, LocantError> {
+    let mut molecule = base;
     molecule
+        .positions
+        .retain(|(pos, _)| !matches!(pos, Locant::Heteroatom(..)));
+
+    add_carbonyls(suffix, positions, &mut molecule, |molecule, carbon| {
+        molecule.free_valences.push(carbon);
+    })?;
+    Ok(molecule)
 }
 
-pub fn unsaturate(n: usize, base: Graph) -> Graph {
+/// Turns the parent hydride into a carboxylic acid at each of `positions`,
+/// with a hydroxy group on the carbonyl carbon, as in "propanoic acid" or
+/// "benzenecarboxylic acid".
+pub fn acid(suffix: AcylSuffix, positions: &[Locant], base: Graph) -> Result<Graph, LocantError> {
     let mut molecule = base;
+    add_carbonyls(suffix, positions, &mut molecule, |molecule, carbon| {
+        let oxygen = molecule.atoms.len();
+        molecule.atoms.extend([Element::Oxygen, Element::Hydrogen]);
+        molecule
+            .bonds
+            .extend([(carbon, oxygen), (oxygen, oxygen + 1)]);
+    })?;
+    Ok(molecule)
+}
+
+/// Adds a carbonyl group at each of `positions` and passes its carbon atom
+/// to `complete`. For "oyl" the carbon atom at the locant becomes the
+/// carbonyl carbon, with unspecified locants at the ends of the chain in
+/// turn, as in "ethanedioyl"; for "carbonyl" a new carbon atom is attached
+/// there.
+fn add_carbonyls(
+    suffix: AcylSuffix,
+    positions: &[Locant],
+    molecule: &mut Graph,
+    complete: impl Fn(&mut Graph, usize),
+) -> Result<(), LocantError> {
+    for (k, &pos) in positions.iter().enumerate() {
+        let pos = match pos {
+            Locant::Unspecified if suffix == AcylSuffix::Oyl && k % 2 == 1 => Locant::Greek('ω', 0),
+            pos => pos,
+        };
+        let &(_, i) = molecule.position(pos)?;
+        let hydrogens = match suffix {
+            AcylSuffix::Oyl => 3,
+            AcylSuffix::Carbonyl => 1,
+        };
+        remove_hydrogens(molecule, pos, i, hydrogens)?;
+
+        let &(_, i) = molecule.position(pos)?;
+        let carbon = match suffix {
+            AcylSuffix::Oyl => i,
+            AcylSuffix::Carbonyl => {
+                let carbon = molecule.atoms.len();
+                molecule.atoms.push(Element::Carbon);
+                molecule.bonds.push((i, carbon));
+                carbon
+            }
+        };
+        let oxygen = molecule.atoms.len();
+        molecule.atoms.push(Element::Oxygen);
+        molecule.bonds.push((carbon, oxygen));
+        molecule.pi_bonds.push((carbon, oxygen));
+        complete(molecule, carbon);
+    }
+    Ok(())
+}
+
+/// Removes `n` hydrogen atoms from atom `i`, at `pos`. Fails if it has fewer.
+fn remove_hydrogens(
+    molecule: &mut Graph,
+    pos: Locant,
+    mut i: usize,
+    n: usize,
+) -> Result<(), LocantError> {
     for _ in 0..n {
-        let positions: [_; 2] = molecule.positions[..2].try_into().unwrap();
-        for (_, i) in positions {
-            let neighboring_hydrogen = molecule
-                .neighbors(i)
-                .find(|&j| molecule.atoms[j] == Element::Hydrogen)
-                .unwrap();
-            molecule.remove_atom(neighboring_hydrogen);
+        let hydrogen = molecule
+            .neighbors(i)
+            .find(|&j| molecule.is_terminal_hydrogen(j))
+            .ok_or(LocantError::NoHydrogen(pos))?;
+        molecule.remove_atom(hydrogen);
+        if hydrogen < i {
+            i -= 1;
         }
     }
-
-    molecule
+    Ok(())
 }
 
-pub fn substitute(pos: Locant, group: Graph, base: Graph) -> Graph {
-    let free_valence_count = if group.atoms.as_slice() == [Element::Oxygen] {
-        2
-    } else {
-        group.free_valences.len()
-    };
+/// Puts a double bond, or a triple bond for `n` of two, between the atom at
+/// each of `positions` and the next one along the chain, as in
+/// "buta-1,3-diene". A compound locant gives the second atom, as in
+/// "bicyclo[4.2.0]oct-1(6)-ene". Without locants the bonds take the atoms in
+/// pairs, as in "butadiene".
+pub fn unsaturate(n: usize, positions: &[Locant], base: Graph) -> Result<Graph, LocantError> {
+    let mut molecule = base;
+    for (k, &pos) in positions.iter().enumerate() {
+        let ends = |molecule: &Graph| -> Result<(usize, usize), LocantError> {
+            let (a, b) = match pos {
+                Locant::Unspecified => match molecule.positions.get(2 * k..2 * k + 2) {
+                    Some(&[(_, a), (_, b)]) => (a, b),
+                    _ => return Err(LocantError::NoBond(pos)),
+                },
+                Locant::Compound(a, b) => (
+                    molecule.position(Locant::Number(a))?.1,
+                    molecule.position(Locant::Number(b))?.1,
+                ),
+                Locant::Number(a) => (
+                    molecule.position(pos)?.1,
+                    molecule.position(Locant::Number(a + 1))?.1,
+                ),
+                Locant::Primed(a, primes) => (
+                    molecule.position(pos)?.1,
+                    molecule.position(Locant::Primed(a + 1, primes))?.1,
+                ),
+                _ => return Err(LocantError::NoBond(pos)),
+            };
+            if molecule.bond_order(a, b) == 0 {
+                return Err(LocantError::NoBond(pos));
+            }
+            Ok((a, b))
+        };
+
+        for _ in 0..n {
+            let (a, _) = ends(&molecule)?;
+            remove_hydrogens(&mut molecule, pos, a, 1)?;
+            let (_, b) = ends(&molecule)?;
+            remove_hydrogens(&mut molecule, pos, b, 1)?;
+            let (a, b) = ends(&molecule)?;
+            molecule.pi_bonds.push((a, b));
+        }
+    }
+
+    Ok(molecule)
+}
 
+/// Attaches `group` at `pos` by the atom of its last free valence, with a bond
+/// for each free valence of that atom: a double bond for "oxo" or
+/// "ylidene". Free valences on its other atoms stay open.
+///
+/// Fails if the group has no free valence, or if `pos` is on the group itself,
+/// which would bond an atom to itself.
+pub fn substitute(pos: Locant, group: Graph, base: Graph) -> Result<Graph, LocantError> {
+    if group.free_valences.is_empty() {
+        return Err(LocantError::NoFreeValence(pos));
+    }
     let mut molecule = base.merge(group);
+    let &j = molecule.free_valences.last().unwrap();
+    let free_valence_count = molecule.free_valences.iter().filter(|&&k| k == j).count();
 
     // Remove the hydrogen at the position
-    let &(_, i) = molecule.position(pos);
+    let &(_, i) = molecule.position(pos)?;
+    if i == j {
+        return Err(LocantError::SameAtom(pos));
+    }
     // Assumes that all hydrogens are at the end of the atom list
     for _ in 0..free_valence_count {
         let neighboring_hydrogen = molecule
@@ -110,18 +324,156 @@ pub fn substitute(pos: Locant, group: Graph, base: Graph) -> Graph {
     }
 
     // Join the group to the base
-    let j = molecule.free_valences.pop().unwrap();
+    let &j = molecule.free_valences.last().unwrap();
+    molecule.free_valences.retain(|&k| k != j);
     molecule.bonds.push((i, j));
+    for _ in 1..free_valence_count {
+        molecule.pi_bonds.push((i, j));
+    }
 
-    molecule
+    Ok(molecule)
 }
 
-impl Graph {
-    fn position(&self, pos: Locant) -> &(Locant, usize) {
-        if pos == Locant::Unspecified {
-            return self.positions.first().unwrap();
+/// Replaces the hydroxy group at `pos` with a hydrogen atom.
+pub fn deoxy(pos: Locant, base: Graph) -> Result<Graph, LocantError> {
+    let mut molecule = base;
+    let &(_, i) = molecule.position(pos)?;
+    let oxygen = molecule
+        .neighbors(i)
+        .find(|&o| molecule.atoms[o] == Element::Oxygen && molecule.hydrogen_count(o) == 1)
+        .ok_or(LocantError::NoGroup(pos))?;
+
+    add_hydrogen(&mut molecule, i);
+    let removed = molecule.branch(i, oxygen);
+    molecule.remove_atoms(removed);
+    Ok(molecule)
+}
+
+/// Removes the skeletal atom at `pos` with its hydrogens. A chain or ring
+/// atom between two others is bridged over, as in "19-norsteroid"; otherwise
+/// its neighbours take a hydrogen atom for each bond lost.
+pub fn nor(pos: Locant, base: Graph) -> Result<Graph, LocantError> {
+    let mut molecule = base;
+    let &(_, i) = molecule.position(pos)?;
+    let neighbors = molecule
+        .neighbors(i)
+        .filter(|&j| !molecule.is_terminal_hydrogen(j))
+        .collect::<Vec<_>>();
+
+    if let &[a, b] = neighbors.as_slice() {
+        molecule.bonds.push((a, b));
+    } else {
+        for &j in &neighbors {
+            for _ in 0..molecule.bond_order(i, j) {
+                add_hydrogen(&mut molecule, j);
+            }
         }
-        self.positions.iter().find(|(p, _)| p == &pos).unwrap()
+    }
+
+    let mut removed = molecule
+        .neighbors(i)
+        .filter(|&j| molecule.is_terminal_hydrogen(j))
+        .collect::<Vec<_>>();
+    removed.push(i);
+    molecule.remove_atoms(removed);
+    Ok(molecule)
+}
+
+/// Removes water from the hydroxy groups at `a` and `b`, keeping the oxygen
+/// atom at `a` and bonding it to the atom at `b`, as in "1,4-anhydro".
+pub fn anhydro(a: Locant, b: Locant, base: Graph) -> Result<Graph, LocantError> {
+    let mut molecule = base;
+    let hydroxy = |molecule: &Graph, pos: Locant| -> Result<_, LocantError> {
+        let &(_, i) = molecule.position(pos)?;
+        let oxygen = molecule
+            .neighbors(i)
+            .find(|&o| molecule.atoms[o] == Element::Oxygen && molecule.hydrogen_count(o) == 1)
+            .ok_or(LocantError::NoGroup(pos))?;
+        Ok((i, oxygen))
+    };
+    let (_, oxygen) = hydroxy(&molecule, a)?;
+    let (j, other) = hydroxy(&molecule, b)?;
+
+    let hydrogen = molecule
+        .neighbors(oxygen)
+        .find(|&h| molecule.is_terminal_hydrogen(h))
+        .unwrap();
+    molecule.bonds.push((oxygen, j));
+    let mut removed = molecule.branch(j, other);
+    removed.push(hydrogen);
+    molecule.remove_atoms(removed);
+    Ok(molecule)
+}
+
+/// Replaces a methyl group on the atom at `pos`, as in "N-demethyl", with a
+/// hydrogen atom.
+pub fn demethyl(pos: Locant, base: Graph) -> Result<Graph, LocantError> {
+    let mut molecule = base;
+    let &(_, i) = molecule.position(pos)?;
+    let methyl = molecule
+        .neighbors(i)
+        .find(|&c| molecule.atoms[c] == Element::Carbon && molecule.hydrogen_count(c) == 3)
+        .ok_or(LocantError::NoGroup(pos))?;
+
+    add_hydrogen(&mut molecule, i);
+    let removed = molecule.branch(i, methyl);
+    molecule.remove_atoms(removed);
+    Ok(molecule)
+}
+
+impl Graph {
+    /// The atom at a locant. Compound locants such as "1(6)" are at their
+    /// first atom, and Greek locants count along the numbered atoms with the
+    /// same primes, with "ω" at the last of them.
+    pub(crate) fn position(&self, pos: Locant) -> Result<&(Locant, usize), LocantError> {
+        let found = match pos {
+            Locant::Unspecified => self.positions.first(),
+            Locant::Compound(n, _) => self.positions.iter().find(|(p, _)| *p == Locant::Number(n)),
+            Locant::Greek(letter, primes) => {
+                let mut chain = self.positions.iter().filter(|(p, _)| {
+                    matches!(p, Locant::Number(_) | Locant::Primed(..)) && p.primes() == primes
+                });
+                if letter == 'ω' {
+                    chain.next_back()
+                } else {
+                    Locant::greek_index(letter).and_then(|k| chain.nth(k))
+                }
+            }
+            _ => self.positions.iter().find(|(p, _)| p == &pos),
+        };
+        found.ok_or(LocantError::NoAtom(pos))
+    }
+
+    /// The order of the bond between `a` and `b`, or zero if they are not
+    /// bonded.
+    pub fn bond_order(&self, a: usize, b: usize) -> u8 {
+        let is_between = |&&(c, d): &&(usize, usize)| (a, b) == (c, d) || (a, b) == (d, c);
+        let sigma = self.bonds.iter().filter(is_between).count();
+        let pi = self.pi_bonds.iter().filter(is_between).count();
+        (sigma + pi) as u8
+    }
+
+    /// The formal charge of atom `i`.
+    pub fn charge(&self, i: usize) -> i8 {
+        self.charges
+            .iter()
+            .find(|&&(j, _)| j == i)
+            .map_or(0, |&(_, charge)| charge)
+    }
+
+    /// The mass number of atom `i`, if it is a specific isotope.
+    pub fn mass_number(&self, i: usize) -> Option<u16> {
+        self.isotopes
+            .iter()
+            .find(|&&(j, _)| j == i)
+            .map(|&(_, mass_number)| mass_number)
+    }
+
+    /// The number of hydrogen atoms bonded to atom `i`.
+    pub fn hydrogen_count(&self, i: usize) -> usize {
+        self.neighbors(i)
+            .filter(|&j| self.atoms[j] == Element::Hydrogen)
+            .count()
     }
 
     pub fn neighbors(&self, i: usize) -> impl Iterator<Item = usize> + '_ {
@@ -136,8 +488,37 @@ impl Graph {
         })
     }
 
-    fn merge(mut self, other: Graph) -> Self {
+    /// Adds the atoms of `other`, unconnected to those of this graph. The
+    /// locants of `other` are primed to tell them apart from those already
+    /// here: heteroatom locants one at a time, as "N" and "N'" in
+    /// "N,N'-dimethylethane-1,2-diamine", and the rest all together, as "1'"
+    /// for the first atom of a substituent group.
+    pub fn merge(mut self, other: Graph) -> Self {
         let offset = self.atoms.len();
+        let is_taken = |positions: &[(Locant, usize)], primes| {
+            other.positions.iter().any(|&(locant, _)| {
+                !matches!(locant, Locant::Heteroatom(..))
+                    && positions.iter().any(|&(p, _)| p == locant.primed(primes))
+            })
+        };
+        let primes = (1..)
+            .find(|&primes| !is_taken(&self.positions, primes))
+            .unwrap();
+        for (locant, i) in other.positions {
+            if !matches!(locant, Locant::Heteroatom(..) | Locant::Unspecified) {
+                self.positions.push((locant.primed(primes), i + offset));
+            } else if let Locant::Heteroatom(element, mut primes) = locant {
+                while self
+                    .positions
+                    .iter()
+                    .any(|&(p, _)| p == Locant::Heteroatom(element, primes))
+                {
+                    primes += 1;
+                }
+                self.positions
+                    .push((Locant::Heteroatom(element, primes), i + offset));
+            }
+        }
         self.atoms.extend(other.atoms);
         self.bonds.extend(
             other
@@ -145,17 +526,63 @@ impl Graph {
                 .into_iter()
                 .map(|(a, b)| (a + offset, b + offset)),
         );
-        // Ignore positions of the added group
+        self.pi_bonds.extend(
+            other
+                .pi_bonds
+                .into_iter()
+                .map(|(a, b)| (a + offset, b + offset)),
+        );
+        self.charges.extend(
+            other
+                .charges
+                .into_iter()
+                .map(|(i, charge)| (i + offset, charge)),
+        );
+        self.isotopes.extend(
+            other
+                .isotopes
+                .into_iter()
+                .map(|(i, mass_number)| (i + offset, mass_number)),
+        );
+        self.stereo
+            .extend(other.stereo.into_iter().map(|mut stereo| {
+                stereo.map_atoms(|i| i + offset);
+                stereo
+            }));
         self.free_valences
             .extend(other.free_valences.into_iter().map(|i| i + offset));
 
         self
     }
 
-    fn remove_atom(&mut self, i: usize) {
+    /// The atoms reached from `root` without passing through `i`: the group
+    /// that `root` attaches to atom `i`.
+    fn branch(&self, i: usize, root: usize) -> Vec<usize> {
+        let mut visited = vec![root];
+        let mut stack = vec![root];
+        while let Some(j) = stack.pop() {
+            for k in self.neighbors(j) {
+                if k != i && !visited.contains(&k) {
+                    visited.push(k);
+                    stack.push(k);
+                }
+            }
+        }
+        visited
+    }
+
+    fn remove_atoms(&mut self, mut atoms: Vec<usize>) {
+        atoms.sort_unstable();
+        atoms.dedup();
+        for &i in atoms.iter().rev() {
+            self.remove_atom(i);
+        }
+    }
+
+    pub(crate) fn remove_atom(&mut self, i: usize) {
         self.atoms.remove(i);
 
-        self.bonds.retain_mut(|(a, b)| {
+        let reindex_bond = |(a, b): &mut (usize, usize)| {
             if *a == i || *b == i {
                 return false;
             }
@@ -166,8 +593,33 @@ impl Graph {
                 *b -= 1;
             }
             true
-        });
+        };
+        self.bonds.retain_mut(reindex_bond);
+        self.pi_bonds.retain_mut(reindex_bond);
+
+        self.stereo.retain(|stereo| !stereo.atoms().contains(&i));
+        for stereo in &mut self.stereo {
+            stereo.map_atoms(|j| if j > i { j - 1 } else { j });
+        }
 
+        self.charges.retain_mut(|(j, _)| {
+            if *j == i {
+                return false;
+            }
+            if *j > i {
+                *j -= 1;
+            }
+            true
+        });
+        self.isotopes.retain_mut(|(j, _)| {
+            if *j == i {
+                return false;
+            }
+            if *j > i {
+                *j -= 1;
+            }
+            true
+        });
         self.positions.retain_mut(|(_, j)| {
             if *j == i {
                 return false;
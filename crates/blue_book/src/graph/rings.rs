@@ -0,0 +1,345 @@
+//! Ring perception, using Horton's algorithm to find the smallest set of
+//! smallest rings (SSSR).
+
+use std::collections::VecDeque;
+
+use super::Graph;
+
+/// The smallest set of smallest rings of a molecule.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct Rings {
+    /// Each ring as a cycle of atom indices, starting from its lowest index.
+    pub rings: Vec<Vec<usize>>,
+}
+
+impl Graph {
+    pub fn rings(&self) -> Rings {
+        let n = self.atoms.len();
+        let mut neighbors = vec![Vec::new(); n];
+        for (e, &(a, b)) in self.bonds.iter().enumerate() {
+            neighbors[a].push((b, e));
+            neighbors[b].push((a, e));
+        }
+
+        let cyclomatic_number = (self.bonds.len() + components(&neighbors)).saturating_sub(n);
+        if cyclomatic_number == 0 {
+            return Rings::default();
+        }
+
+        // Horton's candidates: for each root and bond, the cycle formed by the
+        // bond and the shortest paths from the root to either end of it
+        let mut candidates = Vec::new();
+        for root in 0..n {
+            let (distances, parents) = shortest_path_tree(&neighbors, root);
+            for &(a, b) in &self.bonds {
+                if distances[a] == usize::MAX || distances[b] == usize::MAX {
+                    continue;
+                }
+                let path_a = path_to_root(&parents, a);
+                let path_b = path_to_root(&parents, b);
+                let disjoint = path_a[..path_a.len() - 1]
+                    .iter()
+                    .all(|i| !path_b[..path_b.len() - 1].contains(i));
+                if !disjoint || path_a.contains(&b) || path_b.contains(&a) {
+                    continue;
+                }
+
+                let mut cycle = path_a;
+                cycle.reverse();
+                cycle.extend(&path_b[..path_b.len() - 1]);
+                candidates.push(canonical_cycle(cycle));
+            }
+        }
+        candidates.sort_by(|a, b| a.len().cmp(&b.len()).then_with(|| a.cmp(b)));
+        candidates.dedup();
+
+        // Greedily keep the shortest cycles that are linearly independent over
+        // GF(2), each cycle being represented by its set of bonds
+        let words = self.bonds.len().div_ceil(64);
+        let mut basis: Vec<Vec<u64>> = Vec::new();
+        let mut rings = Vec::new();
+        for cycle in candidates {
+            let mut vector = vec![0u64; words];
+            for (k, &a) in cycle.iter().enumerate() {
+                let b = cycle[(k + 1) % cycle.len()];
+                let &(_, e) = neighbors[a].iter().find(|&&(j, _)| j == b).unwrap();
+                vector[e / 64] ^= 1 << (e % 64);
+            }
+
+            for reduced in &basis {
+                let pivot = leading_bit(reduced);
+                if vector[pivot / 64] & (1 << (pivot % 64)) != 0 {
+                    for (v, r) in vector.iter_mut().zip(reduced) {
+                        *v ^= r;
+                    }
+                }
+            }
+            if vector.iter().all(|&v| v == 0) {
+                continue;
+            }
+
+            // Keep the basis in row echelon form, sorted by pivot
+            let pivot = leading_bit(&vector);
+            for reduced in &mut basis {
+                if reduced[pivot / 64] & (1 << (pivot % 64)) != 0 {
+                    for (r, v) in reduced.iter_mut().zip(&vector) {
+                        *r ^= v;
+                    }
+                }
+            }
+            basis.push(vector);
+
+            rings.push(cycle);
+            if rings.len() == cyclomatic_number {
+                break;
+            }
+        }
+
+        Rings { rings }
+    }
+}
+
+impl Rings {
+    pub fn is_empty(&self) -> bool {
+        self.rings.is_empty()
+    }
+
+    pub fn is_ring_atom(&self, i: usize) -> bool {
+        self.rings.iter().any(|ring| ring.contains(&i))
+    }
+
+    pub fn is_ring_bond(&self, a: usize, b: usize) -> bool {
+        self.bond_rings(a, b).next().is_some()
+    }
+
+    /// The rings containing atom `i`.
+    pub fn atom_rings(&self, i: usize) -> impl Iterator<Item = &[usize]> + '_ {
+        self.rings
+            .iter()
+            .filter(move |ring| ring.contains(&i))
+            .map(Vec::as_slice)
+    }
+
+    /// The rings containing the bond between `a` and `b`.
+    pub fn bond_rings(&self, a: usize, b: usize) -> impl Iterator<Item = &[usize]> + '_ {
+        self.rings
+            .iter()
+            .filter(move |ring| {
+                (0..ring.len()).any(|k| {
+                    let (c, d) = (ring[k], ring[(k + 1) % ring.len()]);
+                    (a, b) == (c, d) || (a, b) == (d, c)
+                })
+            })
+            .map(Vec::as_slice)
+    }
+
+    /// The sizes of the rings containing atom `i`, smallest first.
+    pub fn atom_ring_sizes(&self, i: usize) -> Vec<usize> {
+        let mut sizes = self.atom_rings(i).map(<[_]>::len).collect::<Vec<_>>();
+        sizes.sort();
+        sizes
+    }
+
+    /// The sizes of the rings containing the bond between `a` and `b`,
+    /// smallest first.
+    pub fn bond_ring_sizes(&self, a: usize, b: usize) -> Vec<usize> {
+        let mut sizes = self.bond_rings(a, b).map(<[_]>::len).collect::<Vec<_>>();
+        sizes.sort();
+        sizes
+    }
+
+    pub fn smallest_ring_size(&self, i: usize) -> Option<usize> {
+        self.atom_rings(i).map(<[_]>::len).min()
+    }
+
+    /// Groups rings that share atoms into ring systems, each
+    /// given as indices into `rings`.
+    pub fn ring_systems(&self) -> Vec<Vec<usize>> {
+        let mut systems: Vec<Vec<usize>> = Vec::new();
+        for (r, ring) in self.rings.iter().enumerate() {
+            let (touching, mut rest): (Vec<_>, Vec<_>) = systems.into_iter().partition(|system| {
+                system
+                    .iter()
+                    .any(|&s| self.rings[s].iter().any(|i| ring.contains(i)))
+            });
+            let mut system = touching.into_iter().flatten().collect::<Vec<_>>();
+            system.push(r);
+            system.sort();
+            rest.push(system);
+            systems = rest;
+        }
+        systems.sort();
+        systems
+    }
+
+    /// The atoms of each ring system, in ascending order.
+    pub fn ring_system_atoms(&self) -> Vec<Vec<usize>> {
+        self.ring_systems()
+            .into_iter()
+            .map(|system| {
+                let mut atoms = system
+                    .into_iter()
+                    .flat_map(|r| self.rings[r].iter().copied())
+                    .collect::<Vec<_>>();
+                atoms.sort();
+                atoms.dedup();
+                atoms
+            })
+            .collect()
+    }
+}
+
+fn components(neighbors: &[Vec<(usize, usize)>]) -> usize {
+    let mut visited = vec![false; neighbors.len()];
+    let mut count = 0;
+    for root in 0..neighbors.len() {
+        if visited[root] {
+            continue;
+        }
+        count += 1;
+        visited[root] = true;
+        let mut stack = vec![root];
+        while let Some(i) = stack.pop() {
+            for &(j, _) in &neighbors[i] {
+                if !visited[j] {
+                    visited[j] = true;
+                    stack.push(j);
+                }
+            }
+        }
+    }
+    count
+}
+
+fn shortest_path_tree(neighbors: &[Vec<(usize, usize)>], root: usize) -> (Vec<usize>, Vec<usize>) {
+    let mut distances = vec![usize::MAX; neighbors.len()];
+    let mut parents = (0..neighbors.len()).collect::<Vec<_>>();
+    distances[root] = 0;
+    let mut queue = VecDeque::from([root]);
+    while let Some(i) = queue.pop_front() {
+        for &(j, _) in &neighbors[i] {
+            if distances[j] == usize::MAX {
+                distances[j] = distances[i] + 1;
+                parents[j] = i;
+                queue.push_back(j);
+            }
+        }
+    }
+    (distances, parents)
+}
+
+/// The path from `i` back to the root of the shortest path tree, inclusive.
+fn path_to_root(parents: &[usize], mut i: usize) -> Vec<usize> {
+    let mut path = vec![i];
+    while parents[i] != i {
+        i = parents[i];
+        path.push(i);
+    }
+    path
+}
+
+/// Rotates and reflects a cycle so that it starts at its lowest index and
+/// continues towards the lower of that atom's two neighbours.
+fn canonical_cycle(mut cycle: Vec<usize>) -> Vec<usize> {
+    let start = (0..cycle.len()).min_by_key(|&k| cycle[k]).unwrap();
+    cycle.rotate_left(start);
+    if cycle.len() > 2 && cycle[cycle.len() - 1] < cycle[1] {
+        cycle[1..].reverse();
+    }
+    cycle
+}
+
+fn leading_bit(vector: &[u64]) -> usize {
+    let (word, &value) = vector.iter().enumerate().find(|(_, &v)| v != 0).unwrap();
+    word * 64 + value.trailing_zeros() as usize
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        graph::Graph,
+        parser::parse,
+        test::{CAFFEINE, DOPAMINE, ISOBUTANE},
+        Element,
+    };
+
+    #[test]
+    fn test_benzene_rings() {
+        let graph = Graph::from(&*parse(DOPAMINE));
+        let rings = graph.rings();
+
+        assert_eq!(rings.rings.len(), 1);
+        assert_eq!(rings.rings[0].len(), 6);
+        assert!(rings.is_ring_atom(0));
+        assert!(rings.is_ring_bond(0, 1));
+        assert_eq!(rings.atom_ring_sizes(0), vec![6]);
+    }
+
+    #[test]
+    fn test_purine_rings() {
+        let graph = Graph::from(&*parse(CAFFEINE));
+        let rings = graph.rings();
+
+        let mut sizes = rings.rings.iter().map(Vec::len).collect::<Vec<_>>();
+        sizes.sort();
+        assert_eq!(sizes, vec![5, 6]);
+
+        // C4 and C5 are the fusion atoms
+        assert_eq!(rings.atom_ring_sizes(3), vec![5, 6]);
+        assert_eq!(rings.atom_ring_sizes(4), vec![5, 6]);
+        assert_eq!(rings.bond_ring_sizes(3, 4), vec![5, 6]);
+        assert_eq!(rings.atom_ring_sizes(0), vec![6]);
+        assert_eq!(rings.smallest_ring_size(6), Some(5));
+
+        assert_eq!(rings.ring_systems(), vec![vec![0, 1]]);
+        assert_eq!(rings.ring_system_atoms(), vec![(0..9).collect::<Vec<_>>()]);
+    }
+
+    #[test]
+    fn test_acyclic() {
+        let graph = Graph::from(&*parse(ISOBUTANE));
+        let rings = graph.rings();
+
+        assert!(rings.is_empty());
+        assert!(!rings.is_ring_atom(0));
+        assert_eq!(rings.smallest_ring_size(0), None);
+    }
+
+    #[test]
+    fn test_ring_systems() {
+        // Cyclopropylcyclobutane next to spiro[2.2]pentane
+        let graph = Graph {
+            atoms: vec![Element::Carbon; 12],
+            bonds: vec![
+                (0, 1),
+                (1, 2),
+                (2, 0),
+                (2, 3),
+                (3, 4),
+                (4, 5),
+                (5, 6),
+                (6, 3),
+                (7, 8),
+                (8, 9),
+                (9, 7),
+                (9, 10),
+                (10, 11),
+                (11, 9),
+            ],
+            ..Default::default()
+        };
+        let rings = graph.rings();
+
+        assert_eq!(
+            rings.rings,
+            vec![
+                vec![0, 1, 2],
+                vec![7, 8, 9],
+                vec![9, 10, 11],
+                vec![3, 4, 5, 6]
+            ],
+        );
+        assert!(!rings.is_ring_bond(2, 3));
+        assert_eq!(rings.ring_systems(), vec![vec![0], vec![1, 2], vec![3]]);
+    }
+}
@@ -0,0 +1,83 @@
+//! Stereochemistry, described relative to the neighbours of each stereocentre
+//! or stereogenic double bond in the same way as SMILES.
+
+/// The arrangement of the last three neighbours of a tetrahedral centre, seen
+/// from the first.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Chirality {
+    /// `@` in SMILES.
+    Anticlockwise,
+    /// `@@` in SMILES.
+    Clockwise,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Stereo {
+    Tetrahedral {
+        centre: usize,
+        neighbors: [usize; 4],
+        chirality: Chirality,
+    },
+    /// The double bond between `atoms[1]` and `atoms[2]`, with `atoms[0]` and
+    /// `atoms[3]` being neighbours of either end.
+    DoubleBond {
+        atoms: [usize; 4],
+        /// Whether `atoms[0]` and `atoms[3]` are on the same side of the
+        /// double bond (cis) rather than opposite sides (trans).
+        together: bool,
+    },
+}
+
+impl Chirality {
+    pub fn inverse(self) -> Self {
+        match self {
+            Chirality::Anticlockwise => Chirality::Clockwise,
+            Chirality::Clockwise => Chirality::Anticlockwise,
+        }
+    }
+}
+
+impl Stereo {
+    /// The atoms whose arrangement this describes.
+    pub fn atoms(&self) -> Vec<usize> {
+        match *self {
+            Stereo::Tetrahedral {
+                centre, neighbors, ..
+            } => [&[centre], &neighbors[..]].concat(),
+            Stereo::DoubleBond { atoms, .. } => atoms.to_vec(),
+        }
+    }
+
+    /// Replaces each atom `i` with `f(i)`.
+    pub fn map_atoms(&mut self, mut f: impl FnMut(usize) -> usize) {
+        match self {
+            Stereo::Tetrahedral {
+                centre, neighbors, ..
+            } => {
+                *centre = f(*centre);
+                for i in neighbors {
+                    *i = f(*i);
+                }
+            }
+            Stereo::DoubleBond { atoms, .. } => {
+                for i in atoms {
+                    *i = f(*i);
+                }
+            }
+        }
+    }
+}
+
+/// Whether `order` is an even permutation of `reference`, which must contain
+/// the same distinct elements.
+pub fn is_even_permutation(reference: &[usize], order: &[usize]) -> bool {
+    let positions = order
+        .iter()
+        .map(|i| reference.iter().position(|j| j == i).unwrap())
+        .collect::<Vec<_>>();
+    let mut inversions = 0;
+    for (k, &a) in positions.iter().enumerate() {
+        inversions += positions[k + 1..].iter().filter(|&&b| b < a).count();
+    }
+    inversions % 2 == 0
+}
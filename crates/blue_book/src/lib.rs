@@ -1,11 +1,14 @@
 pub mod chapters;
+pub mod formula;
 pub mod graph;
+pub mod molfile;
 pub mod parser;
 pub mod plugin;
 pub mod scanner;
+pub mod smiles;
 pub mod test;
 
 pub use chapters::p_1_general::{
     p_11_scope::{Element, ELEMENTS},
-    p_14_general_rules::p_14_3_locants::Locant,
+    p_14_general_rules::p_14_3_locants::{Locant, LocantError},
 };
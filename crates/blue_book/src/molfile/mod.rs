@@ -0,0 +1,80 @@
+//! MDL Molfiles and SD files, as described in the BIOVIA CTfile formats
+//! specification.
+//!
+//! Terminal hydrogens are left implicit, radicals stand in for free valences
+//! and aromatic rings are written in their Kekulé form. Stereochemistry is not
+//! written.
+
+use std::{error, fmt};
+
+use crate::graph::Graph;
+
+mod parser;
+mod writer;
+
+pub use parser::parse_sdf;
+pub use writer::write_sdf;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Version {
+    V2000,
+    V3000,
+}
+
+#[derive(Debug, Default, Clone)]
+pub struct Molfile {
+    /// The first line of the header.
+    pub name: String,
+    pub graph: Graph,
+    /// The 2D coordinates of each atom, in ångströms, or empty if the molecule
+    /// has not been laid out.
+    pub coordinates: Vec<[f64; 2]>,
+}
+
+/// A record of an SD file: a molecule followed by named data fields.
+#[derive(Debug, Default, Clone)]
+pub struct SdfRecord {
+    pub molfile: Molfile,
+    pub data: Vec<(String, String)>,
+}
+
+/// An error in a Molfile or SD file, on a line counted from one.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MolfileError {
+    pub line: usize,
+    pub message: &'static str,
+}
+
+impl Molfile {
+    pub fn new(name: &str, graph: Graph) -> Self {
+        Self {
+            name: name.to_owned(),
+            graph,
+            coordinates: Vec::new(),
+        }
+    }
+}
+
+impl SdfRecord {
+    pub fn new(molfile: Molfile) -> Self {
+        Self {
+            molfile,
+            data: Vec::new(),
+        }
+    }
+
+    pub fn field(&self, name: &str) -> Option<&str> {
+        self.data
+            .iter()
+            .find(|(field, _)| field == name)
+            .map(|(_, value)| value.as_str())
+    }
+}
+
+impl fmt::Display for MolfileError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} on line {}", self.message, self.line)
+    }
+}
+
+impl error::Error for MolfileError {}
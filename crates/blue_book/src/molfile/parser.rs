@@ -0,0 +1,472 @@
+use std::str::FromStr;
+
+use super::{Molfile, MolfileError, SdfRecord};
+use crate::Element;
+
+/// A bond type for aromatic bonds, which are kekulized after reading.
+const AROMATIC: u8 = 4;
+
+impl FromStr for Molfile {
+    type Err = MolfileError;
+
+    /// Reads a V2000 or V3000 Molfile, adding implicit hydrogens as atoms.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let lines = s.lines().collect::<Vec<_>>();
+        parse_molfile(&lines, 0)
+    }
+}
+
+/// Reads the records of an SD file.
+pub fn parse_sdf(sdf: &str) -> Result<Vec<SdfRecord>, MolfileError> {
+    let lines = sdf.lines().collect::<Vec<_>>();
+
+    let mut records = Vec::new();
+    let mut start = 0;
+    while lines[start..].iter().any(|line| !line.trim().is_empty()) {
+        let Some(length) = lines[start..].iter().position(|&line| line == "$$$$") else {
+            return Err(MolfileError {
+                line: lines.len(),
+                message: "Record without a closing $$$$",
+            });
+        };
+        let record = &lines[start..start + length];
+
+        let end = record
+            .iter()
+            .position(|&line| line == "M  END")
+            .ok_or(MolfileError {
+                line: start + length + 1,
+                message: "Molfile without M  END",
+            })?;
+        let molfile = parse_molfile(&record[..=end], start)?;
+
+        let mut data = Vec::new();
+        let mut k = end + 1;
+        while k < record.len() {
+            let line = record[k];
+            if line.trim().is_empty() {
+                k += 1;
+                continue;
+            }
+            let name = line
+                .strip_prefix('>')
+                .and_then(|header| {
+                    let open = header.find('<')?;
+                    let close = header[open..].find('>')?;
+                    Some(&header[open + 1..open + close])
+                })
+                .ok_or(MolfileError {
+                    line: start + k + 1,
+                    message: "Expected a data header",
+                })?;
+            k += 1;
+
+            let mut value = Vec::new();
+            while k < record.len() && !record[k].is_empty() {
+                value.push(record[k]);
+                k += 1;
+            }
+            data.push((name.to_owned(), value.join("\n")));
+        }
+
+        records.push(SdfRecord { molfile, data });
+        start += length + 1;
+    }
+
+    Ok(records)
+}
+
+/// Parses the lines of a Molfile, which start on line `offset + 1` of the
+/// input.
+fn parse_molfile(lines: &[&str], offset: usize) -> Result<Molfile, MolfileError> {
+    let error = |k: usize, message| MolfileError {
+        line: offset + k + 1,
+        message,
+    };
+
+    let Some(&counts) = lines.get(3) else {
+        return Err(error(lines.len(), "Missing header"));
+    };
+    let mut reader = if counts.trim_end().ends_with("V3000") {
+        V3000::read(lines, offset)?
+    } else {
+        V2000::read(lines, offset)?
+    };
+    reader.molfile.name = lines[0].to_owned();
+
+    reader.finish().map_err(|message| error(3, message))
+}
+
+/// The atoms and bonds read so far, before hydrogens are added.
+#[derive(Default)]
+struct Reader {
+    molfile: Molfile,
+    bond_types: Vec<u8>,
+    /// The number of unpaired electrons on each atom, as free valences.
+    radicals: Vec<(usize, usize)>,
+}
+
+impl Reader {
+    fn add_atom(&mut self, symbol: &str, x: f64, y: f64) -> Result<(), &'static str> {
+        let element = Element::from_symbol(symbol).ok_or("Unknown element")?;
+        self.molfile.graph.atoms.push(element);
+        self.molfile.coordinates.push([x, y]);
+        Ok(())
+    }
+
+    fn add_bond(&mut self, a: usize, b: usize, bond_type: u8) -> Result<(), &'static str> {
+        let n = self.molfile.graph.atoms.len();
+        if !(1..=n).contains(&a) || !(1..=n).contains(&b) || a == b {
+            return Err("Invalid atom number");
+        }
+        let (a, b) = (a - 1, b - 1);
+        let graph = &mut self.molfile.graph;
+        match bond_type {
+            1..=3 => {
+                for _ in 1..bond_type {
+                    graph.pi_bonds.push((a, b));
+                }
+            }
+            AROMATIC => {}
+            _ => return Err("Unsupported bond type"),
+        }
+        graph.bonds.push((a, b));
+        self.bond_types.push(bond_type);
+        Ok(())
+    }
+
+    fn set_charge(&mut self, atom: usize, charge: i8) -> Result<(), &'static str> {
+        if !(1..=self.molfile.graph.atoms.len()).contains(&atom) {
+            return Err("Invalid atom number");
+        }
+        let charges = &mut self.molfile.graph.charges;
+        charges.retain(|&(i, _)| i != atom - 1);
+        if charge != 0 {
+            charges.push((atom - 1, charge));
+        }
+        Ok(())
+    }
+
+    fn set_radical(&mut self, atom: usize, radical: u8) -> Result<(), &'static str> {
+        if !(1..=self.molfile.graph.atoms.len()).contains(&atom) {
+            return Err("Invalid atom number");
+        }
+        self.radicals.retain(|&(i, _)| i != atom - 1);
+        match radical {
+            0 => {}
+            2 => self.radicals.push((atom - 1, 1)),
+            1 | 3 => self.radicals.push((atom - 1, 2)),
+            _ => return Err("Invalid radical"),
+        }
+        Ok(())
+    }
+
+    /// Adds implicit hydrogens and free valences, then kekulizes aromatic
+    /// bonds.
+    fn finish(mut self) -> Result<Molfile, &'static str> {
+        let graph = &mut self.molfile.graph;
+        let n = graph.atoms.len();
+
+        for i in 0..n {
+            let free_valences = self
+                .radicals
+                .iter()
+                .find(|&&(j, _)| j == i)
+                .map_or(0, |&(_, count)| count);
+            graph.free_valences.extend((0..free_valences).map(|_| i));
+
+            let mut bond_order_sum = free_valences;
+            let mut aromatic = false;
+            for (&(a, b), &bond_type) in graph.bonds.iter().zip(&self.bond_types) {
+                if a == i || b == i {
+                    aromatic |= bond_type == AROMATIC;
+                    bond_order_sum += if bond_type == AROMATIC {
+                        1
+                    } else {
+                        bond_type as usize
+                    };
+                }
+            }
+            // One more bond is shared out around an aromatic ring
+            let hydrogens = graph
+                .bonding_number(i)
+                .saturating_sub(bond_order_sum + usize::from(aromatic));
+
+            for _ in 0..hydrogens {
+                let h = graph.atoms.len();
+                graph.atoms.push(Element::Hydrogen);
+                graph.bonds.push((i, h));
+                let coordinates = self.molfile.coordinates[i];
+                self.molfile.coordinates.push(coordinates);
+            }
+        }
+
+        graph
+            .kekulize()
+            .map_err(|_| "Aromatic bonds without a Kekulé structure")?;
+        Ok(self.molfile)
+    }
+}
+
+struct V2000;
+
+impl V2000 {
+    fn read(lines: &[&str], offset: usize) -> Result<Reader, MolfileError> {
+        let error = |k: usize, message| MolfileError {
+            line: offset + k + 1,
+            message,
+        };
+        let field = |k: usize, start: usize, end: usize| {
+            let line = lines[k];
+            line.get(start..end.min(line.len()))
+                .unwrap_or("")
+                .trim()
+                .parse::<i64>()
+                .map_err(|_| error(k, "Expected a number"))
+        };
+
+        let atom_count = field(3, 0, 3)? as usize;
+        let bond_count = field(3, 3, 6)? as usize;
+        if lines.len() < 4 + atom_count + bond_count {
+            return Err(error(lines.len(), "Missing atoms or bonds"));
+        }
+
+        let mut reader = Reader::default();
+        let mut atom_block_charges = Vec::new();
+        for (k, &line) in lines.iter().enumerate().take(4 + atom_count).skip(4) {
+            let coordinate = |start: usize| {
+                line.get(start..start + 10)
+                    .and_then(|s| s.trim().parse::<f64>().ok())
+                    .ok_or(error(k, "Expected a coordinate"))
+            };
+            let (x, y) = (coordinate(0)?, coordinate(10)?);
+            let symbol = line.get(31..34).unwrap_or("").trim();
+            reader
+                .add_atom(symbol, x, y)
+                .map_err(|message| error(k, message))?;
+
+            let charge = match field(k, 36, 39).unwrap_or(0) {
+                charge @ 1..=7 if charge != 4 => 4 - charge,
+                _ => 0,
+            };
+            atom_block_charges.push((k - 3, charge as i8));
+        }
+
+        for k in 4 + atom_count..4 + atom_count + bond_count {
+            let (a, b) = (field(k, 0, 3)? as usize, field(k, 3, 6)? as usize);
+            let bond_type = field(k, 6, 9)? as u8;
+            reader
+                .add_bond(a, b, bond_type)
+                .map_err(|message| error(k, message))?;
+        }
+
+        // Any M  CHG line supersedes the charges in the atom block
+        let mut has_charge_lines = false;
+        for (k, &line) in lines.iter().enumerate().skip(4 + atom_count + bond_count) {
+            if line == "M  END" {
+                break;
+            }
+            let Some(property) = line.get(3..6).filter(|_| line.starts_with("M  ")) else {
+                continue;
+            };
+            if !matches!(property, "CHG" | "RAD") {
+                continue;
+            }
+
+            let entries = line[6..]
+                .split_whitespace()
+                .map(|s| s.parse::<i64>().map_err(|_| error(k, "Expected a number")))
+                .collect::<Result<Vec<_>, _>>()?;
+            let Some((&count, entries)) = entries.split_first() else {
+                return Err(error(k, "Expected an entry count"));
+            };
+            if entries.len() != 2 * count as usize {
+                return Err(error(k, "Wrong number of entries"));
+            }
+            for entry in entries.chunks(2) {
+                let (atom, value) = (entry[0] as usize, entry[1]);
+                let result = if property == "CHG" {
+                    has_charge_lines = true;
+                    reader.set_charge(atom, value as i8)
+                } else {
+                    reader.set_radical(atom, value as u8)
+                };
+                result.map_err(|message| error(k, message))?;
+            }
+        }
+
+        if !has_charge_lines {
+            for (atom, charge) in atom_block_charges {
+                reader.set_charge(atom, charge).unwrap();
+            }
+        }
+
+        Ok(reader)
+    }
+}
+
+struct V3000;
+
+impl V3000 {
+    fn read(lines: &[&str], offset: usize) -> Result<Reader, MolfileError> {
+        let error = |k: usize, message| MolfileError {
+            line: offset + k + 1,
+            message,
+        };
+
+        // Join continuation lines, which end in "-"
+        let mut entries: Vec<(usize, String)> = Vec::new();
+        let mut continued = false;
+        for (k, &line) in lines.iter().enumerate().skip(4) {
+            let Some(entry) = line.strip_prefix("M  V30 ") else {
+                continue;
+            };
+            let (entry, continues) = match entry.strip_suffix('-') {
+                Some(entry) => (entry, true),
+                None => (entry, false),
+            };
+            if continued {
+                entries.last_mut().unwrap().1.push_str(entry);
+            } else {
+                entries.push((k, entry.to_owned()));
+            }
+            continued = continues;
+        }
+
+        let mut reader = Reader::default();
+        let mut block = "";
+        for (k, entry) in &entries {
+            let k = *k;
+            let fields = entry.split_whitespace().collect::<Vec<_>>();
+            match fields.as_slice() {
+                ["BEGIN", name] => block = name,
+                ["END", _] => block = "",
+                _ if block == "ATOM" => {
+                    let [_, symbol, x, y, _z, _map, attributes @ ..] = fields.as_slice() else {
+                        return Err(error(k, "Expected an atom"));
+                    };
+                    let coordinate = |s: &str| {
+                        s.parse::<f64>()
+                            .map_err(|_| error(k, "Expected a coordinate"))
+                    };
+                    reader
+                        .add_atom(symbol, coordinate(x)?, coordinate(y)?)
+                        .map_err(|message| error(k, message))?;
+
+                    let atom = reader.molfile.graph.atoms.len();
+                    for attribute in attributes {
+                        let result = match attribute.split_once('=') {
+                            Some(("CHG", value)) => value
+                                .parse()
+                                .map_err(|_| "Expected a number")
+                                .and_then(|charge| reader.set_charge(atom, charge)),
+                            Some(("RAD", value)) => value
+                                .parse()
+                                .map_err(|_| "Expected a number")
+                                .and_then(|radical| reader.set_radical(atom, radical)),
+                            _ => Ok(()),
+                        };
+                        result.map_err(|message| error(k, message))?;
+                    }
+                }
+                _ if block == "BOND" => {
+                    let [_, bond_type, a, b, ..] = fields.as_slice() else {
+                        return Err(error(k, "Expected a bond"));
+                    };
+                    let number = |s: &str| s.parse().map_err(|_| error(k, "Expected a number"));
+                    reader
+                        .add_bond(number(a)?, number(b)?, number(bond_type)? as u8)
+                        .map_err(|message| error(k, message))?;
+                }
+                _ => {}
+            }
+        }
+
+        Ok(reader)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::{write_sdf, Molfile, SdfRecord, Version};
+    use super::parse_sdf;
+    use crate::{
+        graph::Graph,
+        parser::parse,
+        test::{CAFFEINE, DOPAMINE, ISOPROPANOL},
+    };
+
+    #[test]
+    fn test_round_trip() {
+        for name in [ISOPROPANOL, DOPAMINE, CAFFEINE] {
+            let graph = Graph::from(&*parse(name));
+            for version in [Version::V2000, Version::V3000] {
+                let molfile = Molfile::new(name, graph.clone()).write(version);
+                let round_trip = molfile.parse::<Molfile>().unwrap();
+                assert_eq!(round_trip.name, name);
+                assert_eq!(round_trip.graph.canonical_key(), graph.canonical_key());
+            }
+        }
+    }
+
+    #[test]
+    fn test_charges_and_radicals() {
+        for smiles in ["C[O-]", "C[NH3+]", "[CH3]", "[CH2]"] {
+            let graph = Graph::from_smiles(smiles).unwrap();
+            for version in [Version::V2000, Version::V3000] {
+                let molfile = Molfile::new(smiles, graph.clone()).write(version);
+                let round_trip = molfile.parse::<Molfile>().unwrap();
+                assert_eq!(round_trip.graph.canonical_key(), graph.canonical_key());
+            }
+        }
+    }
+
+    #[test]
+    fn test_aromatic_bonds() {
+        let molfile = "benzene\n\n\n  6  6  0  0  0  0  0  0  0  0999 V2000\n".to_owned()
+            + &"    0.0000    0.0000    0.0000 C   0  0  0  0  0  0  0  0  0  0  0  0\n".repeat(6)
+            + "  1  2  4  0\n  2  3  4  0\n  3  4  4  0\n"
+            + "  4  5  4  0\n  5  6  4  0\n  6  1  4  0\nM  END\n";
+        let graph = molfile.parse::<Molfile>().unwrap().graph;
+        assert_eq!(
+            graph.canonical_key(),
+            Graph::from_smiles("c1ccccc1").unwrap().canonical_key(),
+        );
+    }
+
+    #[test]
+    fn test_sdf() {
+        let records = [ISOPROPANOL, CAFFEINE].map(|name| {
+            let graph = Graph::from(&*parse(name));
+            let mut record = SdfRecord::new(Molfile::new(name, graph));
+            record.data.push(("NAME".to_owned(), name.to_owned()));
+            record
+                .data
+                .push(("SMILES".to_owned(), record.molfile.graph.smiles()));
+            record
+        });
+        let sdf = write_sdf(&records, Version::V2000);
+        let round_trip = parse_sdf(&sdf).unwrap();
+        assert_eq!(round_trip.len(), 2);
+        for (record, original) in round_trip.iter().zip(&records) {
+            assert_eq!(record.field("NAME"), original.field("NAME"));
+            assert_eq!(record.field("SMILES"), original.field("SMILES"));
+            assert_eq!(
+                record.molfile.graph.canonical_key(),
+                original.molfile.graph.canonical_key(),
+            );
+        }
+    }
+
+    #[test]
+    fn test_errors() {
+        let molfile = "\n\n\n  1  0  0  0  0  0  0  0  0  0999 V2000\n    0.0000    0.0000    0.0000 Xx  0  0\nM  END\n";
+        let error = molfile.parse::<Molfile>().unwrap_err();
+        assert_eq!(error.line, 5);
+        assert_eq!(error.message, "Unknown element");
+
+        let sdf = Molfile::new("", Graph::from_smiles("C").unwrap()).write(Version::V2000);
+        let error = parse_sdf(&sdf).unwrap_err();
+        assert_eq!(error.message, "Record without a closing $$$$");
+    }
+}
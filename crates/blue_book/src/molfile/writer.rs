@@ -0,0 +1,140 @@
+use std::fmt::Write;
+
+use super::{Molfile, SdfRecord, Version};
+
+const PROGRAM: &str = "BlueBook";
+
+impl Molfile {
+    pub fn write(&self, version: Version) -> String {
+        let mut graph = self.graph.clone();
+        // Structures without a Kekulé form are written with single bonds
+        let _ = graph.kekulize();
+
+        let atoms = (0..graph.atoms.len())
+            .filter(|&i| !graph.is_terminal_hydrogen(i))
+            .collect::<Vec<_>>();
+        // Atoms are numbered from one
+        let number = |i: usize| atoms.binary_search(&i).unwrap() + 1;
+        let bonds = graph
+            .bonds
+            .iter()
+            .filter(|&&(a, b)| atoms.binary_search(&a).is_ok() && atoms.binary_search(&b).is_ok())
+            .map(|&(a, b)| (number(a), number(b), graph.bond_order(a, b)))
+            .collect::<Vec<_>>();
+        let coordinate = |i: usize| self.coordinates.get(i).copied().unwrap_or_default();
+        let charges = atoms
+            .iter()
+            .filter(|&&i| graph.charge(i) != 0)
+            .map(|&i| (number(i), graph.charge(i) as i32))
+            .collect::<Vec<_>>();
+        // Doublets for one free valence, triplets for two
+        let radicals = atoms
+            .iter()
+            .filter_map(
+                |&i| match graph.free_valences.iter().filter(|&&j| j == i).count() {
+                    0 => None,
+                    1 => Some((number(i), 2)),
+                    _ => Some((number(i), 3)),
+                },
+            )
+            .collect::<Vec<_>>();
+
+        let mut molfile = String::new();
+        writeln!(molfile, "{}", self.name).unwrap();
+        writeln!(molfile, "  {PROGRAM:<8}{:10}2D", "").unwrap();
+        writeln!(molfile).unwrap();
+
+        match version {
+            Version::V2000 => {
+                writeln!(
+                    molfile,
+                    "{:3}{:3}  0  0  0  0  0  0  0  0999 V2000",
+                    atoms.len(),
+                    bonds.len(),
+                )
+                .unwrap();
+                for &i in &atoms {
+                    let [x, y] = coordinate(i);
+                    let symbol = graph.atoms[i].symbol();
+                    // The old-style charge field, superseded by the M  CHG lines
+                    let charge = match graph.charge(i) as i32 {
+                        0 => 0,
+                        charge @ -3..=3 => 4 - charge,
+                        _ => 0,
+                    };
+                    writeln!(
+                        molfile,
+                        "{x:10.4}{y:10.4}{:10.4} {symbol:<3} 0{charge:3}  0  0  0  0  0  0  0  0  0  0",
+                        0.0,
+                    )
+                    .unwrap();
+                }
+                for &(a, b, order) in &bonds {
+                    writeln!(molfile, "{a:3}{b:3}{order:3}  0").unwrap();
+                }
+                write_properties(&mut molfile, "CHG", &charges);
+                write_properties(&mut molfile, "RAD", &radicals);
+            }
+            Version::V3000 => {
+                writeln!(molfile, "  0  0  0     0  0            999 V3000").unwrap();
+                writeln!(molfile, "M  V30 BEGIN CTAB").unwrap();
+                writeln!(
+                    molfile,
+                    "M  V30 COUNTS {} {} 0 0 0",
+                    atoms.len(),
+                    bonds.len()
+                )
+                .unwrap();
+                writeln!(molfile, "M  V30 BEGIN ATOM").unwrap();
+                for &i in &atoms {
+                    let [x, y] = coordinate(i);
+                    let symbol = graph.atoms[i].symbol();
+                    write!(molfile, "M  V30 {} {symbol} {x:.4} {y:.4} 0 0", number(i)).unwrap();
+                    if let Some(&(_, charge)) = charges.iter().find(|&&(n, _)| n == number(i)) {
+                        write!(molfile, " CHG={charge}").unwrap();
+                    }
+                    if let Some(&(_, radical)) = radicals.iter().find(|&&(n, _)| n == number(i)) {
+                        write!(molfile, " RAD={radical}").unwrap();
+                    }
+                    writeln!(molfile).unwrap();
+                }
+                writeln!(molfile, "M  V30 END ATOM").unwrap();
+                writeln!(molfile, "M  V30 BEGIN BOND").unwrap();
+                for (k, &(a, b, order)) in bonds.iter().enumerate() {
+                    writeln!(molfile, "M  V30 {} {order} {a} {b}", k + 1).unwrap();
+                }
+                writeln!(molfile, "M  V30 END BOND").unwrap();
+                writeln!(molfile, "M  V30 END CTAB").unwrap();
+            }
+        }
+
+        writeln!(molfile, "M  END").unwrap();
+        molfile
+    }
+}
+
+/// Writes `M  CHG`-style property lines, at most eight entries to a line.
+fn write_properties(molfile: &mut String, name: &str, entries: &[(usize, i32)]) {
+    for chunk in entries.chunks(8) {
+        write!(molfile, "M  {name}{:3}", chunk.len()).unwrap();
+        for (atom, value) in chunk {
+            write!(molfile, " {atom:3} {value:3}").unwrap();
+        }
+        writeln!(molfile).unwrap();
+    }
+}
+
+/// Writes the records of an SD file, each followed by its data fields.
+pub fn write_sdf(records: &[SdfRecord], version: Version) -> String {
+    let mut sdf = String::new();
+    for record in records {
+        sdf.push_str(&record.molfile.write(version));
+        for (name, value) in &record.data {
+            writeln!(sdf, "> <{name}>").unwrap();
+            writeln!(sdf, "{value}").unwrap();
+            writeln!(sdf).unwrap();
+        }
+        writeln!(sdf, "$$$$").unwrap();
+    }
+    sdf
+}
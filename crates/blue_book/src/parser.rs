@@ -2,8 +2,14 @@ use std::rc::Rc;
 
 use crate::{
     chapters::{
+        p_10_natural_products::p_103_amino_acids::AminoAcid,
+        p_1_general::p_13_operations::p_13_2_subtractive_operations::SubtractivePrefix,
         p_2_hydrides::{p_21_simple_hydrides::p_21_2_acyclic_hydrides::alkane, Hydride},
-        p_3_substituent_groups::CharacteristicGroup,
+        p_3_substituent_groups::{p_31_1_4_hydro_prefixes::HydroPrefix, CharacteristicGroup},
+        p_6_specific_classes::p_65_acids_and_derivatives::p_65_1_7_acyl_groups::{
+            AcylGroup, AcylSuffix,
+        },
+        p_8_isotopically_modified_compounds::Nuclide,
     },
     scanner::{scan, uncapitalize, Token},
     Element, Locant,
@@ -12,15 +18,29 @@ use crate::{
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub enum AST {
     Hydride(Hydride),
-    Group(Rc<AST>),
+    Group(u8, Vec<Locant>, Rc<AST>),
     CharacteristicGroup(CharacteristicGroup),
-    Unsaturated(u8, Rc<AST>),
+    Unsaturated(u8, Vec<Locant>, Rc<AST>),
     Substitution(Locant, Rc<AST>, Rc<AST>),
+    Isotopic(Nuclide, Rc<AST>),
+    IndicatedHydrogen(Vec<Locant>, Rc<AST>),
+    Hydro(HydroPrefix, Vec<Locant>, Rc<AST>),
+    Subtractive(SubtractivePrefix, Vec<Locant>, Rc<AST>),
+    Acyl(AcylSuffix, Vec<Locant>, Rc<AST>),
+    AcylGroup(AcylGroup),
+    Acid(AcylSuffix, Vec<Locant>, Rc<AST>),
+    AminoAcid(AminoAcid),
 }
 
 #[derive(Debug, Default)]
 pub(crate) struct State {
     pub stack: Vec<StackItem>,
+    /// Substituents on the heteroatom of a characteristic group, as in
+    /// "N-methylethanamine", or on a substituent group, as in "1'-hydroxy",
+    /// which wait for the group to be attached.
+    pub deferred_substituents: Vec<(Locant, Rc<AST>)>,
+    /// Subtractive prefixes, as in "2-deoxy", which apply to the whole name.
+    pub subtractive_prefixes: Vec<(SubtractivePrefix, Vec<Locant>)>,
 }
 
 #[derive(Debug, PartialEq, Eq)]
@@ -30,6 +50,10 @@ pub(crate) enum StackItem {
     OpenBracket,
     Locant(Locant),
     Multiplicity(u16),
+    Nuclide(Nuclide),
+    Hydro(HydroPrefix),
+    Subtractive(SubtractivePrefix),
+    Cyclo,
 }
 
 pub fn parse(name: &str) -> Rc<AST> {
@@ -42,15 +66,14 @@ pub fn parse(name: &str) -> Rc<AST> {
                 state.stack.push(StackItem::OpenBracket);
             }
             Token::CloseBracket => {
-                let mut hydride_positions = Vec::new();
+                let mut added_hydrogen = Vec::new();
                 while let Some(&StackItem::Locant(position)) = state.stack.last() {
                     state.stack.pop();
-                    // In molecules such as Thymine, this token is used to
-                    // indicate which atoms receive hydrogen atoms.
+                    // Added hydrogen, as in "pyrimidine-2,4(1H,3H)-dione"
                     let Locant::Element(n, Element::Hydrogen) = position else {
-                        panic!("Unexpected position in brackets (expected a hydride marker): {position:?}")
+                        panic!("Unexpected position in brackets (expected added hydrogen): {position:?}")
                     };
-                    hydride_positions.push(Locant::Number(n));
+                    added_hydrogen.push(Locant::Number(n));
                 }
 
                 if matches!(state.stack.last(), Some(StackItem::OpenBracket)) {
@@ -65,23 +88,9 @@ pub fn parse(name: &str) -> Rc<AST> {
                     state.stack.push(StackItem::Molecule(molecule));
                 }
 
-                if !hydride_positions.is_empty() {
-                    let StackItem::Molecule(molecule) = state
-                        .stack
-                        .iter_mut()
-                        .rfind(|i| matches!(i, StackItem::Molecule(_)))
-                        .unwrap()
-                    else {
-                        unreachable!()
-                    };
-                    for position in hydride_positions {
-                        *molecule = AST::Substitution(
-                            position,
-                            AST::CharacteristicGroup(CharacteristicGroup::Hydro).into(),
-                            molecule.clone(),
-                        )
-                        .into();
-                    }
+                if !added_hydrogen.is_empty() {
+                    added_hydrogen.reverse();
+                    state.add_hydrogen(added_hydrogen);
                 }
             }
 
@@ -93,22 +102,63 @@ pub fn parse(name: &str) -> Rc<AST> {
             }
 
             Token::Unsaturated(unsaturated) => {
-                let mut molecule = state.pop_molecule();
+                // Locants of the ending, as in "buta-1,3-diene"
+                let positions = state.pop_suffix_positions();
+                // The ending belongs to the parent hydride alone, before its
+                // prefixes, so that "dihydroethene" saturates the double bond
+                let mut molecule = state.pop_parent();
                 if unsaturated != 0 {
-                    molecule = AST::Unsaturated(unsaturated, molecule).into();
+                    molecule = AST::Unsaturated(unsaturated, positions, molecule).into();
                 }
                 state.stack.push(StackItem::Molecule(molecule));
             }
-            Token::FreeValence => {
+            Token::FreeValence(order) => {
+                // Locants of the free valences, as in "ethane-1,2-diyl"
+                let positions = state.pop_suffix_positions();
                 let base = state.pop_molecule();
-                let molecule = AST::Group(base).into();
+                // An isotope descriptor before a substituent group, as in
+                // "(²H₃)methyl", labels the group
+                let molecule = state.pop_nuclides(AST::Group(order, positions, base).into());
                 state.stack.push(StackItem::Molecule(molecule));
             }
 
+            Token::Acyl(suffix) => {
+                let molecule = state.pop_acyl(suffix);
+                let molecule = state.pop_nuclides(molecule.into());
+                state.stack.push(StackItem::Molecule(molecule));
+            }
+            Token::AcylGroup(group) => {
+                let molecule = state.pop_nuclides(AST::AcylGroup(group).into());
+                state.stack.push(StackItem::Molecule(molecule));
+            }
+            Token::Acid(suffix) => {
+                let molecule = state.pop_acid(suffix);
+                state.stack.push(StackItem::Molecule(molecule.into()));
+            }
+            Token::AminoAcid(amino_acid) => {
+                state
+                    .stack
+                    .push(StackItem::Molecule(AST::AminoAcid(amino_acid).into()));
+            }
+
+            Token::Nuclide(nuclide) => {
+                state.stack.push(StackItem::Nuclide(nuclide));
+            }
+
             Token::Hydride(hydride) => {
-                let molecule = AST::Hydride(hydride).into();
+                let hydride = state.pop_cyclo(hydride);
+                let molecule = state.pop_indicated_hydrogen(AST::Hydride(hydride).into());
                 state.stack.push(StackItem::Molecule(molecule));
             }
+            Token::Hydro(prefix) => {
+                state.stack.push(StackItem::Hydro(prefix));
+            }
+            Token::Subtractive(prefix) => {
+                state.stack.push(StackItem::Subtractive(prefix));
+            }
+            Token::Cyclo => {
+                state.stack.push(StackItem::Cyclo);
+            }
             Token::Prefix(group) => {
                 let group = AST::CharacteristicGroup(group).into();
                 state.stack.push(StackItem::Molecule(group));
@@ -116,8 +166,17 @@ pub fn parse(name: &str) -> Rc<AST> {
             Token::Suffix(group) => {
                 let group: Rc<_> = AST::CharacteristicGroup(group).into();
 
-                let positions = state.pop_multiplicity_and_positions().collect::<Vec<_>>();
+                let mut positions = state.pop_multiplicity_and_positions().collect::<Vec<_>>();
                 let mut molecule = state.pop_molecule();
+                // The locants may also come before the parent, as in "2-propanol"
+                if positions.iter().all(|&pos| pos == Locant::Unspecified) {
+                    for pos in &mut positions {
+                        if let Some(&StackItem::Locant(locant)) = state.stack.last() {
+                            state.stack.pop();
+                            *pos = locant;
+                        }
+                    }
+                }
                 for pos in positions {
                     molecule = AST::Substitution(pos, group.clone(), molecule).into();
                 }
@@ -127,39 +186,91 @@ pub fn parse(name: &str) -> Rc<AST> {
         }
     }
 
-    let molecule = state.pop_molecule();
+    let mut molecule = state.pop_molecule();
+    for (pos, group) in std::mem::take(&mut state.deferred_substituents) {
+        molecule = AST::Substitution(pos, group, molecule).into();
+    }
+    for (prefix, locants) in std::mem::take(&mut state.subtractive_prefixes) {
+        molecule = AST::Subtractive(prefix, locants, molecule).into();
+    }
+    let molecule = state.pop_nuclides(molecule);
     assert!(state.stack.is_empty(), "unbalanced stack: {state:?}");
     molecule
 }
 
 impl State {
-    fn pop_molecule(&mut self) -> Rc<AST> {
-        let mut molecule;
+    /// Pops the parent hydride, without its prefixes.
+    fn pop_parent(&mut self) -> Rc<AST> {
         match self.stack.last().unwrap() {
             StackItem::Molecule(mol) => {
-                molecule = mol.clone();
+                let molecule = mol.clone();
                 self.stack.pop();
+                molecule
             }
             StackItem::Multiplicity(_) => {
                 let num = self.pop_multiplicity();
-                molecule = AST::Hydride(alkane(num).into()).into();
+                let hydride = self.pop_cyclo(alkane(num).into());
+                AST::Hydride(hydride).into()
+            }
+            item => panic!("expected a parent hydride, found {item:?}"),
+        }
+    }
+
+    /// Pops the locants of an ending or a free valence suffix, if any, along
+    /// with its multiplicity. The multiplicity is the suffix's when it follows
+    /// a locant or the parent hydride, as in "ethanediyl", and otherwise names
+    /// the parent, as in "hexyl".
+    fn pop_suffix_positions(&mut self) -> Vec<Locant> {
+        let has_positions = match self.stack.as_slice() {
+            [.., StackItem::Locant(_)] | [.., StackItem::Locant(_), StackItem::Multiplicity(_)] => {
+                true
             }
-            _ => todo!(),
+            [.., StackItem::Molecule(parent), StackItem::Multiplicity(_)] => matches!(
+                **parent,
+                AST::Hydride(_) | AST::Unsaturated(..) | AST::IndicatedHydrogen(..)
+            ),
+            _ => false,
         };
+        if !has_positions {
+            return vec![Locant::Unspecified];
+        }
+        let mut positions = self.pop_multiplicity_and_positions().collect::<Vec<_>>();
+        positions.reverse();
+        positions
+    }
 
-        while let Some(StackItem::Molecule(group)) = self.stack.last() {
-            let group = group.clone();
+    pub(crate) fn pop_molecule(&mut self) -> Rc<AST> {
+        let mut molecule = self.pop_parent();
+
+        loop {
+            let group = match self.stack.last() {
+                Some(StackItem::Molecule(group)) => group.clone(),
+                Some(StackItem::Hydro(_)) => {
+                    molecule = self.pop_hydro_prefix(molecule);
+                    continue;
+                }
+                Some(StackItem::Subtractive(_)) => {
+                    self.pop_subtractive_prefix();
+                    continue;
+                }
+                _ => break,
+            };
             self.stack.pop();
 
-            for pos in self.pop_multiplicity_and_positions() {
-                molecule = AST::Substitution(pos, group.clone(), molecule).into();
+            let positions = self.pop_multiplicity_and_positions().collect::<Vec<_>>();
+            for pos in positions {
+                if matches!(pos, Locant::Heteroatom(..)) || pos.is_primed() {
+                    self.deferred_substituents.push((pos, group.clone()));
+                } else {
+                    molecule = AST::Substitution(pos, group.clone(), molecule).into();
+                }
             }
         }
 
         molecule
     }
 
-    fn pop_multiplicity_and_positions(&mut self) -> impl Iterator<Item = Locant> + '_ {
+    pub(crate) fn pop_multiplicity_and_positions(&mut self) -> impl Iterator<Item = Locant> + '_ {
         let multiplicity = self.pop_multiplicity();
 
         (0..multiplicity).map(|_| {
@@ -186,7 +297,7 @@ mod tests {
                 p_25_fused_ring_systems::p_25_2_heterocyclic_ring_components::HeterocyclicRing::Purine,
                 Hydride::Isobutane,
             },
-            p_3_substituent_groups::CharacteristicGroup,
+            p_3_substituent_groups::{p_31_1_4_hydro_prefixes::HydroPrefix, CharacteristicGroup},
         },
         test::{ADENINE, CAFFEINE, CYTOSINE, DOPAMINE, GUANINE, SALBUTAMOL, THYMINE},
         Locant,
@@ -200,29 +311,64 @@ mod tests {
 
         assert_eq!(
             parse("Ethene"),
-            AST::Unsaturated(1, AST::Hydride(ETHANE.into()).into()).into(),
+            AST::Unsaturated(
+                1,
+                vec![Locant::Unspecified],
+                AST::Hydride(ETHANE.into()).into()
+            )
+            .into(),
         );
 
         assert_eq!(
             parse("Hexamethylpentane"),
             AST::Substitution(
                 Locant::Unspecified,
-                AST::Group(AST::Hydride(METHANE.into()).into()).into(),
+                AST::Group(
+                    1,
+                    vec![Locant::Unspecified],
+                    AST::Hydride(METHANE.into()).into()
+                )
+                .into(),
                 AST::Substitution(
                     Locant::Unspecified,
-                    AST::Group(AST::Hydride(METHANE.into()).into()).into(),
+                    AST::Group(
+                        1,
+                        vec![Locant::Unspecified],
+                        AST::Hydride(METHANE.into()).into()
+                    )
+                    .into(),
                     AST::Substitution(
                         Locant::Unspecified,
-                        AST::Group(AST::Hydride(METHANE.into()).into()).into(),
+                        AST::Group(
+                            1,
+                            vec![Locant::Unspecified],
+                            AST::Hydride(METHANE.into()).into()
+                        )
+                        .into(),
                         AST::Substitution(
                             Locant::Unspecified,
-                            AST::Group(AST::Hydride(METHANE.into()).into()).into(),
+                            AST::Group(
+                                1,
+                                vec![Locant::Unspecified],
+                                AST::Hydride(METHANE.into()).into()
+                            )
+                            .into(),
                             AST::Substitution(
                                 Locant::Unspecified,
-                                AST::Group(AST::Hydride(METHANE.into()).into()).into(),
+                                AST::Group(
+                                    1,
+                                    vec![Locant::Unspecified],
+                                    AST::Hydride(METHANE.into()).into()
+                                )
+                                .into(),
                                 AST::Substitution(
                                     Locant::Unspecified,
-                                    AST::Group(AST::Hydride(METHANE.into()).into()).into(),
+                                    AST::Group(
+                                        1,
+                                        vec![Locant::Unspecified],
+                                        AST::Hydride(METHANE.into()).into()
+                                    )
+                                    .into(),
                                     AST::Hydride(alkane(5).into()).into(),
                                 )
                                 .into(),
@@ -242,10 +388,20 @@ mod tests {
             parse("2,2-Dimethylpropane"),
             AST::Substitution(
                 Locant::Number(2),
-                AST::Group(AST::Hydride(METHANE.into()).into()).into(),
+                AST::Group(
+                    1,
+                    vec![Locant::Unspecified],
+                    AST::Hydride(METHANE.into()).into()
+                )
+                .into(),
                 AST::Substitution(
                     Locant::Number(2),
-                    AST::Group(AST::Hydride(METHANE.into()).into()).into(),
+                    AST::Group(
+                        1,
+                        vec![Locant::Unspecified],
+                        AST::Hydride(METHANE.into()).into()
+                    )
+                    .into(),
                     AST::Hydride(PROPANE.into()).into(),
                 )
                 .into(),
@@ -255,7 +411,12 @@ mod tests {
 
         assert_eq!(
             parse("Pentyne"),
-            AST::Unsaturated(2, AST::Hydride(alkane(5).into()).into()).into(),
+            AST::Unsaturated(
+                2,
+                vec![Locant::Unspecified],
+                AST::Hydride(alkane(5).into()).into()
+            )
+            .into(),
         );
     }
 
@@ -272,6 +433,8 @@ mod tests {
                     AST::Substitution(
                         Locant::Number(4),
                         AST::Group(
+                            1,
+                            vec![Locant::Unspecified],
                             AST::Substitution(
                                 Locant::Number(2),
                                 AST::CharacteristicGroup(CharacteristicGroup::Amino).into(),
@@ -298,12 +461,19 @@ mod tests {
                     Locant::Number(4),
                     // 2-(tert-Butylamino)-1-hydroxyethyl
                     AST::Group(
+                        1,
+                        vec![Locant::Unspecified],
                         AST::Substitution(
                             Locant::Number(2),
                             // tert-Butylamino
                             AST::Substitution(
                                 Locant::Unspecified,
-                                AST::Group(AST::Hydride(Isobutane).into()).into(),
+                                AST::Group(
+                                    1,
+                                    vec![Locant::Unspecified],
+                                    AST::Hydride(Isobutane).into()
+                                )
+                                .into(),
                                 AST::CharacteristicGroup(CharacteristicGroup::Amino).into(),
                             )
                             .into(),
@@ -322,6 +492,8 @@ mod tests {
                     AST::Substitution(
                         Locant::Number(2),
                         AST::Group(
+                            1,
+                            vec![Locant::Unspecified],
                             // Hydroxymethane
                             AST::Substitution(
                                 Locant::Unspecified,
@@ -351,22 +523,36 @@ mod tests {
                     // 1,3,7-Trimethyl-3,7-dihydro-1H-purine
                     AST::Substitution(
                         Locant::Number(1),
-                        AST::Group(AST::Hydride(METHANE.into()).into()).into(),
+                        AST::Group(
+                            1,
+                            vec![Locant::Unspecified],
+                            AST::Hydride(METHANE.into()).into()
+                        )
+                        .into(),
                         AST::Substitution(
                             Locant::Number(3),
-                            AST::Group(AST::Hydride(METHANE.into()).into()).into(),
+                            AST::Group(
+                                1,
+                                vec![Locant::Unspecified],
+                                AST::Hydride(METHANE.into()).into()
+                            )
+                            .into(),
                             AST::Substitution(
                                 Locant::Number(7),
-                                AST::Group(AST::Hydride(METHANE.into()).into()).into(),
+                                AST::Group(
+                                    1,
+                                    vec![Locant::Unspecified],
+                                    AST::Hydride(METHANE.into()).into()
+                                )
+                                .into(),
                                 // 3,7-Dihydro-1H-purine
-                                AST::Substitution(
-                                    Locant::Number(3),
-                                    AST::CharacteristicGroup(CharacteristicGroup::Hydro).into(),
-                                    AST::Substitution(
-                                        Locant::Number(7),
-                                        AST::CharacteristicGroup(CharacteristicGroup::Hydro).into(),
-                                        // 1H-Purine
-                                        AST::Hydride(Purine(1).into()).into(),
+                                AST::Hydro(
+                                    HydroPrefix::Hydro,
+                                    vec![Locant::Number(3), Locant::Number(7)],
+                                    // 1H-Purine
+                                    AST::IndicatedHydrogen(
+                                        vec![Locant::Number(1)],
+                                        AST::Hydride(Purine.into()).into(),
                                     )
                                     .into(),
                                 )
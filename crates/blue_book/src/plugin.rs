@@ -2,16 +2,27 @@ use parsing::dfa;
 
 use crate::{
     chapters::{
-        p_1_general::p_14_general_rules::p_14_2_multiplicative_prefixes,
+        p_10_natural_products::p_103_amino_acids,
+        p_1_general::{
+            p_13_operations::p_13_2_subtractive_operations,
+            p_14_general_rules::p_14_2_multiplicative_prefixes,
+        },
         p_2_hydrides::{
             p_21_simple_hydrides::{p_21_1_mononuclear_hydrides, p_21_2_acyclic_hydrides},
             p_22_monocyclic_hydrides::{
                 p_22_1_monocyclic_hydocarbons, p_22_2_heteromonocyclic_hydrides,
             },
-            p_25_fused_ring_systems::p_25_2_heterocyclic_ring_components,
+            p_25_fused_ring_systems::{
+                p_25_1_polycyclic_hydrocarbons, p_25_2_heterocyclic_ring_components,
+            },
             p_29_hydride_prefixes::p_29_2_general_names,
         },
-        p_3_substituent_groups::{p_33_suffixes, p_35_characteristic_group_prefixes},
+        p_3_substituent_groups::{
+            p_31_1_4_hydro_prefixes, p_33_suffixes, p_35_characteristic_group_prefixes,
+        },
+        p_6_specific_classes::p_65_acids_and_derivatives::{
+            p_65_1_1_carboxylic_acids, p_65_1_7_acyl_groups,
+        },
     },
     scanner::Token,
 };
@@ -21,13 +32,19 @@ pub trait Plugin {
 }
 
 pub const PLUGINS: &[&dyn Plugin] = &[
+    &p_13_2_subtractive_operations::SubtractivePrefixesPlugin,
     &p_14_2_multiplicative_prefixes::MultiplicativePrefixesPlugin,
     &p_21_1_mononuclear_hydrides::MononuclearHydridesPlugin,
     &p_21_2_acyclic_hydrides::AcyclicHydridesPlugin,
     &p_22_1_monocyclic_hydocarbons::MonocyclicHydrocarbonsPlugin,
     &p_22_2_heteromonocyclic_hydrides::HeteromonocyclicHydridesPlugin,
+    &p_25_1_polycyclic_hydrocarbons::PolycyclicHydrocarbonsPlugin,
     &p_25_2_heterocyclic_ring_components::HeterocyclicRingPlugin,
     &p_29_2_general_names::GeneralHydridePrefixesPlugin,
+    &p_31_1_4_hydro_prefixes::HydroPrefixesPlugin,
     &p_33_suffixes::SuffixesPlugin,
     &p_35_characteristic_group_prefixes::CharacteristicGroupPrefixesPlugin,
+    &p_65_1_1_carboxylic_acids::CarboxylicAcidsPlugin,
+    &p_65_1_7_acyl_groups::AcylGroupsPlugin,
+    &p_103_amino_acids::AminoAcidsPlugin,
 ];
@@ -3,7 +3,16 @@ use lazy_static::lazy_static;
 use parsing::dfa;
 
 use crate::{
-    chapters::{p_2_hydrides::Hydride, p_3_substituent_groups::CharacteristicGroup},
+    chapters::{
+        p_10_natural_products::p_103_amino_acids::AminoAcid,
+        p_1_general::p_13_operations::p_13_2_subtractive_operations::SubtractivePrefix,
+        p_2_hydrides::Hydride,
+        p_3_substituent_groups::{p_31_1_4_hydro_prefixes::HydroPrefix, CharacteristicGroup},
+        p_6_specific_classes::p_65_acids_and_derivatives::p_65_1_7_acyl_groups::{
+            AcylGroup, AcylSuffix,
+        },
+        p_8_isotopically_modified_compounds::{scan_descriptor, Nuclide},
+    },
     plugin::PLUGINS,
     Element, Locant,
 };
@@ -21,15 +30,32 @@ pub enum Token {
     Multiplicity(u16),
     /// "ane", "ene", "yne"
     Unsaturated(u8),
-    /// "yl"
-    FreeValence,
-
-    /// A parent hydride: "borane", "ethane", "cyclohexane", etc.
+    /// "yl", "ylidene", "ylidyne", by the order of the bond to the parent
+    FreeValence(u8),
+    /// "oyl", "carbonyl"
+    Acyl(AcylSuffix),
+    /// A retained acyl group: "acetyl", "benzoyl", etc.
+    AcylGroup(AcylGroup),
+    /// "oic acid", "carboxylic acid"
+    Acid(AcylSuffix),
+    /// An amino acid with a retained name: "glycine", "alanine"
+    AminoAcid(AminoAcid),
+
+    /// A parent hydride: "borane", "ethane", "benzene", etc.
     Hydride(Hydride),
+    /// "cyclo", closing the parent hydride after it into a ring
+    Cyclo,
+    /// "hydro", "dehydro", "perhydro"
+    Hydro(HydroPrefix),
+    /// "deoxy", "nor", "anhydro", "demethyl"
+    Subtractive(SubtractivePrefix),
     /// A named base in prefix form: "hydroxy", "amino", etc.
     Prefix(CharacteristicGroup),
     /// A named base in suffix form: "hydroxy", "amine", etc.
     Suffix(CharacteristicGroup),
+
+    /// One nuclide of an isotope descriptor: "(²H₃)", "[1-¹⁴C]", etc.
+    Nuclide(Nuclide),
 }
 
 lazy_static! {
@@ -51,7 +77,7 @@ lazy_static! {
 
     static ref ELEMENTS: dfa::Automaton<Element> = {
         let mut dfa = dfa::Automaton::new();
-        for &element in crate::ELEMENTS {
+        for &element in crate::ELEMENTS.iter().filter(|element| element.is_in_scope()) {
             dfa.insert(element.symbol(), element);
         }
         dfa
@@ -60,7 +86,10 @@ lazy_static! {
 
 #[derive(Debug)]
 pub struct Scanner<'input> {
+    name: &'input str,
     input: &'input str,
+    /// The rest of the nuclides of the last isotope descriptor, in reverse.
+    nuclides: Vec<Nuclide>,
 }
 
 /// Undoes the capitalization mentioned in P-16.0 (Name writing / Introduction).
@@ -99,11 +128,15 @@ pub fn uncapitalize(input: &str) -> String {
     input.to_owned()
 }
 
-pub fn scan(input: &str) -> Scanner {
+pub fn scan(input: &str) -> Scanner<'_> {
     // Trim common stereochemistry prefixes
     let input = input.trim_start_matches("(RS)-");
 
-    Scanner { input }
+    Scanner {
+        name: input,
+        input,
+        nuclides: Vec::new(),
+    }
 }
 
 fn is_vowel(c: char) -> bool {
@@ -114,6 +147,10 @@ impl<'input> Iterator for Scanner<'input> {
     type Item = Token;
 
     fn next(&mut self) -> Option<Self::Item> {
+        if let Some(nuclide) = self.nuclides.pop() {
+            return Some(Token::Nuclide(nuclide));
+        }
+
         while let Some(c) = self.input.chars().next() {
             let len = c.len_utf8();
             match c {
@@ -124,6 +161,18 @@ impl<'input> Iterator for Scanner<'input> {
             }
         }
 
+        // Isotope descriptors written without superscripts, as in
+        // "(2H3)methanol", would be confused with the indicated hydrogens of
+        // "2,4(1H,3H)-dione" if they followed a locant
+        let offset = self.name.len() - self.input.len();
+        let plain = !self.name[..offset].ends_with(|c: char| c.is_ascii_digit());
+        if let Some((mut nuclides, len)) = scan_descriptor(self.input, plain) {
+            self.input = &self.input[len..];
+            nuclides.reverse();
+            self.nuclides = nuclides;
+            return self.next();
+        }
+
         if let Some((len, &token)) = TOKENS.get_by_prefix(self.input) {
             self.input = &self.input[len..];
             return Some(token);
@@ -141,6 +190,9 @@ impl<'input> Iterator for Scanner<'input> {
             let pos = if let Some((len, &element)) = ELEMENTS.get_by_prefix(self.input) {
                 self.input = &self.input[len..];
                 Locant::Element(num, element)
+            } else if let Some((pos, len)) = Locant::scan_suffix(Some(num), self.input) {
+                self.input = &self.input[len..];
+                pos
             } else {
                 Locant::Number(num)
             };
@@ -148,6 +200,11 @@ impl<'input> Iterator for Scanner<'input> {
             return Some(Token::Locant(pos));
         }
 
+        if let Some((pos, len)) = Locant::scan_suffix(None, self.input) {
+            self.input = &self.input[len..];
+            return Some(Token::Locant(pos));
+        }
+
         if let Some(rest) = self.input.strip_prefix(is_vowel) {
             self.input = rest;
             self.next()
@@ -175,11 +232,11 @@ mod tests {
                 p_25_fused_ring_systems::p_25_2_heterocyclic_ring_components::HeterocyclicRing::Purine,
                 Hydride::Isobutane,
             },
-            p_3_substituent_groups::CharacteristicGroup,
+            p_3_substituent_groups::{p_31_1_4_hydro_prefixes::HydroPrefix, CharacteristicGroup},
         },
         scanner::uncapitalize,
         test::{CAFFEINE, DOPAMINE, SALBUTAMOL},
-        Locant,
+        Element, Locant,
     };
 
     use super::{scan, Token};
@@ -201,7 +258,7 @@ mod tests {
             vec![
                 Token::Multiplicity(6),
                 Token::Hydride(METHANE.into()),
-                Token::FreeValence,
+                Token::FreeValence(1),
                 Token::Multiplicity(5),
                 Token::Unsaturated(0),
             ],
@@ -213,6 +270,57 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_scan_locants() {
+        let locants = |name: &str| {
+            scan(name)
+                .filter_map(|token| match token {
+                    Token::Locant(locant) => Some(locant),
+                    _ => None,
+                })
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(
+            locants("1',2''-dimethyl"),
+            [Locant::Primed(1, 1), Locant::Primed(2, 2)],
+        );
+        assert_eq!(
+            locants("3a,4,7,7a-tetrahydro"),
+            [
+                Locant::Letter(3, 'a', 0),
+                Locant::Number(4),
+                Locant::Number(7),
+                Locant::Letter(7, 'a', 0),
+            ],
+        );
+        assert_eq!(
+            locants("4a',8a''-dimethyl"),
+            [Locant::Letter(4, 'a', 1), Locant::Letter(8, 'a', 2)],
+        );
+        assert_eq!(
+            locants("α,ω-dihydroxy"),
+            [Locant::Greek('α', 0), Locant::Greek('ω', 0)],
+        );
+        assert_eq!(locants("α'-hydroxy"), [Locant::Greek('α', 1)]);
+        assert_eq!(
+            locants("N,N'-dimethyl"),
+            [
+                Locant::Heteroatom(Element::Nitrogen, 0),
+                Locant::Heteroatom(Element::Nitrogen, 1),
+            ],
+        );
+        assert_eq!(
+            locants("O-methyl"),
+            [Locant::Heteroatom(Element::Oxygen, 0)]
+        );
+        assert_eq!(locants("1(6)-ene"), [Locant::Compound(1, 6)]);
+        assert_eq!(
+            locants("2(1H)-one"),
+            [Locant::Number(2), Locant::Element(1, Element::Hydrogen)],
+        );
+    }
+
     #[test]
     fn test_scan_complex() {
         assert_eq!(
@@ -223,7 +331,7 @@ mod tests {
                 Token::Locant(Locant::Number(2)),
                 Token::Prefix(CharacteristicGroup::Amino),
                 Token::Hydride(ETHANE.into()),
-                Token::FreeValence,
+                Token::FreeValence(1),
                 Token::CloseBracket,
                 Token::Hydride(Benzene.into()),
                 Token::Locant(Locant::Number(1)),
@@ -241,19 +349,19 @@ mod tests {
                 Token::Locant(Locant::Number(2)),
                 Token::OpenBracket,
                 Token::Hydride(Isobutane),
-                Token::FreeValence,
+                Token::FreeValence(1),
                 Token::Prefix(CharacteristicGroup::Amino),
                 Token::CloseBracket,
                 Token::Locant(Locant::Number(1)),
                 Token::Prefix(CharacteristicGroup::Hydroxy),
                 Token::Hydride(ETHANE.into()),
-                Token::FreeValence,
+                Token::FreeValence(1),
                 Token::CloseBracket,
                 Token::Locant(Locant::Number(2)),
                 Token::OpenBracket,
                 Token::Prefix(CharacteristicGroup::Hydroxy),
                 Token::Hydride(METHANE.into()),
-                Token::FreeValence,
+                Token::FreeValence(1),
                 Token::CloseBracket,
                 Token::Hydride(Benzene.into()),
                 Token::Suffix(CharacteristicGroup::Hydroxy),
@@ -268,12 +376,13 @@ mod tests {
                 Token::Locant(Locant::Number(7)),
                 Token::Multiplicity(3),
                 Token::Hydride(METHANE.into()),
-                Token::FreeValence,
+                Token::FreeValence(1),
                 Token::Locant(Locant::Number(3)),
                 Token::Locant(Locant::Number(7)),
                 Token::Multiplicity(2),
-                Token::Prefix(CharacteristicGroup::Hydro),
-                Token::Hydride(Purine(1).into()),
+                Token::Hydro(HydroPrefix::Hydro),
+                Token::Locant(Locant::Element(1, Element::Hydrogen)),
+                Token::Hydride(Purine.into()),
                 Token::Locant(Locant::Number(2)),
                 Token::Locant(Locant::Number(6)),
                 Token::Multiplicity(2),
@@ -0,0 +1,54 @@
+//! SMILES, as described by the OpenSMILES specification
+//! (<http://opensmiles.org/opensmiles.html>).
+
+use crate::Element;
+
+mod parser;
+mod writer;
+
+pub use parser::SmilesError;
+
+/// The normal valences of the elements that may be written without brackets,
+/// lowest first.
+fn normal_valences(element: Element) -> Option<&'static [usize]> {
+    match element {
+        Element::Boron => Some(&[3]),
+        Element::Carbon => Some(&[4]),
+        Element::Nitrogen | Element::Phosphorus => Some(&[3, 5]),
+        Element::Oxygen => Some(&[2]),
+        Element::Sulfur => Some(&[2, 4, 6]),
+        Element::Fluorine | Element::Chlorine | Element::Bromine | Element::Iodine => Some(&[1]),
+        _ => None,
+    }
+}
+
+/// The lowercase symbol of an element that may take part in an aromatic ring.
+fn aromatic_symbol(element: Element) -> Option<&'static str> {
+    match element {
+        Element::Boron => Some("b"),
+        Element::Carbon => Some("c"),
+        Element::Nitrogen => Some("n"),
+        Element::Oxygen => Some("o"),
+        Element::Phosphorus => Some("p"),
+        Element::Sulfur => Some("s"),
+        Element::Selenium => Some("se"),
+        Element::Arsenic => Some("as"),
+        _ => None,
+    }
+}
+
+/// The number of hydrogens implied by an atom written without brackets, given
+/// the sum of the orders of its bonds with each aromatic bond counted once.
+fn implicit_hydrogens(element: Element, aromatic: bool, bond_order_sum: usize) -> usize {
+    let Some(valences) = normal_valences(element) else {
+        return 0;
+    };
+    if aromatic {
+        // One more bond is shared out around the ring
+        return valences[0].saturating_sub(bond_order_sum + 1);
+    }
+    valences
+        .iter()
+        .find(|&&valence| valence >= bond_order_sum)
+        .map_or(0, |&valence| valence - bond_order_sum)
+}
@@ -0,0 +1,705 @@
+use std::{error, fmt};
+
+use super::{aromatic_symbol, implicit_hydrogens, normal_valences};
+use crate::{
+    graph::{
+        stereo::{Chirality, Stereo},
+        Graph,
+    },
+    Element,
+};
+
+/// An error in a SMILES string, at a byte offset into it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SmilesError {
+    pub position: usize,
+    pub message: &'static str,
+}
+
+impl fmt::Display for SmilesError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} at position {}", self.message, self.position)
+    }
+}
+
+impl error::Error for SmilesError {}
+
+impl Graph {
+    /// Reads a SMILES string. Hydrogens are added as atoms, aromatic rings are
+    /// kekulized and atoms left short of their bonding number, as in `[CH3]`,
+    /// are given free valences. Atoms outside brackets may have no more bonds
+    /// than their highest normal valence.
+    pub fn from_smiles(smiles: &str) -> Result<Graph, SmilesError> {
+        Parser::new(smiles).parse()
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum BondSymbol {
+    Implicit,
+    Single,
+    Double,
+    Triple,
+    Quadruple,
+    Aromatic,
+    /// `/`
+    Up,
+    /// `\`
+    Down,
+}
+
+#[derive(Debug)]
+struct Atom {
+    position: usize,
+    element: Element,
+    aromatic: bool,
+    charge: i8,
+    mass_number: Option<u16>,
+    /// The hydrogen count of a bracket atom.
+    hydrogens: Option<usize>,
+    chirality: Option<Chirality>,
+    /// Whether the atom follows another atom it is bonded to, which comes first
+    /// in its neighbour order.
+    has_previous: bool,
+    /// The other ends of the atom's bonds, in the order they are written.
+    /// Ring bonds are filled in when they close.
+    neighbors: Vec<Option<usize>>,
+}
+
+/// A bond as written, from the earlier atom to the later.
+#[derive(Debug)]
+struct Bond {
+    from: usize,
+    to: usize,
+    symbol: BondSymbol,
+}
+
+struct RingBond {
+    digit: u16,
+    position: usize,
+    atom: usize,
+    symbol: Option<BondSymbol>,
+    /// Where the ring bond goes in the opening atom's neighbours.
+    slot: usize,
+}
+
+struct Parser<'a> {
+    input: &'a str,
+    position: usize,
+    graph: Graph,
+    atoms: Vec<Atom>,
+    bonds: Vec<Bond>,
+}
+
+impl<'a> Parser<'a> {
+    fn new(input: &'a str) -> Self {
+        Self {
+            input,
+            position: 0,
+            graph: Graph::default(),
+            atoms: Vec::new(),
+            bonds: Vec::new(),
+        }
+    }
+
+    fn error<T>(&self, message: &'static str) -> Result<T, SmilesError> {
+        Err(SmilesError {
+            position: self.position,
+            message,
+        })
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.input[self.position..].chars().next()
+    }
+
+    fn eat(&mut self, c: char) -> bool {
+        if self.peek() == Some(c) {
+            self.position += c.len_utf8();
+            true
+        } else {
+            false
+        }
+    }
+
+    fn number(&mut self) -> Option<usize> {
+        let rest = &self.input[self.position..];
+        let len = rest
+            .find(|c: char| !c.is_ascii_digit())
+            .unwrap_or(rest.len());
+        if len == 0 {
+            return None;
+        }
+        self.position += len;
+        rest[..len].parse().ok()
+    }
+
+    fn parse(mut self) -> Result<Graph, SmilesError> {
+        let mut previous: Option<usize> = None;
+        let mut branches: Vec<(usize, Option<usize>)> = Vec::new();
+        let mut bond: Option<(usize, BondSymbol)> = None;
+        let mut ring_bonds: Vec<RingBond> = Vec::new();
+
+        while let Some(c) = self.peek() {
+            let start = self.position;
+            match c {
+                '(' => {
+                    if previous.is_none() {
+                        return self.error("Branch without a preceding atom");
+                    }
+                    self.position += 1;
+                    branches.push((start, previous));
+                }
+                ')' => {
+                    if bond.is_some() {
+                        return self.error("Bond without a following atom");
+                    }
+                    let Some((_, atom)) = branches.pop() else {
+                        return self.error("Unmatched closing bracket");
+                    };
+                    self.position += 1;
+                    previous = atom;
+                }
+                '-' | '=' | '#' | '$' | ':' | '/' | '\\' => {
+                    if bond.is_some() {
+                        return self.error("Two bond symbols in a row");
+                    }
+                    if previous.is_none() {
+                        return self.error("Bond without a preceding atom");
+                    }
+                    self.position += 1;
+                    let symbol = match c {
+                        '-' => BondSymbol::Single,
+                        '=' => BondSymbol::Double,
+                        '#' => BondSymbol::Triple,
+                        '$' => BondSymbol::Quadruple,
+                        ':' => BondSymbol::Aromatic,
+                        '/' => BondSymbol::Up,
+                        _ => BondSymbol::Down,
+                    };
+                    bond = Some((start, symbol));
+                }
+                '0'..='9' | '%' => {
+                    let Some(atom) = previous else {
+                        return self.error("Ring bond without a preceding atom");
+                    };
+                    let digit = if self.eat('%') {
+                        let rest = &self.input[self.position..];
+                        let digits = rest
+                            .get(..2)
+                            .filter(|d| d.bytes().all(|b| b.is_ascii_digit()));
+                        let Some(digits) = digits else {
+                            return self.error("Expected two digits after '%'");
+                        };
+                        self.position += 2;
+                        digits.parse().unwrap()
+                    } else {
+                        self.position += 1;
+                        c as u16 - '0' as u16
+                    };
+                    let symbol = bond.take().map(|(_, symbol)| symbol);
+
+                    if let Some(k) = ring_bonds.iter().position(|r| r.digit == digit) {
+                        let opening = ring_bonds.remove(k);
+                        if opening.atom == atom {
+                            self.position = start;
+                            return self.error("Ring bond from an atom to itself");
+                        }
+                        let symbol = match (opening.symbol, symbol) {
+                            (Some(a), Some(b)) if a != b => {
+                                self.position = start;
+                                return self.error("Conflicting ring bond symbols");
+                            }
+                            (a, b) => a.or(b).unwrap_or(BondSymbol::Implicit),
+                        };
+                        self.atoms[opening.atom].neighbors[opening.slot] = Some(atom);
+                        self.atoms[atom].neighbors.push(Some(opening.atom));
+                        self.add_bond(opening.atom, atom, symbol);
+                    } else {
+                        let slot = self.atoms[atom].neighbors.len();
+                        self.atoms[atom].neighbors.push(None);
+                        ring_bonds.push(RingBond {
+                            digit,
+                            position: start,
+                            atom,
+                            symbol,
+                            slot,
+                        });
+                    }
+                }
+                '.' => {
+                    if bond.is_some() {
+                        return self.error("Bond without a following atom");
+                    }
+                    self.position += 1;
+                    previous = None;
+                }
+                _ => {
+                    let atom = self.atom()?;
+                    if let Some(previous) = previous {
+                        let symbol = bond.take().map_or(BondSymbol::Implicit, |(_, s)| s);
+                        self.atoms[previous].neighbors.push(Some(atom));
+                        self.atoms[atom].neighbors.push(Some(previous));
+                        self.atoms[atom].has_previous = true;
+                        self.add_bond(previous, atom, symbol);
+                    }
+                    previous = Some(atom);
+                }
+            }
+        }
+
+        if let Some((position, _)) = bond {
+            self.position = position;
+            return self.error("Bond without a following atom");
+        }
+        if let Some(&(position, _)) = branches.last() {
+            self.position = position;
+            return self.error("Unclosed branch");
+        }
+        if let Some(ring_bond) = ring_bonds.first() {
+            self.position = ring_bond.position;
+            return self.error("Unclosed ring bond");
+        }
+
+        self.finish()
+    }
+
+    /// Parses an atom, adding it to the graph and returning its index.
+    fn atom(&mut self) -> Result<usize, SmilesError> {
+        let start = self.position;
+        let atom = if self.eat('[') {
+            self.bracket_atom(start)?
+        } else {
+            let (element, aromatic) = self.organic_atom()?;
+            Atom {
+                position: start,
+                element,
+                aromatic,
+                charge: 0,
+                mass_number: None,
+                hydrogens: None,
+                chirality: None,
+                has_previous: false,
+                neighbors: Vec::new(),
+            }
+        };
+
+        let i = self.graph.atoms.len();
+        self.graph.atoms.push(atom.element);
+        if atom.charge != 0 {
+            self.graph.charges.push((i, atom.charge));
+        }
+        if let Some(mass_number) = atom.mass_number {
+            self.graph.isotopes.push((i, mass_number));
+        }
+        self.atoms.push(atom);
+        Ok(i)
+    }
+
+    fn organic_atom(&mut self) -> Result<(Element, bool), SmilesError> {
+        let rest = &self.input[self.position..];
+        for (symbol, element) in [("Cl", Element::Chlorine), ("Br", Element::Bromine)] {
+            if rest.starts_with(symbol) {
+                self.position += 2;
+                return Ok((element, false));
+            }
+        }
+
+        let Some(c) = self.peek() else {
+            return self.error("Expected an atom");
+        };
+        let symbol = &rest[..c.len_utf8()];
+        if let Some(&element) = crate::ELEMENTS
+            .iter()
+            .find(|&&e| aromatic_symbol(e) == Some(symbol))
+        {
+            self.position += 1;
+            return Ok((element, true));
+        }
+        match Element::from_symbol(symbol) {
+            Some(element) if normal_valences(element).is_some() => {
+                self.position += 1;
+                Ok((element, false))
+            }
+            _ if c == '*' => self.error("Wildcard atoms are not supported"),
+            _ => self.error("Unexpected character"),
+        }
+    }
+
+    fn bracket_atom(&mut self, start: usize) -> Result<Atom, SmilesError> {
+        let mass_number = self.number().map(|n| n as u16);
+
+        let rest = &self.input[self.position..];
+        let two = rest.get(..2).unwrap_or("");
+        let one = rest.get(..1).unwrap_or("");
+        let aromatic = |symbol: &str| {
+            crate::ELEMENTS
+                .iter()
+                .copied()
+                .find(|&e| aromatic_symbol(e) == Some(symbol))
+        };
+        let (element, aromatic, len) = if let Some(element) = aromatic(two) {
+            (element, true, 2)
+        } else if let Some(element) = Element::from_symbol(two) {
+            (element, false, 2)
+        } else if let Some(element) = aromatic(one) {
+            (element, true, 1)
+        } else if let Some(element) = Element::from_symbol(one) {
+            (element, false, 1)
+        } else {
+            return self.error("Unknown element");
+        };
+        self.position += len;
+
+        let chirality = if self.eat('@') {
+            let chirality = if self.eat('@') {
+                Chirality::Clockwise
+            } else {
+                Chirality::Anticlockwise
+            };
+            if self
+                .peek()
+                .is_some_and(|c| c.is_ascii_uppercase() && c != 'H')
+            {
+                return self.error("Only @ and @@ chirality is supported");
+            }
+            Some(chirality)
+        } else {
+            None
+        };
+
+        let hydrogens = if self.eat('H') {
+            self.number().unwrap_or(1)
+        } else {
+            0
+        };
+
+        let charge = if let Some(sign @ ('+' | '-')) = self.peek() {
+            self.position += 1;
+            let magnitude = if let Some(n) = self.number() {
+                n as i8
+            } else {
+                let mut n = 1;
+                while self.eat(sign) {
+                    n += 1;
+                }
+                n
+            };
+            if sign == '+' {
+                magnitude
+            } else {
+                -magnitude
+            }
+        } else {
+            0
+        };
+
+        // Atom classes carry no chemical meaning
+        if self.eat(':') && self.number().is_none() {
+            return self.error("Expected an atom class");
+        }
+
+        if !self.eat(']') {
+            return self.error("Expected ']'");
+        }
+        Ok(Atom {
+            position: start,
+            element,
+            aromatic,
+            charge,
+            mass_number,
+            hydrogens: Some(hydrogens),
+            chirality,
+            has_previous: false,
+            neighbors: Vec::new(),
+        })
+    }
+
+    fn add_bond(&mut self, from: usize, to: usize, symbol: BondSymbol) {
+        let order = match symbol {
+            BondSymbol::Double => 2,
+            BondSymbol::Triple => 3,
+            BondSymbol::Quadruple => 4,
+            _ => 1,
+        };
+        self.graph.bonds.push((from, to));
+        for _ in 1..order {
+            self.graph.pi_bonds.push((from, to));
+        }
+        self.bonds.push(Bond { from, to, symbol });
+    }
+
+    fn is_aromatic_bond(&self, bond: &Bond) -> bool {
+        match bond.symbol {
+            BondSymbol::Aromatic => true,
+            BondSymbol::Implicit => self.atoms[bond.from].aromatic && self.atoms[bond.to].aromatic,
+            _ => false,
+        }
+    }
+
+    /// Adds hydrogens, stereochemistry, free valences and the Kekulé structure.
+    fn finish(mut self) -> Result<Graph, SmilesError> {
+        let heavy_atom_count = self.atoms.len();
+
+        for i in 0..heavy_atom_count {
+            let hydrogens = match self.atoms[i].hydrogens {
+                Some(hydrogens) => hydrogens,
+                None => {
+                    let bond_order_sum = self
+                        .bonds
+                        .iter()
+                        .filter(|bond| bond.from == i || bond.to == i)
+                        .map(|bond| {
+                            if self.is_aromatic_bond(bond) {
+                                1
+                            } else {
+                                self.graph.bond_order(bond.from, bond.to) as usize
+                            }
+                        })
+                        .sum();
+                    let element = self.graph.atoms[i];
+                    let valences = normal_valences(element).unwrap_or_default();
+                    if valences
+                        .last()
+                        .is_some_and(|&valence| bond_order_sum > valence)
+                    {
+                        self.position = self.atoms[i].position;
+                        return self.error("More bonds than the atom's valence");
+                    }
+                    implicit_hydrogens(element, self.atoms[i].aromatic, bond_order_sum)
+                }
+            };
+
+            for k in 0..hydrogens {
+                let h = self.graph.atoms.len();
+                self.graph.atoms.push(Element::Hydrogen);
+                self.graph.bonds.push((i, h));
+                if k == 0 {
+                    // A bracket hydrogen comes straight after the preceding atom
+                    let slot = usize::from(self.atoms[i].has_previous);
+                    self.atoms[i].neighbors.insert(slot, Some(h));
+                }
+            }
+        }
+
+        for i in 0..heavy_atom_count {
+            let Some(chirality) = self.atoms[i].chirality else {
+                continue;
+            };
+            let neighbors = self.atoms[i].neighbors.iter().flatten().copied();
+            let Ok(neighbors) = neighbors.collect::<Vec<_>>().try_into() else {
+                self.position = self.atoms[i].position;
+                return self.error("Stereocentre without four neighbours");
+            };
+            self.graph.stereo.push(Stereo::Tetrahedral {
+                centre: i,
+                neighbors,
+                chirality,
+            });
+        }
+        self.double_bond_stereo();
+
+        // Atoms outside aromatic rings that are short of bonds are radicals
+        for i in 0..heavy_atom_count {
+            if !self.atoms[i].aromatic {
+                for _ in 0..self.graph.valence_deficit(i) {
+                    self.graph.free_valences.push(i);
+                }
+            }
+        }
+
+        if self.graph.kekulize().is_err() {
+            let first = (0..heavy_atom_count).find(|&i| self.graph.valence_deficit(i) > 0);
+            self.position = first.map_or(0, |i| self.atoms[i].position);
+            return self.error("Aromatic atoms without a Kekulé structure");
+        }
+
+        Ok(self.graph)
+    }
+
+    /// Turns pairs of `/` and `\` bonds either side of a double bond into
+    /// cis or trans configurations.
+    fn double_bond_stereo(&mut self) {
+        // The neighbour across a `/` or `\` bond from `end`, and the bond's
+        // direction when written from `end` towards the neighbour
+        let direction = |bond: &Bond, end: usize| {
+            let sign = match bond.symbol {
+                BondSymbol::Up => 1,
+                BondSymbol::Down => -1,
+                _ => return None,
+            };
+            if bond.from == end {
+                Some((bond.to, sign))
+            } else {
+                Some((bond.from, -sign))
+            }
+        };
+
+        for double in self.bonds.iter().filter(|b| b.symbol == BondSymbol::Double) {
+            let (a, b) = (double.from, double.to);
+            let side = |end: usize| {
+                self.bonds
+                    .iter()
+                    .filter(|bond| bond.from == end || bond.to == end)
+                    .find_map(|bond| direction(bond, end))
+            };
+            let (Some((c, sign_c)), Some((d, sign_d))) = (side(a), side(b)) else {
+                continue;
+            };
+            // Written outwards from the double bond, F/C=C/F has one bond
+            // going down and the other up, so the fluorines are trans
+            self.graph.stereo.push(Stereo::DoubleBond {
+                atoms: [c, a, b, d],
+                together: sign_c == sign_d,
+            });
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        graph::{
+            stereo::{Chirality, Stereo},
+            Graph,
+        },
+        parser::parse,
+        test::{CAFFEINE, DOPAMINE, ISOPROPANOL},
+        Element,
+    };
+
+    #[test]
+    fn test_parse_smiles() {
+        let graph = Graph::from_smiles("CC(C)O").unwrap();
+        assert_eq!(graph.atoms.len(), 12);
+        assert_eq!(
+            graph.canonical_key(),
+            Graph::from(&*parse(ISOPROPANOL)).canonical_key(),
+        );
+
+        let graph = Graph::from_smiles("NCCc1ccc(O)c(O)c1").unwrap();
+        assert_eq!(graph.pi_bonds.len(), 3);
+        assert_eq!(
+            graph.canonical_key(),
+            Graph::from(&*parse(DOPAMINE)).canonical_key(),
+        );
+
+        let graph = Graph::from_smiles("CN1C=NC2=C1C(=O)N(C(=O)N2C)C").unwrap();
+        assert_eq!(
+            graph.canonical_key(),
+            Graph::from(&*parse(CAFFEINE)).canonical_key(),
+        );
+    }
+
+    #[test]
+    fn test_round_trip() {
+        for name in [
+            ISOPROPANOL,
+            DOPAMINE,
+            CAFFEINE,
+            "(²H₃)Methanol",
+            "[2-¹⁴C]glycine",
+        ] {
+            let graph = Graph::from(&*parse(name));
+            let smiles = graph.smiles();
+            let round_trip = Graph::from_smiles(&smiles).unwrap();
+            assert_eq!(
+                graph.canonical_key(),
+                round_trip.canonical_key(),
+                "{smiles}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_bracket_atoms() {
+        let graph = Graph::from_smiles("[NH4+]").unwrap();
+        assert_eq!(graph.atoms.len(), 5);
+        assert_eq!(graph.charge(0), 1);
+
+        let graph = Graph::from_smiles("C[O-]").unwrap();
+        assert_eq!(graph.charge(1), -1);
+        assert!(graph.free_valences.is_empty());
+
+        let graph = Graph::from_smiles("[CH3]").unwrap();
+        assert_eq!(graph.free_valences, vec![0]);
+
+        let graph = Graph::from_smiles("[13CH4]").unwrap();
+        assert_eq!(graph.atoms.len(), 5);
+        assert_eq!(graph.isotopes, vec![(0, 13)]);
+
+        let graph = Graph::from_smiles("[2H]C([2H])([2H])O").unwrap();
+        assert_eq!(graph.isotopes.len(), 3);
+        assert_eq!(
+            graph.canonical_key(),
+            Graph::from(&*parse("(²H₃)Methanol")).canonical_key(),
+        );
+
+        let graph = Graph::from_smiles("c1cc[nH]c1").unwrap();
+        assert_eq!(graph.pi_bonds.len(), 2);
+        assert_eq!(graph.atoms[3], Element::Nitrogen);
+    }
+
+    #[test]
+    fn test_stereo() {
+        let graph = Graph::from_smiles("N[C@@H](C)C(=O)O").unwrap();
+        let &[Stereo::Tetrahedral {
+            centre,
+            neighbors,
+            chirality,
+        }] = graph.stereo.as_slice()
+        else {
+            panic!("{:?}", graph.stereo);
+        };
+        assert_eq!(centre, 1);
+        assert_eq!(graph.atoms[neighbors[1]], Element::Hydrogen);
+        assert_eq!([neighbors[0], neighbors[2], neighbors[3]], [0, 2, 3]);
+        assert_eq!(chirality, Chirality::Clockwise);
+
+        let trans = Graph::from_smiles("F/C=C/F").unwrap();
+        assert_eq!(
+            trans.stereo,
+            vec![Stereo::DoubleBond {
+                atoms: [0, 1, 2, 3],
+                together: false,
+            }],
+        );
+        let cis = Graph::from_smiles("F/C=C\\F").unwrap();
+        assert!(matches!(
+            cis.stereo[0],
+            Stereo::DoubleBond { together: true, .. }
+        ));
+        let cis = Graph::from_smiles("C(\\F)=C/F").unwrap();
+        assert!(matches!(
+            cis.stereo[0],
+            Stereo::DoubleBond {
+                together: false,
+                ..
+            }
+        ));
+    }
+
+    #[test]
+    fn test_errors() {
+        for (smiles, position, message) in [
+            ("CC(C", 2, "Unclosed branch"),
+            ("C1CC", 1, "Unclosed ring bond"),
+            ("CC)", 2, "Unmatched closing bracket"),
+            ("C=", 1, "Bond without a following atom"),
+            ("C[Xx]", 2, "Unknown element"),
+            ("C[C", 3, "Expected ']'"),
+            ("CQ", 1, "Unexpected character"),
+            ("c1cccc1", 0, "Aromatic atoms without a Kekulé structure"),
+            ("[C@](F)Cl", 0, "Stereocentre without four neighbours"),
+            ("CC(C)(C)(C)C", 1, "More bonds than the atom's valence"),
+            ("C=C(C)=O", 2, "More bonds than the atom's valence"),
+        ] {
+            let error = Graph::from_smiles(smiles).unwrap_err();
+            assert_eq!(
+                (error.position, error.message),
+                (position, message),
+                "{smiles}"
+            );
+        }
+    }
+}
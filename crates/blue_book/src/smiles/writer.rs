@@ -0,0 +1,441 @@
+use std::fmt::Write;
+
+use super::{aromatic_symbol, implicit_hydrogens, normal_valences};
+use crate::graph::{
+    aromaticity::Aromaticity,
+    stereo::{is_even_permutation, Chirality, Stereo},
+    Graph,
+};
+
+impl Graph {
+    /// Writes the molecule as SMILES, with aromatic rings in lowercase and
+    /// atoms visited in the order they appear in the graph. Stereocentres are
+    /// written with `@` or `@@`, and double bond configurations with `/` and
+    /// `\\`.
+    pub fn smiles(&self) -> String {
+        let ranks = (0..self.atoms.len()).collect();
+        Writer::new(self, ranks).write()
+    }
+
+    /// Writes the molecule as SMILES with atoms visited in canonical order, so
+    /// that the same molecule always gives the same string.
+    pub fn canonical_smiles(&self) -> String {
+        Writer::new(self, self.canonical_ranks()).write()
+    }
+}
+
+struct Writer<'a> {
+    original: &'a Graph,
+    /// The original graph with its Kekulé structure assigned.
+    graph: Graph,
+    aromaticity: Aromaticity,
+    /// The atoms written out, sorted by rank. Terminal hydrogens are instead
+    /// written as hydrogen counts, unless they are a specific isotope.
+    neighbors: Vec<Vec<usize>>,
+    ranks: Vec<usize>,
+
+    visited: Vec<bool>,
+    children: Vec<Vec<usize>>,
+    subtree_sizes: Vec<usize>,
+    /// For each atom, the other ends of the ring bonds that open or close at
+    /// it, in the order they are met.
+    ring_bonds: Vec<Vec<usize>>,
+    /// The ring bond digits in use, as `(opening atom, closing atom, digit)`.
+    open_digits: Vec<(usize, usize, usize)>,
+    /// `(a, b, direction)` for bonds next to stereogenic double bonds, where
+    /// the direction is 1 for `/` and -1 for `\\` when written from `a` to
+    /// `b`.
+    directions: Vec<(usize, usize, i8)>,
+}
+
+impl<'a> Writer<'a> {
+    fn new(original: &'a Graph, ranks: Vec<usize>) -> Self {
+        let mut graph = original.clone();
+        // Structures without a Kekulé form are written with single bonds
+        let _ = graph.kekulize();
+        let aromaticity = graph.aromaticity();
+
+        let n = graph.atoms.len();
+        let mut neighbors = vec![Vec::new(); n];
+        for &(a, b) in &graph.bonds {
+            if original.is_folded_hydrogen(a) || original.is_folded_hydrogen(b) {
+                continue;
+            }
+            neighbors[a].push(b);
+            neighbors[b].push(a);
+        }
+        for neighbors in &mut neighbors {
+            neighbors.sort_by_key(|&j| ranks[j]);
+        }
+
+        let mut directions = Vec::new();
+        for stereo in &original.stereo {
+            let &Stereo::DoubleBond {
+                atoms: [c, a, b, d],
+                mut together,
+            } = stereo
+            else {
+                continue;
+            };
+            // Directions can only be written on bonds to atoms that are
+            // written out, so swap terminal hydrogens for the other neighbour
+            let mut written_neighbor = |end: usize, other: usize, neighbor: usize| {
+                if neighbors[end].contains(&neighbor) {
+                    return Some(neighbor);
+                }
+                together = !together;
+                neighbors[end].iter().copied().find(|&j| j != other)
+            };
+            let (Some(c), Some(d)) = (written_neighbor(a, b, c), written_neighbor(b, a, d)) else {
+                continue;
+            };
+            let is_set = |x: usize, y: usize| {
+                directions
+                    .iter()
+                    .any(|&(p, q, _)| (p, q) == (x, y) || (p, q) == (y, x))
+            };
+            if is_set(c, a) || is_set(b, d) {
+                // Conjugated double bonds share their direction markers
+                continue;
+            }
+            // Written outwards from the double bond, opposite directions put
+            // the neighbours on opposite sides
+            directions.push((a, c, -1));
+            directions.push((b, d, if together { -1 } else { 1 }));
+        }
+
+        Self {
+            original,
+            graph,
+            aromaticity,
+            neighbors,
+            ranks,
+            visited: vec![false; n],
+            children: vec![Vec::new(); n],
+            subtree_sizes: vec![1; n],
+            ring_bonds: vec![Vec::new(); n],
+            open_digits: Vec::new(),
+            directions,
+        }
+    }
+
+    fn write(mut self) -> String {
+        let mut roots = (0..self.graph.atoms.len())
+            .filter(|&i| !self.original.is_folded_hydrogen(i))
+            .collect::<Vec<_>>();
+        roots.sort_by_key(|&i| self.ranks[i]);
+
+        let mut smiles = String::new();
+        for root in roots {
+            if self.visited[root] {
+                continue;
+            }
+            self.visit(root, None);
+            if !smiles.is_empty() {
+                smiles.push('.');
+            }
+            self.write_atom(root, None, &mut smiles);
+        }
+        smiles
+    }
+
+    /// Builds the depth-first spanning tree, recording the bonds that close
+    /// rings. Smaller branches are put first, to be written in parentheses.
+    fn visit(&mut self, i: usize, parent: Option<usize>) {
+        self.visited[i] = true;
+        for k in 0..self.neighbors[i].len() {
+            let j = self.neighbors[i][k];
+            if Some(j) == parent {
+                continue;
+            }
+            if !self.visited[j] {
+                self.children[i].push(j);
+                self.visit(j, Some(i));
+                self.subtree_sizes[i] += self.subtree_sizes[j];
+            } else if !self.ring_bonds[i].contains(&j) {
+                self.ring_bonds[j].push(i);
+                self.ring_bonds[i].push(j);
+            }
+        }
+
+        let mut children = std::mem::take(&mut self.children[i]);
+        children.sort_by_key(|&j| self.subtree_sizes[j]);
+        self.children[i] = children;
+    }
+
+    fn write_atom(&mut self, i: usize, parent: Option<usize>, smiles: &mut String) {
+        if let Some(parent) = parent {
+            smiles.push_str(self.bond_symbol(parent, i));
+        }
+        self.write_atom_symbol(i, parent, smiles);
+
+        for k in 0..self.ring_bonds[i].len() {
+            let j = self.ring_bonds[i][k];
+            let opened = self
+                .open_digits
+                .iter()
+                .position(|&(a, b, _)| (a, b) == (j, i));
+            if let Some(position) = opened {
+                // Close a ring opened earlier
+                let (_, _, digit) = self.open_digits.remove(position);
+                write_digit(digit, smiles);
+            } else {
+                let digit = (1..)
+                    .find(|d| self.open_digits.iter().all(|&(_, _, e)| e != *d))
+                    .unwrap();
+                self.open_digits.push((i, j, digit));
+                smiles.push_str(self.bond_symbol(i, j));
+                write_digit(digit, smiles);
+            }
+        }
+
+        let children = self.children[i].clone();
+        for (k, &child) in children.iter().enumerate() {
+            if k + 1 < children.len() {
+                smiles.push('(');
+                self.write_atom(child, Some(i), smiles);
+                smiles.push(')');
+            } else {
+                self.write_atom(child, Some(i), smiles);
+            }
+        }
+    }
+
+    fn is_aromatic(&self, i: usize) -> bool {
+        self.aromaticity.is_aromatic_atom(i) && aromatic_symbol(self.graph.atoms[i]).is_some()
+    }
+
+    fn is_aromatic_bond(&self, a: usize, b: usize) -> bool {
+        self.is_aromatic(a) && self.is_aromatic(b) && self.aromaticity.is_aromatic_bond(a, b)
+    }
+
+    fn bond_symbol(&self, a: usize, b: usize) -> &'static str {
+        if self.is_aromatic_bond(a, b) {
+            return "";
+        }
+        for &(c, d, direction) in &self.directions {
+            let direction = if (c, d) == (a, b) {
+                direction
+            } else if (c, d) == (b, a) {
+                -direction
+            } else {
+                continue;
+            };
+            return if direction > 0 { "/" } else { "\\" };
+        }
+
+        match self.graph.bond_order(a, b) {
+            // A single bond between aromatic atoms must be explicit, as in
+            // biphenyl
+            1 if self.is_aromatic(a) && self.is_aromatic(b) => "-",
+            1 => "",
+            2 => "=",
+            3 => "#",
+            _ => "$",
+        }
+    }
+
+    fn write_atom_symbol(&self, i: usize, parent: Option<usize>, smiles: &mut String) {
+        let element = self.graph.atoms[i];
+        let aromatic = self.is_aromatic(i);
+        let symbol = if aromatic {
+            aromatic_symbol(element).unwrap()
+        } else {
+            element.symbol()
+        };
+
+        let hydrogens = self
+            .graph
+            .neighbors(i)
+            .filter(|&j| self.original.is_folded_hydrogen(j))
+            .count();
+        let bond_order_sum = self.neighbors[i]
+            .iter()
+            .map(|&j| {
+                if self.is_aromatic_bond(i, j) {
+                    1
+                } else {
+                    self.graph.bond_order(i, j) as usize
+                }
+            })
+            .sum();
+        let charge = self.graph.charge(i);
+        let mass_number = self.graph.mass_number(i);
+        let chirality = self.chirality(i, parent);
+
+        let is_organic = normal_valences(element).is_some() && !matches!(symbol, "se" | "as");
+        if is_organic
+            && charge == 0
+            && mass_number.is_none()
+            && chirality.is_none()
+            && implicit_hydrogens(element, aromatic, bond_order_sum) == hydrogens
+        {
+            smiles.push_str(symbol);
+            return;
+        }
+
+        smiles.push('[');
+        if let Some(mass_number) = mass_number {
+            write!(smiles, "{mass_number}").unwrap();
+        }
+        smiles.push_str(symbol);
+        match chirality {
+            Some(Chirality::Anticlockwise) => smiles.push('@'),
+            Some(Chirality::Clockwise) => smiles.push_str("@@"),
+            None => {}
+        }
+        match hydrogens {
+            0 => {}
+            1 => smiles.push('H'),
+            n => write!(smiles, "H{n}").unwrap(),
+        }
+        match charge {
+            0 => {}
+            1 => smiles.push('+'),
+            -1 => smiles.push('-'),
+            charge => write!(smiles, "{charge:+}").unwrap(),
+        }
+        smiles.push(']');
+    }
+}
+
+impl Writer<'_> {
+    /// The chirality of atom `i` in the order its neighbours are written: the
+    /// atom before it, its hydrogen, its ring bonds and then its branches.
+    fn chirality(&self, i: usize, parent: Option<usize>) -> Option<Chirality> {
+        let (neighbors, chirality) =
+            self.original
+                .stereo
+                .iter()
+                .find_map(|stereo| match *stereo {
+                    Stereo::Tetrahedral {
+                        centre,
+                        neighbors,
+                        chirality,
+                    } if centre == i => Some((neighbors, chirality)),
+                    _ => None,
+                })?;
+
+        let hydrogens = self
+            .graph
+            .neighbors(i)
+            .filter(|&j| self.original.is_folded_hydrogen(j));
+        let written = parent
+            .into_iter()
+            .chain(hydrogens)
+            .chain(self.ring_bonds[i].iter().copied())
+            .chain(self.children[i].iter().copied())
+            .collect::<Vec<_>>();
+        if written.len() != 4 || !written.iter().all(|j| neighbors.contains(j)) {
+            return None;
+        }
+
+        if is_even_permutation(&neighbors, &written) {
+            Some(chirality)
+        } else {
+            Some(chirality.inverse())
+        }
+    }
+}
+
+fn write_digit(digit: usize, smiles: &mut String) {
+    if digit < 10 {
+        write!(smiles, "{digit}").unwrap();
+    } else {
+        write!(smiles, "%{digit}").unwrap();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        chapters::p_2_hydrides::{
+            p_22_monocyclic_hydrides::p_22_1_monocyclic_hydocarbons::MonocyclicHydrocarbon, Hydride,
+        },
+        graph::Graph,
+        parser::parse,
+        test::{CAFFEINE, DOPAMINE, ISOBUTANE, ISOPROPANOL},
+        Element,
+    };
+
+    fn smiles(name: &str) -> String {
+        Graph::from(&*parse(name)).smiles()
+    }
+
+    #[test]
+    fn test_acyclic() {
+        assert_eq!(smiles(ISOPROPANOL), "CC(C)O");
+        assert_eq!(smiles(ISOBUTANE), "CC(C)C");
+        assert_eq!(smiles("Ethene"), "C=C");
+        assert_eq!(smiles("Propyne"), "C#CC");
+    }
+
+    #[test]
+    fn test_aromatic() {
+        let graph = Hydride::from(MonocyclicHydrocarbon::Benzene).to_graph();
+        assert_eq!(graph.smiles(), "c1ccccc1");
+
+        assert_eq!(smiles(DOPAMINE), "c1(O)c(O)cc(cc1)CCN");
+        assert_eq!(
+            Graph::from(&*parse(CAFFEINE)).canonical_smiles(),
+            "Cn1cnc2c1c(=O)n(C)c(=O)n2C",
+        );
+    }
+
+    #[test]
+    fn test_bracket_atoms() {
+        // Methyl has a free valence in place of a hydrogen
+        let graph = Graph::from(&*parse("Methyl"));
+        assert_eq!(graph.smiles(), "[CH3]");
+
+        // Ammonium
+        let graph = Graph {
+            atoms: vec![
+                Element::Nitrogen,
+                Element::Hydrogen,
+                Element::Hydrogen,
+                Element::Hydrogen,
+                Element::Hydrogen,
+            ],
+            bonds: vec![(0, 1), (0, 2), (0, 3), (0, 4)],
+            charges: vec![(0, 1)],
+            ..Default::default()
+        };
+        assert_eq!(graph.smiles(), "[NH4+]");
+
+        let graph = Graph {
+            atoms: vec![Element::Hydrogen, Element::Hydrogen],
+            bonds: vec![(0, 1)],
+            ..Default::default()
+        };
+        assert_eq!(graph.smiles(), "[H][H]");
+
+        // Labelled hydrogens are written out rather than counted
+        let graph = Graph::from(&*parse("(²H₃)Methanol"));
+        assert_eq!(graph.smiles(), "C([2H])([2H])([2H])O");
+        let graph = Graph::from(&*parse("(1-¹³C)ethanoic acid"));
+        assert_eq!(graph.smiles(), "[13C](C)(=O)O");
+    }
+
+    #[test]
+    fn test_stereo() {
+        for smiles in ["N[C@@H](C)C(=O)O", "N[C@H](C)C(=O)O", "F/C=C/F", "F/C=C\\F"] {
+            assert_eq!(Graph::from_smiles(smiles).unwrap().smiles(), smiles);
+        }
+
+        // Written from the other end, the chirality is given relative to a
+        // different neighbour order
+        let graph = Graph::from_smiles("OC(=O)[C@@H](C)N").unwrap();
+        assert_eq!(graph.smiles(), "OC(=O)[C@@H](C)N");
+        let graph = Graph::from_smiles("C[C@@H](C(=O)O)N").unwrap();
+        assert_eq!(graph.smiles(), "C[C@H](N)C(=O)O");
+    }
+
+    #[test]
+    fn test_canonical() {
+        let a = Graph::from(&*parse(ISOPROPANOL));
+        let b = Graph::from(&*parse("2-propanol"));
+        assert_eq!(a.canonical_smiles(), b.canonical_smiles());
+        assert_eq!(a.canonical_smiles(), "CC(C)O");
+    }
+}
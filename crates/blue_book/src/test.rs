@@ -1,3 +1,5 @@
+use crate::{graph::Graph, parser::parse};
+
 pub const ISOPROPANOL: &str = "Propan-2-ol";
 pub const ISOBUTANE: &str = "2-Methylpropane";
 
@@ -9,3 +11,11 @@ pub const ADENINE: &str = "9H-Purin-6-amine";
 pub const THYMINE: &str = "5-Methylpyrimidine-2,4(1H,3H)-dione";
 pub const CYTOSINE: &str = "4-Aminopyrimidin-2(1H)-one";
 pub const GUANINE: &str = "2-Amino-1,9-dihydro-6H-purin-6-one";
+
+/// Asserts that two names are of the same compound, with the same canonical
+/// key.
+#[track_caller]
+pub fn assert_same_compound(a: &str, b: &str) {
+    let key = |name: &str| Graph::from(&*parse(name)).canonical_key();
+    assert_eq!(key(a), key(b), "{a:?} and {b:?} are different compounds");
+}
@@ -0,0 +1,35 @@
+//! Prints the standard InChI, or InChIKey, for an IUPAC name.
+//!
+//! # Usage
+//!
+//! ```sh
+//! cargo run --bin inchi 'Propan-2-ol'
+//! cargo run --bin inchi -- --key 'Propan-2-ol'
+//! ```
+
+use std::{env, process};
+
+use blue_book::{graph::Graph, parser::parse};
+use inchi::InChI;
+
+fn main() {
+    let mut key = false;
+    let mut name = None;
+    for arg in env::args().skip(1) {
+        match arg.as_str() {
+            "--key" => key = true,
+            _ => name = Some(arg),
+        }
+    }
+
+    let ast = parse(&name.unwrap());
+    let graph = Graph::from(&*ast);
+    match InChI::try_from(&graph) {
+        Ok(inchi) if key => println!("{}", inchi.key()),
+        Ok(inchi) => println!("{inchi}"),
+        Err(message) => {
+            eprintln!("{message}");
+            process::exit(1);
+        }
+    }
+}
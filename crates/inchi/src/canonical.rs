@@ -0,0 +1,131 @@
+//! Canonical numbering of the atoms other than hydrogen, following the InChI
+//! Technical Manual.
+//!
+//! Atoms are first ranked by element in Hill order and then by their number of
+//! connections. Each rank is the number of atoms ranked at or below it, and
+//! ranks are refined by the sorted ranks of each atom's neighbours until they
+//! stop changing. Atoms that are still tied are told apart in every possible
+//! order, keeping the numbering with the smallest connection table, then the
+//! fewest hydrogens on the lowest numbers, and then the isotopic atoms on the
+//! lowest numbers.
+
+use blue_book::Element;
+
+/// Numbers the atoms from one, given the element, neighbours and hydrogen
+/// count of each atom, and whether it or its hydrogens are specific isotopes.
+pub fn canonical_numbers(
+    elements: &[Element],
+    neighbors: &[Vec<usize>],
+    hydrogen_counts: &[usize],
+    isotopic: &[bool],
+) -> Vec<usize> {
+    let invariants = (0..elements.len())
+        .map(|i| (elements[i], neighbors[i].len()))
+        .collect::<Vec<_>>();
+    let ranks = ranks(&invariants);
+
+    let mut best = None;
+    let atoms = Atoms {
+        neighbors,
+        hydrogen_counts,
+        isotopic,
+    };
+    search(&atoms, ranks, &mut best);
+    best.map(|(numbers, _)| numbers).unwrap_or_default()
+}
+
+/// What tells apart numberings of the same atoms.
+struct Atoms<'a> {
+    neighbors: &'a [Vec<usize>],
+    hydrogen_counts: &'a [usize],
+    isotopic: &'a [bool],
+}
+
+/// The connection table, hydrogen counts and atoms that are not isotopic of a
+/// numbering, compared to choose between numberings.
+type Key = (Vec<(usize, usize)>, Vec<usize>, Vec<bool>);
+
+fn search(atoms: &Atoms, ranks: Vec<usize>, best: &mut Option<(Vec<usize>, Key)>) {
+    let ranks = refine(atoms.neighbors, ranks);
+
+    // The lowest rank shared by more than one atom
+    let mut counts = vec![0; ranks.len() + 1];
+    for &rank in &ranks {
+        counts[rank] += 1;
+    }
+    let Some(tied) = (1..counts.len()).find(|&rank| counts[rank] > 1) else {
+        let key = key(atoms, &ranks);
+        if best.as_ref().is_none_or(|(_, best_key)| key < *best_key) {
+            *best = Some((ranks, key));
+        }
+        return;
+    };
+
+    for i in 0..ranks.len() {
+        if ranks[i] == tied {
+            let mut ranks = ranks.clone();
+            ranks[i] = tied - counts[tied] + 1;
+            search(atoms, ranks, best);
+        }
+    }
+}
+
+fn key(atoms: &Atoms, numbers: &[usize]) -> Key {
+    let mut connections = Vec::new();
+    for (i, neighbors) in atoms.neighbors.iter().enumerate() {
+        for &j in neighbors {
+            if numbers[j] < numbers[i] {
+                connections.push((numbers[i], numbers[j]));
+            }
+        }
+    }
+    connections.sort_unstable();
+
+    let mut hydrogens = vec![0; numbers.len()];
+    let mut natural = vec![true; numbers.len()];
+    for (i, &number) in numbers.iter().enumerate() {
+        hydrogens[number - 1] = atoms.hydrogen_counts[i];
+        natural[number - 1] = !atoms.isotopic[i];
+    }
+
+    (connections, hydrogens, natural)
+}
+
+/// Refines ranks by the sorted ranks of each atom's neighbours until the
+/// number of distinct ranks stops growing.
+fn refine(neighbors: &[Vec<usize>], mut ranks: Vec<usize>) -> Vec<usize> {
+    let mut distinct = distinct_count(&ranks);
+    loop {
+        let invariants = (0..ranks.len())
+            .map(|i| {
+                let mut neighbor_ranks = neighbors[i].iter().map(|&j| ranks[j]).collect::<Vec<_>>();
+                neighbor_ranks.sort_unstable();
+                (ranks[i], neighbor_ranks)
+            })
+            .collect::<Vec<_>>();
+        let refined = self::ranks(&invariants);
+        let refined_distinct = distinct_count(&refined);
+        if refined_distinct == distinct {
+            return ranks;
+        }
+        ranks = refined;
+        distinct = refined_distinct;
+    }
+}
+
+/// Ranks each invariant by the number of invariants less than or equal to it.
+fn ranks<T: Ord>(invariants: &[T]) -> Vec<usize> {
+    let mut sorted = invariants.iter().collect::<Vec<_>>();
+    sorted.sort_unstable();
+    invariants
+        .iter()
+        .map(|invariant| sorted.partition_point(|&other| other <= invariant))
+        .collect()
+}
+
+fn distinct_count(ranks: &[usize]) -> usize {
+    let mut ranks = ranks.to_vec();
+    ranks.sort_unstable();
+    ranks.dedup();
+    ranks.len()
+}
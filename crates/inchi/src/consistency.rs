@@ -0,0 +1,172 @@
+use std::{error, fmt};
+
+use blue_book::{formula::Formula, graph::Graph, parser::parse, Element};
+
+use super::InChI;
+
+/// Why a structure does not match an InChI, from the most basic difference.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Mismatch {
+    /// The formulas differ, with the count of each differing element in the
+    /// structure and in the InChI, once the protons of its `/p` layer are
+    /// added or removed.
+    Formula {
+        structure: Formula,
+        inchi: Formula,
+        counts: Vec<(Element, usize, usize)>,
+    },
+    /// The formulas agree but the total charges differ.
+    Charge { structure: i32, inchi: i32 },
+    /// The atoms other than hydrogen are connected differently, as shown by
+    /// the canonical keys of both skeletons.
+    Connectivity { structure: String, inchi: String },
+    /// The skeletons agree, but no placement of the mobile hydrogens gives the
+    /// same hydrogens and bond orders.
+    Hydrogens,
+}
+
+impl InChI {
+    /// Checks that an IUPAC name describes the structure of this InChI.
+    ///
+    /// # Panics
+    ///
+    /// If the name cannot be parsed, as for [`parse`].
+    pub fn check_name(&self, name: &str) -> Result<(), Mismatch> {
+        self.check_graph(&Graph::from(&*parse(name)))
+    }
+
+    /// Checks that a graph has the structure of this InChI, up to the
+    /// placement of its mobile hydrogens. Stereochemistry and isotopes are
+    /// not compared.
+    pub fn check_graph(&self, graph: &Graph) -> Result<(), Mismatch> {
+        // The formula layer is before protons are added or removed
+        let mut expected = self.formula.clone();
+        let hydrogens = expected.count(Element::Hydrogen) as i32 + self.protons;
+        expected
+            .atom_counts
+            .retain(|&element, _| element != Element::Hydrogen);
+        if hydrogens > 0 {
+            expected
+                .atom_counts
+                .insert(Element::Hydrogen, hydrogens as usize);
+        }
+
+        let mut formula = Formula::from(graph);
+        formula.isotopes.clear();
+        if formula != expected {
+            let mut elements = formula
+                .atom_counts
+                .keys()
+                .chain(expected.atom_counts.keys())
+                .copied()
+                .collect::<Vec<_>>();
+            elements.sort();
+            elements.dedup();
+            let counts = elements
+                .into_iter()
+                .map(|element| (element, formula.count(element), expected.count(element)))
+                .filter(|(_, a, b)| a != b)
+                .collect();
+            return Err(Mismatch::Formula {
+                structure: formula,
+                inchi: expected,
+                counts,
+            });
+        }
+
+        let charge = graph.charges.iter().map(|&(_, charge)| charge as i32).sum();
+        if charge != self.charge() + self.protons {
+            return Err(Mismatch::Charge {
+                structure: charge,
+                inchi: self.charge() + self.protons,
+            });
+        }
+
+        let mut base = self.clone();
+        for component in &mut base.components {
+            component.hydrogens = Default::default();
+        }
+        base.protons = 0;
+        let (structure, inchi) = (skeleton_key(graph), skeleton_key(&base.graph().0));
+        if structure != inchi {
+            return Err(Mismatch::Connectivity { structure, inchi });
+        }
+
+        // The generated InChI is canonical, so usually settles it without
+        // trying each tautomer
+        if let Ok(generated) = InChI::try_from(graph) {
+            let same = |a: &InChI, b: &InChI| {
+                a.components.len() == b.components.len()
+                    && a.components
+                        .iter()
+                        .zip(&b.components)
+                        .all(|(a, b)| a.connections == b.connections && a.hydrogens == b.hydrogens)
+            };
+            if same(&generated, self) {
+                return Ok(());
+            }
+        }
+
+        let key = graph.canonical_key();
+        if self
+            .tautomers()
+            .any(|tautomer| tautomer.canonical_key() == key)
+        {
+            Ok(())
+        } else {
+            Err(Mismatch::Hydrogens)
+        }
+    }
+}
+
+/// The canonical key of the atoms other than hydrogen, with single bonds and
+/// no charges.
+fn skeleton_key(graph: &Graph) -> String {
+    let atoms = (0..graph.atoms.len())
+        .filter(|&i| graph.atoms[i] != Element::Hydrogen)
+        .collect::<Vec<_>>();
+    let index = |i: usize| atoms.binary_search(&i).ok();
+
+    let mut skeleton = Graph {
+        atoms: atoms.iter().map(|&i| graph.atoms[i]).collect(),
+        ..Default::default()
+    };
+    for &(a, b) in &graph.bonds {
+        if let (Some(x), Some(y)) = (index(a), index(b)) {
+            skeleton.bonds.push((x, y));
+        }
+    }
+    skeleton.canonical_key()
+}
+
+impl fmt::Display for Mismatch {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Mismatch::Formula {
+                structure,
+                inchi,
+                counts,
+            } => {
+                write!(f, "Formula {structure} differs from {inchi} in the InChI:")?;
+                for (k, (element, a, b)) in counts.iter().enumerate() {
+                    let separator = if k == 0 { " " } else { ", " };
+                    write!(f, "{separator}{a} {} rather than {b}", element.symbol())?;
+                }
+                Ok(())
+            }
+            Mismatch::Charge { structure, inchi } => {
+                write!(f, "Charge {structure} differs from {inchi} in the InChI")
+            }
+            Mismatch::Connectivity { structure, inchi } => write!(
+                f,
+                "Atoms are connected as {structure} rather than {inchi} in the InChI"
+            ),
+            Mismatch::Hydrogens => write!(
+                f,
+                "Hydrogens or bond orders differ from every tautomer of the InChI"
+            ),
+        }
+    }
+}
+
+impl error::Error for Mismatch {}
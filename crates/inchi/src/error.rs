@@ -0,0 +1,174 @@
+use std::{error, fmt};
+
+/// A layer of an InChI string.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Layer {
+    Formula,
+    /// `/c`
+    Connections,
+    /// `/h`
+    Hydrogens,
+    /// `/q`
+    Charge,
+    /// `/p`
+    Protons,
+    /// `/b`
+    DoubleBonds,
+    /// `/t`
+    Tetrahedral,
+    /// `/m`
+    Inverted,
+    /// `/s`
+    StereoType,
+    /// `/i`
+    Isotopic,
+    /// `/f`
+    FixedHydrogens,
+    /// `/r`
+    Reconnected,
+}
+
+/// An error in an InChI string, at a character offset into the whole string.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum InChIError {
+    /// The string does not start with `InChI=1S/` or `InChI=1/`.
+    MissingPrefix,
+    /// A formula that cannot be read, in the formula layer or in the `/f` or
+    /// `/r` layer that starts with one.
+    InvalidFormula {
+        layer: Layer,
+        offset: usize,
+        message: &'static str,
+    },
+    UnexpectedCharacter {
+        layer: Layer,
+        offset: usize,
+        character: char,
+    },
+    UnexpectedToken {
+        layer: Layer,
+        offset: usize,
+        expected: &'static str,
+    },
+    UnexpectedEnd {
+        layer: Layer,
+        offset: usize,
+        expected: &'static str,
+    },
+    UnmatchedParenthesis {
+        layer: Layer,
+        offset: usize,
+    },
+    /// A number too large to read, an atom number that is zero or more than
+    /// the number of atoms in the formula, an atom bonded to itself, or an
+    /// isotope that cannot be.
+    InvalidNumber {
+        layer: Layer,
+        offset: usize,
+    },
+    /// A layer with more parts, separated by `;`, than there are components
+    /// in the formula.
+    TooManyComponents {
+        layer: Layer,
+        offset: usize,
+    },
+    /// A layer that is unknown or out of order.
+    UnexpectedLayer {
+        offset: usize,
+    },
+    /// A charge from the `/q` or `/p` layer that no atom can take.
+    UnplacedCharge {
+        layer: Layer,
+        offset: usize,
+    },
+    /// A part of the `/h` layer with a different number of hydrogens from the
+    /// formula of its component.
+    HydrogenCount {
+        offset: usize,
+    },
+}
+
+impl InChIError {
+    pub fn layer(&self) -> Option<Layer> {
+        match *self {
+            InChIError::MissingPrefix | InChIError::UnexpectedLayer { .. } => None,
+            InChIError::HydrogenCount { .. } => Some(Layer::Hydrogens),
+            InChIError::InvalidFormula { layer, .. }
+            | InChIError::UnexpectedCharacter { layer, .. }
+            | InChIError::UnexpectedToken { layer, .. }
+            | InChIError::UnexpectedEnd { layer, .. }
+            | InChIError::UnmatchedParenthesis { layer, .. }
+            | InChIError::InvalidNumber { layer, .. }
+            | InChIError::TooManyComponents { layer, .. }
+            | InChIError::UnplacedCharge { layer, .. } => Some(layer),
+        }
+    }
+
+    pub fn offset(&self) -> usize {
+        match *self {
+            InChIError::MissingPrefix => 0,
+            InChIError::InvalidFormula { offset, .. }
+            | InChIError::UnexpectedCharacter { offset, .. }
+            | InChIError::UnexpectedToken { offset, .. }
+            | InChIError::UnexpectedEnd { offset, .. }
+            | InChIError::UnmatchedParenthesis { offset, .. }
+            | InChIError::InvalidNumber { offset, .. }
+            | InChIError::TooManyComponents { offset, .. }
+            | InChIError::UnplacedCharge { offset, .. }
+            | InChIError::UnexpectedLayer { offset }
+            | InChIError::HydrogenCount { offset } => offset,
+        }
+    }
+}
+
+impl fmt::Display for Layer {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match self {
+            Layer::Formula => "formula",
+            Layer::Connections => "connections",
+            Layer::Hydrogens => "hydrogens",
+            Layer::Charge => "charge",
+            Layer::Protons => "protons",
+            Layer::DoubleBonds => "double bond stereo",
+            Layer::Tetrahedral => "tetrahedral stereo",
+            Layer::Inverted => "inverted stereo",
+            Layer::StereoType => "stereo type",
+            Layer::Isotopic => "isotopic",
+            Layer::FixedHydrogens => "fixed hydrogen",
+            Layer::Reconnected => "reconnected",
+        };
+        write!(f, "{name}")
+    }
+}
+
+impl fmt::Display for InChIError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            InChIError::MissingPrefix => return write!(f, "Missing InChI=1S/ or InChI=1/ prefix"),
+            InChIError::UnexpectedLayer { .. } => write!(f, "Unexpected layer")?,
+            InChIError::InvalidFormula { message, .. } => write!(f, "{message}")?,
+            InChIError::UnexpectedCharacter { character, .. } => {
+                write!(f, "Unexpected character {character:?}")?
+            }
+            InChIError::UnexpectedToken { expected, .. } => write!(f, "Expected {expected}")?,
+            InChIError::UnexpectedEnd { expected, .. } => {
+                write!(f, "Expected {expected}, got nothing")?
+            }
+            InChIError::UnmatchedParenthesis { .. } => write!(f, "Unmatched parenthesis")?,
+            InChIError::InvalidNumber { .. } => write!(f, "Invalid number")?,
+            InChIError::TooManyComponents { .. } => {
+                write!(f, "More components than in the formula")?
+            }
+            InChIError::UnplacedCharge { .. } => write!(f, "No atom can take the charge")?,
+            InChIError::HydrogenCount { .. } => {
+                write!(f, "Different number of hydrogens from the formula")?
+            }
+        }
+        if let Some(layer) = self.layer() {
+            write!(f, " in the {layer} layer")?;
+        }
+        write!(f, " at offset {}", self.offset())
+    }
+}
+
+impl error::Error for InChIError {}
@@ -0,0 +1,96 @@
+use super::{sha256::sha256, InChI};
+
+/// The layers hashed into the first block of the key, after the formula.
+const MAJOR_LAYERS: &[char] = &['c', 'h', 'q'];
+
+/// Minor layers shorter than this are repeated before hashing.
+const MINOR_REPEAT_LENGTH: usize = 255;
+
+impl InChI {
+    /// The InChIKey: the hash of the formula, connection, hydrogen and charge
+    /// layers, the hash of the other layers apart from protonation, the
+    /// standard and version flags and the protonation character.
+    pub fn key(&self) -> String {
+        let inchi = self.to_string();
+        let (_, layers) = inchi.split_once('/').unwrap();
+
+        let mut major = String::new();
+        let mut minor = String::new();
+        for (k, layer) in layers.split('/').enumerate() {
+            if k == 0 {
+                major.push_str(layer);
+            } else if minor.is_empty() && layer.starts_with(MAJOR_LAYERS) {
+                major.push('/');
+                major.push_str(layer);
+            } else if layer.starts_with('p') {
+                // Written as the protonation character instead
+            } else {
+                minor.push('/');
+                minor.push_str(layer);
+            }
+        }
+        if minor.len() < MINOR_REPEAT_LENGTH {
+            minor = minor.repeat(2);
+        }
+
+        let major = sha256(major.as_bytes());
+        let minor = sha256(minor.as_bytes());
+        let protonation = (b'N' as i32 + self.protons.clamp(-13, 12)) as u8 as char;
+
+        let mut key = String::new();
+        for k in 0..4 {
+            key.push_str(&triplet(bits(&major, 14 * k, 14)));
+        }
+        key.push_str(&doublet(bits(&major, 56, 9)));
+        key.push('-');
+        for k in 0..2 {
+            key.push_str(&triplet(bits(&minor, 14 * k, 14)));
+        }
+        key.push_str(&doublet(bits(&minor, 28, 9)));
+        // Version 1
+        key.push(if self.standard { 'S' } else { 'N' });
+        key.push_str("A-");
+        key.push(protonation);
+        key
+    }
+}
+
+/// Reads `count` bits of the hash from `start`, least significant first.
+fn bits(hash: &[u8], start: usize, count: usize) -> usize {
+    (0..count)
+        .map(|k| {
+            let bit = start + k;
+            (((hash[bit / 8] >> (bit % 8)) & 1) as usize) << k
+        })
+        .sum()
+}
+
+/// Encodes 14 bits as three letters, counting through the triplets in
+/// alphabetical order without those starting with E or from TAA to TTV.
+fn triplet(value: usize) -> String {
+    const FIRST: &[u8] = b"ABCDFGHIJKLMNOPQRSTUVWXYZ";
+    const SKIPPED_FROM: usize = 18 * 26 * 26;
+    const SKIPPED: usize = 19 * 26 + 22;
+
+    let value = if value >= SKIPPED_FROM {
+        value + SKIPPED
+    } else {
+        value
+    };
+    [
+        FIRST[value / (26 * 26)],
+        b'A' + (value / 26 % 26) as u8,
+        b'A' + (value % 26) as u8,
+    ]
+    .map(char::from)
+    .iter()
+    .collect()
+}
+
+/// Encodes 9 bits as two letters.
+fn doublet(value: usize) -> String {
+    [b'A' + (value / 26) as u8, b'A' + (value % 26) as u8]
+        .map(char::from)
+        .iter()
+        .collect()
+}
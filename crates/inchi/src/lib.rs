@@ -1,135 +1,642 @@
-use std::{collections::BTreeMap, iter, ops::RangeInclusive};
+use std::{iter, ops::RangeInclusive};
 
-use blue_book::Element;
-use petgraph::graph::UnGraph;
+use blue_book::{
+    formula::Formula,
+    graph::{
+        stereo::{Chirality, Stereo as GraphStereo},
+        Graph,
+    },
+    Element,
+};
+use petgraph::graph::{NodeIndex, UnGraph};
 
+mod canonical;
+mod consistency;
+mod error;
+mod key;
 mod parser;
 mod scanner;
+mod sha256;
+mod tautomer;
+mod writer;
+
+pub use consistency::Mismatch;
+pub use error::{InChIError, Layer};
+pub use tautomer::Tautomers;
 
 #[derive(Debug, Default, Clone)]
 pub struct InChI {
+    /// Whether this is a standard InChI, starting with `InChI=1S/` rather than
+    /// `InChI=1/`.
+    standard: bool,
+    /// The formula of all the components together.
     formula: Formula,
-    connections: Connections,
-    hydrogens: Hydrogens,
+    components: Vec<Component>,
+    protons: i32,
+    stereo_type: Option<StereoType>,
+    isotopic_stereo_type: Option<StereoType>,
+    fixed_hydrogens: Option<FixedHydrogens>,
+    reconnected: Option<Box<InChI>>,
 }
 
+/// A disconnected part of the structure, such as one ion of a salt or one
+/// molecule of water in a hydrate. Each component numbers its atoms from
+/// one, and has its own part of each layer, separated by `;`.
 #[derive(Debug, Default, Clone)]
-struct Formula {
-    atom_counts: BTreeMap<Element, usize>,
+pub struct Component {
+    formula: Formula,
+    connections: Connections,
+    hydrogens: Hydrogens,
+    charge: i32,
+    stereo: Stereo,
+    isotopic: Option<Isotopic>,
 }
 
-#[derive(Debug, Default, Clone)]
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
 struct Connections {
     connections: Vec<(usize, usize)>,
 }
 
-#[derive(Debug, Default, Clone)]
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
 struct Hydrogens {
     immobile_hydrogens: Vec<(Vec<RangeInclusive<usize>>, usize)>,
     mobile_hydrogens: Vec<(usize, Vec<usize>)>,
 }
 
+/// The parity of a stereocentre or stereogenic double bond, relative to the
+/// canonical numbers of its neighbours.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Parity {
+    /// `-`
+    Odd,
+    /// `+`
+    Even,
+    /// `?`: the atom is stereogenic but its configuration is not known.
+    Unknown,
+    /// `u`: the configuration is not defined by the structure it came from.
+    Undefined,
+}
+
+/// The `/s` layer.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StereoType {
+    Absolute,
+    Relative,
+    Racemic,
+}
+
+/// The `/b`, `/t` and `/m` layers of a component.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct Stereo {
+    /// The atoms at either end of each stereogenic double bond, the larger
+    /// number first.
+    pub double_bonds: Vec<(usize, usize, Parity)>,
+    pub tetrahedral: Vec<(usize, Parity)>,
+    /// Whether the structure is the mirror image of the one described by
+    /// `tetrahedral`.
+    pub inverted: Option<bool>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HydrogenIsotope {
+    /// `H`
+    Protium,
+    /// `D`
+    Deuterium,
+    /// `T`
+    Tritium,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct IsotopicAtom {
+    pub atom: usize,
+    /// The mass number less the atomic mass of the element, rounded.
+    pub mass_shift: Option<i32>,
+    /// The isotopic hydrogens on the atom.
+    pub hydrogens: Vec<(HydrogenIsotope, usize)>,
+}
+
+/// The `/i` layer of a component, and its stereo layers in the isotopic
+/// structure where they differ from those of the main one.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct Isotopic {
+    pub atoms: Vec<IsotopicAtom>,
+    /// Isotopic hydrogens among the mobile hydrogens, from the isotopic `/h`
+    /// layer.
+    pub exchangeable_hydrogens: Vec<(HydrogenIsotope, usize)>,
+    pub stereo: Stereo,
+}
+
+/// The `/f` layers of a non-standard InChI, with the hydrogens that were
+/// mobile fixed in place, and the layers that change with them.
+#[derive(Debug, Default, Clone)]
+pub struct FixedHydrogens {
+    /// Whether the formula layer is written, as it is where fixing the
+    /// hydrogens changes the formula of a component.
+    has_formula: bool,
+    /// The components, without connections.
+    components: Vec<Component>,
+    stereo_type: Option<StereoType>,
+    isotopic_stereo_type: Option<StereoType>,
+}
+
 impl InChI {
-    pub fn isomers(self) -> Vec<InChI> {
-        let mut isomers = Vec::new();
-        self.clone().collect_isomers(&mut isomers);
-        isomers
+    pub fn is_standard(&self) -> bool {
+        self.standard
     }
 
-    fn collect_isomers(self, isomers: &mut Vec<InChI>) {
-        let Some((count, possible_indices)) = self.hydrogens.mobile_hydrogens.first() else {
-            isomers.push(self);
-            return;
-        };
+    pub fn formula(&self) -> &Formula {
+        &self.formula
+    }
 
-        let mut mobile_hydrogens = self.hydrogens.mobile_hydrogens.clone();
-        if *count == 1 {
-            mobile_hydrogens.remove(0);
-        } else {
-            mobile_hydrogens[0].0 -= 1;
-        }
+    pub fn components(&self) -> &[Component] {
+        &self.components
+    }
 
-        for &i in possible_indices {
-            let mut new = self.clone();
-            new.hydrogens.mobile_hydrogens.clone_from(&mobile_hydrogens);
-            new.hydrogens.immobile_hydrogens.push((vec![i..=i], 1));
-            if new.is_plausible() {
-                new.collect_isomers(isomers);
-            }
-        }
+    /// The net charge, from the `/q` layer.
+    pub fn charge(&self) -> i32 {
+        self.components
+            .iter()
+            .map(|component| component.charge)
+            .sum()
     }
 
-    /// Quickly checks if a compound is plausible by checking if the number of
-    /// bonds for each atom is less than its standard valence.
-    fn is_plausible(&self) -> bool {
-        let atom_count = self
-            .formula
+    /// The number of protons added to, or removed from, the structure
+    /// described by the other layers, from the `/p` layer.
+    pub fn protons(&self) -> i32 {
+        self.protons
+    }
+
+    /// The `/s` layer, which applies to every component.
+    pub fn stereo_type(&self) -> Option<StereoType> {
+        self.stereo_type
+    }
+
+    /// The `/s` layer of the isotopic structure.
+    pub fn isotopic_stereo_type(&self) -> Option<StereoType> {
+        self.isotopic_stereo_type
+    }
+
+    pub fn fixed_hydrogens(&self) -> Option<&FixedHydrogens> {
+        self.fixed_hydrogens.as_ref()
+    }
+
+    /// The structure with bonds to metals kept, from the `/r` layer.
+    pub fn reconnected(&self) -> Option<&InChI> {
+        self.reconnected.as_deref()
+    }
+}
+
+impl Component {
+    pub fn formula(&self) -> &Formula {
+        &self.formula
+    }
+
+    /// The charge, from this component's part of the `/q` layer.
+    pub fn charge(&self) -> i32 {
+        self.charge
+    }
+
+    /// The atoms with a fixed number of hydrogens, and those numbers.
+    pub fn immobile_hydrogens(&self) -> &[(Vec<RangeInclusive<usize>>, usize)] {
+        &self.hydrogens.immobile_hydrogens
+    }
+
+    pub fn stereo(&self) -> &Stereo {
+        &self.stereo
+    }
+
+    pub fn isotopic(&self) -> Option<&Isotopic> {
+        self.isotopic.as_ref()
+    }
+
+    /// The elements of the atoms other than hydrogen, in numbered order. A
+    /// formula of hydrogen alone, as in `H2`, numbers one of its hydrogens.
+    fn elements(&self) -> impl Iterator<Item = Element> + '_ {
+        let hydrogen_only = self.formula.atom_count() > 0
+            && self.formula.atom_count() == self.formula.count(Element::Hydrogen);
+        self.formula
             .atom_counts
             .iter()
-            .map(|(_, &count)| count)
-            .sum();
+            .filter(|(&element, _)| element != Element::Hydrogen)
+            .flat_map(|(&element, &count)| iter::repeat_n(element, count))
+            .chain(hydrogen_only.then_some(Element::Hydrogen))
+    }
 
-        let mut degrees = vec![0; atom_count];
-        for &(i, j) in &self.connections.connections {
-            degrees[i - 1] += 1;
-            degrees[j - 1] += 1;
-        }
+    /// The number of hydrogens on the numbered atoms, which the `/h` layer
+    /// places.
+    fn hydrogen_count(&self) -> usize {
+        let numbered = self
+            .elements()
+            .filter(|&element| element == Element::Hydrogen)
+            .count();
+        self.formula.count(Element::Hydrogen) - numbered
+    }
+
+    /// The number of atoms other than hydrogen, which are the ones numbered
+    /// in the other layers.
+    fn atom_count(&self) -> usize {
+        self.elements().count()
+    }
+
+    /// The number of immobile hydrogens on each atom other than hydrogen.
+    fn hydrogen_counts(&self) -> Vec<usize> {
+        let mut hydrogen_counts = vec![0; self.atom_count()];
         for (ranges, count) in &self.hydrogens.immobile_hydrogens {
             for i in ranges.iter().cloned().flatten() {
-                degrees[i - 1] += count;
+                hydrogen_counts[i - 1] += count;
             }
         }
+        hydrogen_counts
+    }
+}
 
-        let elements = self
-            .formula
-            .atom_counts
+impl Hydrogens {
+    /// The number of hydrogens, immobile and mobile.
+    fn count(&self) -> usize {
+        let immobile = self
+            .immobile_hydrogens
             .iter()
-            .filter(|(&element, _)| element != Element::Hydrogen)
-            .flat_map(|(&element, &count)| iter::repeat(element).take(count));
+            .map(|(ranges, count)| ranges.iter().cloned().flatten().count() * count);
+        let mobile = self.mobile_hydrogens.iter().map(|(count, _)| count);
+        immobile.sum::<usize>() + mobile.sum::<usize>()
+    }
+}
+
+impl FixedHydrogens {
+    /// The components, with their fixed hydrogens as immobile hydrogens.
+    pub fn components(&self) -> &[Component] {
+        &self.components
+    }
+
+    pub fn stereo_type(&self) -> Option<StereoType> {
+        self.stereo_type
+    }
+
+    pub fn isotopic_stereo_type(&self) -> Option<StereoType> {
+        self.isotopic_stereo_type
+    }
+}
 
-        elements
-            .zip(degrees)
-            .all(|(element, degree)| degree <= element.standard_bonding_number() as usize)
+impl Parity {
+    /// The parity of the mirror image.
+    pub fn inverse(self) -> Self {
+        match self {
+            Parity::Odd => Parity::Even,
+            Parity::Even => Parity::Odd,
+            parity => parity,
+        }
     }
 }
 
-impl From<&InChI> for UnGraph<Element, ()> {
-    fn from(value: &InChI) -> Self {
-        let mut graph = UnGraph::new_undirected();
+impl Stereo {
+    /// The tetrahedral parities of the structure rather than its mirror
+    /// image, where `/m1` says they differ.
+    pub fn parities(&self) -> impl Iterator<Item = (usize, Parity)> + '_ {
+        let inverted = self.inverted == Some(true);
+        self.tetrahedral
+            .iter()
+            .map(move |&(atom, parity)| (atom, if inverted { parity.inverse() } else { parity }))
+    }
+}
 
-        let mut terminal_indices = Vec::new();
-        let mut skeletal_indices = Vec::new();
-        for (&element, &count) in &value.formula.atom_counts {
-            for _ in 0..count {
-                let node = graph.add_node(element);
+/// The mass number of an isotope of `element`, or `None` if the mass shift
+/// takes it out of range.
+fn mass_number(element: Element, mass_shift: i32) -> Option<u16> {
+    let mass_number = (element.atomic_mass().round() as i32).checked_add(mass_shift)?;
+    u16::try_from(mass_number)
+        .ok()
+        .filter(|&mass_number| mass_number > 0)
+}
 
-                if element == Element::Hydrogen {
-                    terminal_indices.push(node);
-                } else {
-                    skeletal_indices.push(node);
+impl HydrogenIsotope {
+    pub fn mass_number(self) -> u16 {
+        match self {
+            HydrogenIsotope::Protium => 1,
+            HydrogenIsotope::Deuterium => 2,
+            HydrogenIsotope::Tritium => 3,
+        }
+    }
+}
+
+impl TryFrom<&InChI> for Graph {
+    type Error = &'static str;
+
+    /// Builds the graph from each component in turn, with its hydrogens after
+    /// its other atoms, and no pi bonds, which can be added with
+    /// [`Graph::kekulize`]. There must be no mobile hydrogens: use
+    /// [`InChI::tautomer`] to place them.
+    ///
+    /// Protons from the `/p` layer are added to or removed from heteroatoms,
+    /// and the charge is placed on the atoms that need it to have a Kekulé
+    /// structure.
+    fn try_from(value: &InChI) -> Result<Self, Self::Error> {
+        value.check_immobile()?;
+        Ok(value.graph().0)
+    }
+}
+
+impl InChI {
+    /// Fails if any component has mobile hydrogens, which have no place in
+    /// the graph.
+    fn check_immobile(&self) -> Result<(), &'static str> {
+        if self
+            .components
+            .iter()
+            .any(|component| !component.hydrogens.mobile_hydrogens.is_empty())
+        {
+            return Err("Mobile hydrogens must be placed with InChI::tautomer");
+        }
+        Ok(())
+    }
+
+    /// Builds the graph, with the index of the first atom of each component.
+    /// Mobile hydrogens are left out.
+    fn graph(&self) -> (Graph, Vec<usize>) {
+        let mut elements = Vec::new();
+        let mut hydrogen_counts = Vec::new();
+        for component in &self.components {
+            elements.extend(component.elements());
+            hydrogen_counts.extend(component.hydrogen_counts());
+        }
+        place_protons(&elements, &mut hydrogen_counts, self.protons);
+
+        let mut graph = Graph::default();
+        let mut starts = Vec::new();
+        let mut hydrogen_counts = hydrogen_counts.into_iter();
+        for component in &self.components {
+            let counts = hydrogen_counts
+                .by_ref()
+                .take(component.atom_count())
+                .collect::<Vec<_>>();
+            starts.push(graph.atoms.len());
+            graph = graph.merge(component.graph(&counts));
+        }
+
+        let atoms = (0..graph.atoms.len()).collect::<Vec<_>>();
+        place_charges(&mut graph, &atoms, self.protons);
+
+        (graph, starts)
+    }
+}
+
+impl Component {
+    fn graph(&self, hydrogen_counts: &[usize]) -> Graph {
+        let mut graph = Graph {
+            atoms: self.elements().collect(),
+            ..Default::default()
+        };
+        let atom_count = graph.atoms.len();
+
+        for &(i, j) in &self.connections.connections {
+            graph.bonds.push((i - 1, j - 1));
+        }
+
+        // The mass numbers of the first hydrogens on each atom
+        let mut hydrogen_isotopes = vec![Vec::new(); atom_count];
+        if let Some(isotopic) = &self.isotopic {
+            for atom in &isotopic.atoms {
+                let i = atom.atom - 1;
+                if let Some(mass_shift) = atom.mass_shift {
+                    let mass_number = mass_number(graph.atoms[i], mass_shift)
+                        .expect("mass shifts are checked when parsing");
+                    graph.isotopes.push((i, mass_number));
+                }
+                for &(isotope, count) in &atom.hydrogens {
+                    hydrogen_isotopes[i].extend(iter::repeat_n(isotope.mass_number(), count));
                 }
             }
-        }
 
-        for &(i, j) in &value.connections.connections {
-            let a = skeletal_indices[i - 1];
-            let b = skeletal_indices[j - 1];
-            graph.add_edge(a, b, ());
+            // Exchangeable hydrogens are on the heteroatoms
+            let mut exchangeable = isotopic
+                .exchangeable_hydrogens
+                .iter()
+                .flat_map(|&(isotope, count)| iter::repeat_n(isotope.mass_number(), count));
+            for i in 0..atom_count {
+                if graph.atoms[i] == Element::Carbon {
+                    continue;
+                }
+                while hydrogen_isotopes[i].len() < hydrogen_counts[i] {
+                    let Some(mass_number) = exchangeable.next() else {
+                        break;
+                    };
+                    hydrogen_isotopes[i].push(mass_number);
+                }
+            }
         }
 
-        let mut terminal_indices = terminal_indices.into_iter();
-        for (ranges, count) in &value.hydrogens.immobile_hydrogens {
-            for i in ranges.iter().cloned().flatten() {
-                let c = skeletal_indices[i - 1];
-                for _ in 0..*count {
-                    let h = terminal_indices.next().unwrap();
-                    graph.add_edge(c, h, ());
+        for i in 0..atom_count {
+            for k in 0..hydrogen_counts[i] {
+                let h = graph.atoms.len();
+                graph.atoms.push(Element::Hydrogen);
+                graph.bonds.push((i, h));
+                if let Some(&mass_number) = hydrogen_isotopes[i].get(k) {
+                    graph.isotopes.push((h, mass_number));
                 }
             }
         }
 
-        assert!(value.hydrogens.mobile_hydrogens.is_empty());
+        let atoms = (0..atom_count).collect::<Vec<_>>();
+        place_charges(&mut graph, &atoms, self.charge);
+
+        add_stereo(&mut graph, &self.stereo);
+        if let Some(isotopic) = &self.isotopic {
+            add_stereo(&mut graph, &isotopic.stereo);
+        }
 
         graph
     }
 }
+
+/// Adds hydrogens to, or removes them from, heteroatoms: added to those
+/// furthest left in the periodic table first, as in ammonium ions, and
+/// removed from those furthest right, as in carboxylate ions. Only atoms in
+/// groups 15 to 17 are protonated, never metals.
+fn place_protons(elements: &[Element], hydrogen_counts: &mut [usize], protons: i32) {
+    let mut heteroatoms = (0..elements.len())
+        .filter(|&i| elements[i].group() >= 15)
+        .collect::<Vec<_>>();
+    heteroatoms.sort_by_key(|&i| elements[i].group());
+
+    for _ in 0..protons.unsigned_abs() {
+        if protons > 0 {
+            if let Some(&i) = heteroatoms.first() {
+                hydrogen_counts[i] += 1;
+            }
+        } else if let Some(&i) = heteroatoms.iter().rev().find(|&&i| hydrogen_counts[i] > 0) {
+            hydrogen_counts[i] -= 1;
+        }
+    }
+}
+
+/// Places positive charges on atoms with more bonds than their standard
+/// bonding number, as in quaternary ammonium ions, and the rest one at a time
+/// on the first of `atoms`, uncharged atoms before charged ones and
+/// heteroatoms before carbon, for which the graph still has a Kekulé
+/// structure.
+fn place_charges(graph: &mut Graph, atoms: &[usize], mut charge: i32) {
+    let mut atoms = atoms
+        .iter()
+        .copied()
+        .filter(|&i| graph.atoms[i] != Element::Hydrogen)
+        .collect::<Vec<_>>();
+
+    for &i in &atoms {
+        let excess = graph
+            .neighbors(i)
+            .count()
+            .saturating_sub(graph.bonding_number(i)) as i32;
+        if charge > 0 && excess > 0 && graph.atoms[i].group() >= 15 {
+            let placed = excess.min(charge);
+            graph.charges.push((i, placed as i8));
+            charge -= placed;
+        }
+    }
+
+    while charge != 0 {
+        let unit = charge.signum() as i8;
+        atoms.sort_by_key(|&i| (graph.charge(i) != 0, graph.atoms[i] == Element::Carbon));
+        let mut options = atoms
+            .iter()
+            .filter(|&&i| graph.charge(i) * unit >= 0)
+            .map(|&i| {
+                let mut charged = graph.clone();
+                match charged.charges.iter_mut().find(|(j, _)| *j == i) {
+                    Some((_, charge)) => *charge += unit,
+                    None => charged.charges.push((i, unit)),
+                }
+                (i, charged)
+            })
+            .filter(|(i, charged)| charged.neighbors(*i).count() <= charged.bonding_number(*i))
+            .collect::<Vec<_>>();
+        if options.is_empty() {
+            break;
+        }
+        let k = options
+            .iter()
+            .position(|(_, charged)| charged.clone().kekulize().is_ok())
+            .unwrap_or_default();
+        *graph = options.swap_remove(k).1;
+        charge -= unit as i32;
+    }
+}
+
+/// Adds the stereo layers. Parities are relative to the neighbours in order
+/// of canonical number, hydrogens first: odd when, seen from the first, the
+/// rest are in anticlockwise order, or when the highest numbered neighbours
+/// of a double bond are on the same side.
+fn add_stereo(graph: &mut Graph, stereo: &Stereo) {
+    let sorted_neighbors = |graph: &Graph, i: usize| {
+        let mut neighbors = graph.neighbors(i).collect::<Vec<_>>();
+        neighbors.sort_by_key(|&j| (graph.atoms[j] != Element::Hydrogen, j));
+        neighbors
+    };
+    let is_odd = |parity: Parity| match parity {
+        Parity::Odd => Some(true),
+        Parity::Even => Some(false),
+        Parity::Unknown | Parity::Undefined => None,
+    };
+
+    for (atom, parity) in stereo.parities() {
+        let centre = atom - 1;
+        let (Ok(neighbors), Some(odd)) = (
+            <[usize; 4]>::try_from(sorted_neighbors(graph, centre)),
+            is_odd(parity),
+        ) else {
+            continue;
+        };
+        let chirality = if odd {
+            Chirality::Anticlockwise
+        } else {
+            Chirality::Clockwise
+        };
+        graph.stereo.push(GraphStereo::Tetrahedral {
+            centre,
+            neighbors,
+            chirality,
+        });
+    }
+
+    for &(a, b, parity) in &stereo.double_bonds {
+        let (a, b) = (a - 1, b - 1);
+        let highest = |i: usize, other: usize| {
+            sorted_neighbors(graph, i)
+                .into_iter()
+                .rfind(|&j| j != other)
+        };
+        let (Some(c), Some(d), Some(together)) = (highest(a, b), highest(b, a), is_odd(parity))
+        else {
+            continue;
+        };
+        graph.stereo.push(GraphStereo::DoubleBond {
+            atoms: [c, a, b, d],
+            together,
+        });
+    }
+}
+
+impl TryFrom<&InChI> for UnGraph<Element, ()> {
+    type Error = &'static str;
+
+    fn try_from(value: &InChI) -> Result<Self, Self::Error> {
+        Ok(UnGraph::from(&Graph::try_from(value)?))
+    }
+}
+
+/// An atom of the graph built from an InChI.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Atom {
+    pub element: Element,
+    pub charge: i8,
+    pub mass_number: Option<u16>,
+    /// The tetrahedral parity, as given by [`Stereo::parities`].
+    pub parity: Option<Parity>,
+}
+
+/// A bond of the graph built from an InChI.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct Bond {
+    /// The double bond parity.
+    pub parity: Option<Parity>,
+}
+
+impl TryFrom<&InChI> for UnGraph<Atom, Bond> {
+    type Error = &'static str;
+
+    /// Numbers the atoms as in the [`Graph`], so the atoms other than
+    /// hydrogen of each component come first, in order of canonical number.
+    fn try_from(value: &InChI) -> Result<Self, Self::Error> {
+        value.check_immobile()?;
+        let (graph, starts) = value.graph();
+
+        let mut ungraph = UnGraph::new_undirected();
+        for (i, &element) in graph.atoms.iter().enumerate() {
+            ungraph.add_node(Atom {
+                element,
+                charge: graph.charge(i),
+                mass_number: graph.mass_number(i),
+                parity: None,
+            });
+        }
+        for &(a, b) in &graph.bonds {
+            ungraph.add_edge(NodeIndex::new(a), NodeIndex::new(b), Bond::default());
+        }
+
+        for (component, start) in value.components.iter().zip(starts) {
+            let node = |atom: usize| NodeIndex::new(start + atom - 1);
+            let isotopic = component.isotopic.as_ref().map(|isotopic| &isotopic.stereo);
+            for stereo in iter::once(&component.stereo).chain(isotopic) {
+                for (atom, parity) in stereo.parities() {
+                    ungraph[node(atom)].parity = Some(parity);
+                }
+                for &(a, b, parity) in &stereo.double_bonds {
+                    if let Some(edge) = ungraph.find_edge(node(a), node(b)) {
+                        ungraph[edge].parity = Some(parity);
+                    }
+                }
+            }
+        }
+
+        Ok(ungraph)
+    }
+}
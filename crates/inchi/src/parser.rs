@@ -1,128 +1,352 @@
-use std::{collections::BTreeMap, str::FromStr};
+use std::{
+    iter::{self, Peekable},
+    str::FromStr,
+    vec,
+};
+
+use blue_book::{graph::Graph, Element};
 
 use super::{
+    error::{InChIError, Layer},
+    mass_number,
     scanner::{Scanner, Token},
-    Connections, Formula, Hydrogens, InChI,
+    Component, Connections, FixedHydrogens, Formula, HydrogenIsotope, Hydrogens, InChI, Isotopic,
+    IsotopicAtom, Parity, Stereo, StereoType,
 };
 
+const PREFIX: &str = "InChI=1S/";
+const NON_STANDARD_PREFIX: &str = "InChI=1/";
+
+/// The layers separated by slashes, with their offsets in the whole string.
+type Parts<'a> = Peekable<vec::IntoIter<(&'a str, usize)>>;
+
 impl FromStr for InChI {
-    type Err = &'static str;
+    type Err = InChIError;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let mut parts = s.split('/').peekable();
-
-        if parts.next() != Some("InChI=1S") {
-            return Err("String does not contain InChI prefix");
+        let (standard, prefix) = if s.starts_with(PREFIX) {
+            (true, PREFIX)
+        } else if s.starts_with(NON_STANDARD_PREFIX) {
+            (false, NON_STANDARD_PREFIX)
+        } else {
+            return Err(InChIError::MissingPrefix);
+        };
+
+        let mut offset = prefix.len();
+        let mut parts = s[prefix.len()..]
+            .split('/')
+            .map(|part| {
+                let start = offset;
+                offset += part.len() + 1;
+                (part, start)
+            })
+            .collect::<Vec<_>>()
+            .into_iter()
+            .peekable();
+
+        let (formula, offset) = parts.next().unwrap();
+        let inchi = InChI::parse(formula, offset, Layer::Formula, standard, &mut parts)?;
+        if let Some((_, offset)) = parts.next() {
+            return Err(InChIError::UnexpectedLayer { offset });
         }
+        Ok(inchi)
+    }
+}
 
-        let mut formula = Formula::default();
-        if let Some(part) = parts.next() {
-            formula = part.parse()?;
-        }
+/// Takes the next layer if it starts with `prefix`, and scans the rest of it.
+fn layer<'a>(parts: &mut Parts<'a>, prefix: char, layer: Layer) -> Option<Scanner<'a>> {
+    parts
+        .next_if(|(part, _)| part.starts_with(prefix))
+        .map(|(part, offset)| Scanner::new(&part[1..], offset + 1, layer))
+}
 
-        let mut connections = Connections::default();
-        if let Some(part) = parts.next().and_then(|p| p.strip_prefix('c')) {
-            connections = part.parse()?;
-        }
+/// Splits off a leading multiplier, as in `2H2O` or `2*1H2`, returning the
+/// multiplier and the length of the prefix it takes up.
+fn multiplier(
+    part: &str,
+    separator: &str,
+    layer: Layer,
+    offset: usize,
+) -> Result<(usize, usize), InChIError> {
+    let digits = part
+        .find(|c: char| !c.is_ascii_digit())
+        .unwrap_or(part.len());
+    let skip = digits + separator.len();
+    if digits == 0 || !part[digits..].starts_with(separator) || part.len() == skip {
+        return Ok((1, 0));
+    }
+    let multiplier = part[..digits]
+        .parse()
+        .map_err(|_| InChIError::InvalidNumber { layer, offset })?;
+    Ok((multiplier, skip))
+}
 
-        let mut hydrogens = Hydrogens::default();
-        if let Some(part) = parts.next().and_then(|p| p.strip_prefix('h')) {
-            hydrogens = part.parse()?;
-        }
+/// Parses the formula of each component, separated by `.`, at the start of
+/// `layer`.
+fn parse_formulas(formula: &str, offset: usize, layer: Layer) -> Result<Vec<Formula>, InChIError> {
+    let mut formulas = Vec::new();
+    if formula.is_empty() {
+        return Ok(formulas);
+    }
 
-        Ok(Self {
-            formula,
-            connections,
-            hydrogens,
-        })
+    let mut offset = offset;
+    for part in formula.split('.') {
+        let (count, skip) = multiplier(part, "", layer, offset)?;
+        let parsed: Formula =
+            part[skip..]
+                .parse()
+                .map_err(|message| InChIError::InvalidFormula {
+                    layer,
+                    offset: offset + skip,
+                    message,
+                })?;
+        formulas.extend(iter::repeat_n(parsed, count));
+        offset += part.len() + 1;
     }
+    Ok(formulas)
 }
 
-impl FromStr for Formula {
-    type Err = &'static str;
-
-    fn from_str(mut s: &str) -> Result<Self, Self::Err> {
-        let mut atom_counts = BTreeMap::new();
+/// Takes the next layer if it starts with `prefix`, and parses the part of it
+/// for each component. Parts are separated by `;`, and `n*` repeats a part
+/// for `n` components. Components without a part are left as they are.
+fn component_layer<'a>(
+    parts: &mut Parts<'a>,
+    prefix: char,
+    layer: Layer,
+    components: &mut [Component],
+    mut parse: impl FnMut(&mut Component, Scanner<'a>) -> Result<(), InChIError>,
+) -> Result<(), InChIError> {
+    let Some((part, offset)) = parts.next_if(|(part, _)| part.starts_with(prefix)) else {
+        return Ok(());
+    };
+
+    let mut components = components.iter_mut();
+    let mut offset = offset + 1;
+    for text in part[1..].split(';') {
+        let (count, skip) = multiplier(text, "*", layer, offset)?;
+        for _ in 0..count {
+            let component = components
+                .next()
+                .ok_or(InChIError::TooManyComponents { layer, offset })?;
+            if text.len() > skip {
+                parse(component, Scanner::new(&text[skip..], offset + skip, layer))?;
+            }
+        }
+        offset += text.len() + 1;
+    }
+    Ok(())
+}
 
-        while !s.is_empty() {
-            let len = s[1..]
-                .find(|c: char| !c.is_ascii_lowercase())
-                .map_or(s.len(), |i| i + 1);
-            let element = s[..len].parse()?;
-            s = &s[len..];
+impl InChI {
+    /// Parses the layers that follow a formula: those of the main structure,
+    /// or of the reconnected structure after `/r`, as `formula_layer` says.
+    fn parse(
+        formula: &str,
+        offset: usize,
+        formula_layer: Layer,
+        standard: bool,
+        parts: &mut Parts,
+    ) -> Result<Self, InChIError> {
+        let mut components = parse_formulas(formula, offset, formula_layer)?
+            .into_iter()
+            .map(|formula| Component {
+                formula,
+                ..Default::default()
+            })
+            .collect::<Vec<_>>();
 
-            let len = s.find(|c: char| !c.is_ascii_digit()).unwrap_or(s.len());
-            let count;
-            if len == 0 {
-                count = 1;
-            } else {
-                count = s[..len].parse().unwrap();
-                s = &s[len..];
+        let mut formula = Formula::default();
+        for component in &components {
+            for (&element, &count) in &component.formula.atom_counts {
+                *formula.atom_counts.entry(element).or_default() += count;
             }
+        }
 
-            atom_counts.insert(element, count);
+        component_layer(
+            parts,
+            'c',
+            Layer::Connections,
+            &mut components,
+            |component, scanner| {
+                component.connections = Connections::parse(scanner, component.atom_count())?;
+                Ok(())
+            },
+        )?;
+
+        component_layer(
+            parts,
+            'h',
+            Layer::Hydrogens,
+            &mut components,
+            |component, scanner| {
+                let offset = scanner.offset();
+                component.hydrogens = Hydrogens::parse(scanner, component.atom_count())?;
+                if component.hydrogens.count() != component.hydrogen_count() {
+                    return Err(InChIError::HydrogenCount { offset });
+                }
+                Ok(())
+            },
+        )?;
+
+        component_layer(
+            parts,
+            'q',
+            Layer::Charge,
+            &mut components,
+            |component, scanner| {
+                let offset = scanner.offset();
+                component.charge = signed_layer(scanner)?;
+                let graph = component.graph(&component.hydrogen_counts());
+                if charge(&graph) != component.charge {
+                    return Err(InChIError::UnplacedCharge {
+                        layer: Layer::Charge,
+                        offset,
+                    });
+                }
+                Ok(())
+            },
+        )?;
+
+        let mut protons = 0;
+        let mut protons_offset = 0;
+        if let Some(scanner) = layer(parts, 'p', Layer::Protons) {
+            protons_offset = scanner.offset();
+            protons = signed_layer(scanner)?;
+        }
+
+        let stereo_type = parse_stereo(parts, &mut components, |component| &mut component.stereo)?;
+        let isotopic_stereo_type = parse_isotopic(parts, &mut components, protons)?;
+
+        let mut fixed_hydrogens = None;
+        if let Some((part, offset)) = parts.next_if(|(part, _)| part.starts_with('f')) {
+            fixed_hydrogens = Some(FixedHydrogens::parse(
+                &part[1..],
+                offset + 1,
+                &components,
+                protons,
+                parts,
+            )?);
         }
 
-        Ok(Self { atom_counts })
+        let mut reconnected = None;
+        if let Some((part, offset)) = parts.next_if(|(part, _)| part.starts_with('r')) {
+            let inchi = InChI::parse(&part[1..], offset + 1, Layer::Reconnected, standard, parts)?;
+            reconnected = Some(Box::new(inchi));
+        }
+
+        let inchi = Self {
+            standard,
+            formula,
+            components,
+            protons,
+            stereo_type,
+            isotopic_stereo_type,
+            fixed_hydrogens,
+            reconnected,
+        };
+        if protons != 0 && charge(&inchi.graph().0) != inchi.charge() + protons {
+            return Err(InChIError::UnplacedCharge {
+                layer: Layer::Protons,
+                offset: protons_offset,
+            });
+        }
+        Ok(inchi)
     }
 }
 
-impl FromStr for Connections {
-    type Err = &'static str;
+/// The total charge on the atoms of a graph.
+fn charge(graph: &Graph) -> i32 {
+    graph.charges.iter().map(|&(_, charge)| charge as i32).sum()
+}
 
-    fn from_str(s: &str) -> Result<Self, Self::Err> {
+/// Reads an atom number, which must be between one and the number of atoms
+/// other than hydrogen.
+fn atom_number(
+    scanner: &mut Scanner,
+    layer: Layer,
+    atom_count: usize,
+) -> Result<usize, InChIError> {
+    let offset = scanner.offset();
+    let number = scanner.expect_numeric()?;
+    if !(1..=atom_count).contains(&number) {
+        return Err(InChIError::InvalidNumber { layer, offset });
+    }
+    Ok(number)
+}
+
+impl Connections {
+    fn parse(mut scanner: Scanner, atom_count: usize) -> Result<Self, InChIError> {
         let mut connections = Vec::new();
 
-        let scanner = Scanner::new(s);
         let mut last_index = None;
         let mut last_last_index = None;
         let mut stack = Vec::new();
-        for token in scanner {
-            match token {
-                Token::Numeric(i) => {
-                    if let Some(last_index) = last_index {
-                        connections.push((last_index, i));
-                    }
-                    last_last_index = last_index;
-                    last_index = Some(i);
+        loop {
+            let offset = scanner.offset();
+            if scanner.lookahead_numeric() {
+                let i = atom_number(&mut scanner, Layer::Connections, atom_count)?;
+                if last_index == Some(i) {
+                    return Err(InChIError::InvalidNumber {
+                        layer: Layer::Connections,
+                        offset,
+                    });
+                }
+                if let Some(last_index) = last_index {
+                    connections.push((last_index, i));
                 }
-                Token::Hyphen => { /* used to separate consecutive numbers */ }
-                Token::Comma => {
+                last_last_index = last_index;
+                last_index = Some(i);
+                continue;
+            }
+
+            match scanner.next().transpose()? {
+                None => break,
+                Some(Token::Hyphen) => { /* used to separate consecutive numbers */ }
+                Some(Token::Comma) => {
                     last_index = last_last_index;
                     last_last_index = None;
                 }
-                Token::LParen => {
-                    stack.push(last_index);
+                Some(Token::LParen) => {
+                    stack.push((last_index, offset));
                 }
-                Token::RParen => {
-                    last_index = stack.pop().ok_or("Unmatched right parenthesis")?;
+                Some(Token::RParen) => {
+                    let (index, _) = stack.pop().ok_or(InChIError::UnmatchedParenthesis {
+                        layer: Layer::Connections,
+                        offset,
+                    })?;
+                    last_index = index;
                 }
-                _ => return Err("Unexpected token in connections layer"),
+                Some(_) => return Err(scanner.unexpected_token(offset, "an atom number")),
             }
         }
 
+        if let Some(&(_, offset)) = stack.last() {
+            return Err(InChIError::UnmatchedParenthesis {
+                layer: Layer::Connections,
+                offset,
+            });
+        }
+
         Ok(Self { connections })
     }
 }
 
-impl FromStr for Hydrogens {
-    type Err = &'static str;
-
-    fn from_str(s: &str) -> Result<Self, Self::Err> {
+impl Hydrogens {
+    fn parse(mut scanner: Scanner, atom_count: usize) -> Result<Self, InChIError> {
+        let layer = scanner.layer();
         let mut immobile_hydrogens = Vec::new();
         let mut mobile_hydrogens = Vec::new();
 
-        let mut scanner = Scanner::new(s);
         loop {
             if scanner.lookahead_numeric() {
                 // immobile hydrogens
                 let mut ranges = Vec::new();
                 loop {
-                    let start = scanner.expect_numeric();
+                    let start = atom_number(&mut scanner, layer, atom_count)?;
 
                     let end = if scanner.lookahead_hyphen() {
-                        scanner.expect(Token::Hyphen);
-                        scanner.expect_numeric()
+                        scanner.expect(Token::Hyphen)?;
+                        atom_number(&mut scanner, layer, atom_count)?
                     } else {
                         start
                     };
@@ -130,16 +354,16 @@ impl FromStr for Hydrogens {
                     ranges.push(start..=end);
 
                     if scanner.lookahead_comma() {
-                        scanner.expect(Token::Comma);
+                        scanner.expect(Token::Comma)?;
                     } else {
                         break;
                     }
                 }
 
-                scanner.expect(Token::Alpha("H"));
+                scanner.expect(Token::Alpha("H"))?;
 
                 let count = if scanner.lookahead_numeric() {
-                    scanner.expect_numeric()
+                    scanner.expect_numeric()?
                 } else {
                     1
                 };
@@ -147,43 +371,380 @@ impl FromStr for Hydrogens {
                 immobile_hydrogens.push((ranges, count));
             } else {
                 // mobile hydrogens
-                scanner.expect(Token::LParen);
-                scanner.expect(Token::Alpha("H"));
+                scanner.expect(Token::LParen)?;
+                scanner.expect(Token::Alpha("H"))?;
 
                 let count = if scanner.lookahead_numeric() {
-                    scanner.expect_numeric()
+                    scanner.expect_numeric()?
                 } else {
                     1
                 };
 
-                scanner.expect(Token::Comma);
+                scanner.expect(Token::Comma)?;
 
                 let mut indices = Vec::new();
                 loop {
-                    let index = scanner.expect_numeric();
+                    let index = atom_number(&mut scanner, layer, atom_count)?;
                     indices.push(index);
                     if scanner.lookahead_comma() {
-                        scanner.expect(Token::Comma);
+                        scanner.expect(Token::Comma)?;
                     } else {
                         break;
                     }
                 }
 
-                scanner.expect(Token::RParen);
+                scanner.expect(Token::RParen)?;
 
                 mobile_hydrogens.push((count, indices));
+
+                // Groups of mobile hydrogens follow each other without a comma
+                if scanner.lookahead_lparen() {
+                    continue;
+                }
             }
 
             if scanner.lookahead_comma() {
-                scanner.expect(Token::Comma);
+                scanner.expect(Token::Comma)?;
             } else {
                 break;
             }
         }
 
+        scanner.expect_end("','")?;
+
         Ok(Self {
             immobile_hydrogens,
             mobile_hydrogens,
         })
     }
 }
+
+/// Reads a number with a sign, as in `+1` or `-2`.
+fn signed_number(scanner: &mut Scanner) -> Result<i32, InChIError> {
+    let offset = scanner.offset();
+    let sign = match scanner.next().transpose()? {
+        Some(Token::Plus) => 1,
+        Some(Token::Hyphen) => -1,
+        Some(_) => return Err(scanner.unexpected_token(offset, "'+' or '-'")),
+        None => return Err(scanner.unexpected_end("'+' or '-'")),
+    };
+    let number_offset = scanner.offset();
+    let number = scanner.expect_numeric()?;
+    let number = i32::try_from(number).map_err(|_| InChIError::InvalidNumber {
+        layer: scanner.layer(),
+        offset: number_offset,
+    })?;
+    Ok(sign * number)
+}
+
+/// Reads a layer of a single signed number, such as the charge.
+fn signed_layer(mut scanner: Scanner) -> Result<i32, InChIError> {
+    let number = signed_number(&mut scanner)?;
+    scanner.expect_end("nothing")?;
+    Ok(number)
+}
+
+fn parity(scanner: &mut Scanner) -> Result<Parity, InChIError> {
+    let offset = scanner.offset();
+    match scanner.next().transpose()? {
+        Some(Token::Hyphen) => Ok(Parity::Odd),
+        Some(Token::Plus) => Ok(Parity::Even),
+        Some(Token::Question) => Ok(Parity::Unknown),
+        Some(Token::Alpha("u")) => Ok(Parity::Undefined),
+        Some(_) => Err(scanner.unexpected_token(offset, "a parity")),
+        None => Err(scanner.unexpected_end("a parity")),
+    }
+}
+
+/// Reads a single digit layer, returning its index in `values`.
+fn choice<T: Copy>(mut scanner: Scanner, values: &[T]) -> Result<T, InChIError> {
+    let offset = scanner.offset();
+    let number = scanner.expect_numeric()?;
+    scanner.expect_end("nothing")?;
+    values
+        .get(number)
+        .copied()
+        .ok_or(InChIError::InvalidNumber {
+            layer: scanner.layer(),
+            offset,
+        })
+}
+
+/// Reads items separated by commas until the end of the layer.
+fn list<T>(
+    mut scanner: Scanner,
+    mut item: impl FnMut(&mut Scanner) -> Result<T, InChIError>,
+) -> Result<Vec<T>, InChIError> {
+    let mut items = Vec::new();
+    loop {
+        items.push(item(&mut scanner)?);
+        if scanner.lookahead_comma() {
+            scanner.expect(Token::Comma)?;
+        } else {
+            break;
+        }
+    }
+    scanner.expect_end("','")?;
+    Ok(items)
+}
+
+/// Parses the `/b`, `/t` and `/m` layers into the [`Stereo`] that `select`
+/// picks from each component, and returns the `/s` layer.
+fn parse_stereo(
+    parts: &mut Parts,
+    components: &mut [Component],
+    select: fn(&mut Component) -> &mut Stereo,
+) -> Result<Option<StereoType>, InChIError> {
+    component_layer(
+        parts,
+        'b',
+        Layer::DoubleBonds,
+        components,
+        |component, scanner| {
+            let atom_count = component.atom_count();
+            select(component).double_bonds = list(scanner, |scanner| {
+                let a = atom_number(scanner, Layer::DoubleBonds, atom_count)?;
+                scanner.expect(Token::Hyphen)?;
+                let b = atom_number(scanner, Layer::DoubleBonds, atom_count)?;
+                Ok((a, b, parity(scanner)?))
+            })?;
+            Ok(())
+        },
+    )?;
+
+    component_layer(
+        parts,
+        't',
+        Layer::Tetrahedral,
+        components,
+        |component, scanner| {
+            let atom_count = component.atom_count();
+            select(component).tetrahedral = list(scanner, |scanner| {
+                let atom = atom_number(scanner, Layer::Tetrahedral, atom_count)?;
+                Ok((atom, parity(scanner)?))
+            })?;
+            Ok(())
+        },
+    )?;
+
+    // One digit for each component, separated by `.`
+    if let Some((part, offset)) = parts.next_if(|(part, _)| part.starts_with('m')) {
+        let mut components = components.iter_mut();
+        let mut offset = offset + 1;
+        for text in part[1..].split('.') {
+            let component = components.next().ok_or(InChIError::TooManyComponents {
+                layer: Layer::Inverted,
+                offset,
+            })?;
+            if !text.is_empty() {
+                let scanner = Scanner::new(text, offset, Layer::Inverted);
+                select(component).inverted = Some(choice(scanner, &[false, true])?);
+            }
+            offset += text.len() + 1;
+        }
+    }
+
+    let mut stereo_type = None;
+    if let Some(scanner) = layer(parts, 's', Layer::StereoType) {
+        let types = [
+            None,
+            Some(StereoType::Absolute),
+            Some(StereoType::Relative),
+            Some(StereoType::Racemic),
+        ];
+        let offset = scanner.offset();
+        stereo_type = choice(scanner, &types)?;
+        if stereo_type.is_none() {
+            return Err(InChIError::InvalidNumber {
+                layer: Layer::StereoType,
+                offset,
+            });
+        }
+    }
+
+    Ok(stereo_type)
+}
+
+/// Reads isotopic hydrogens, as in `D2T`, until anything else.
+fn hydrogen_isotopes(scanner: &mut Scanner) -> Result<Vec<(HydrogenIsotope, usize)>, InChIError> {
+    let mut hydrogens = Vec::new();
+    while scanner.lookahead_alpha() {
+        let offset = scanner.offset();
+        let Some(Ok(Token::Alpha(letters))) = scanner.next() else {
+            unreachable!()
+        };
+        for (k, letter) in letters.chars().enumerate() {
+            let isotope = match letter {
+                'H' => HydrogenIsotope::Protium,
+                'D' => HydrogenIsotope::Deuterium,
+                'T' => HydrogenIsotope::Tritium,
+                _ => return Err(scanner.unexpected_token(offset + k, "'H', 'D' or 'T'")),
+            };
+            hydrogens.push((isotope, 1));
+        }
+        if scanner.lookahead_numeric() {
+            hydrogens.last_mut().unwrap().1 = scanner.expect_numeric()?;
+        }
+    }
+    Ok(hydrogens)
+}
+
+/// The number of isotopic hydrogens, saturating rather than overflowing so
+/// that too many are caught.
+fn count(hydrogens: &[(HydrogenIsotope, usize)]) -> usize {
+    hydrogens
+        .iter()
+        .fold(0, |total, &(_, count)| total.saturating_add(count))
+}
+
+/// Parses the `/i` layer and the isotopic layers after it, if present, and
+/// returns the isotopic `/s` layer. Each atom must be an isotope that can
+/// exist, with no more isotopic hydrogens than it has hydrogens, and the
+/// exchangeable ones can also be the `protons` added by the `/p` layer.
+fn parse_isotopic(
+    parts: &mut Parts,
+    components: &mut [Component],
+    protons: i32,
+) -> Result<Option<StereoType>, InChIError> {
+    if !parts.peek().is_some_and(|(part, _)| part.starts_with('i')) {
+        return Ok(None);
+    }
+    for component in components.iter_mut() {
+        component.isotopic = Some(Isotopic::default());
+    }
+    fn isotopic(component: &mut Component) -> &mut Isotopic {
+        component.isotopic.as_mut().unwrap()
+    }
+
+    component_layer(
+        parts,
+        'i',
+        Layer::Isotopic,
+        components,
+        |component, scanner| {
+            let elements = component.elements().collect::<Vec<_>>();
+            let hydrogen_counts = component.hydrogen_counts();
+            isotopic(component).atoms = list(scanner, |scanner| {
+                let atom = atom_number(scanner, Layer::Isotopic, elements.len())?;
+                let offset = scanner.offset();
+                let mass_shift = if scanner.lookahead_sign() {
+                    let mass_shift = signed_number(scanner)?;
+                    if mass_number(elements[atom - 1], mass_shift).is_none() {
+                        return Err(InChIError::InvalidNumber {
+                            layer: Layer::Isotopic,
+                            offset,
+                        });
+                    }
+                    Some(mass_shift)
+                } else {
+                    None
+                };
+                let offset = scanner.offset();
+                let hydrogens = hydrogen_isotopes(scanner)?;
+                if count(&hydrogens) > hydrogen_counts[atom - 1] {
+                    return Err(InChIError::InvalidNumber {
+                        layer: Layer::Isotopic,
+                        offset,
+                    });
+                }
+                Ok(IsotopicAtom {
+                    atom,
+                    mass_shift,
+                    hydrogens,
+                })
+            })?;
+            Ok(())
+        },
+    )?;
+
+    component_layer(
+        parts,
+        'h',
+        Layer::Isotopic,
+        components,
+        |component, mut scanner| {
+            let offset = scanner.offset();
+            let hydrogens = hydrogen_isotopes(&mut scanner)?;
+            let hydrogen_count = component.formula.count(Element::Hydrogen);
+            if count(&hydrogens) > hydrogen_count + protons.max(0) as usize {
+                return Err(InChIError::InvalidNumber {
+                    layer: Layer::Isotopic,
+                    offset,
+                });
+            }
+            isotopic(component).exchangeable_hydrogens = hydrogens;
+            scanner.expect_end("'H', 'D' or 'T'")
+        },
+    )?;
+
+    parse_stereo(parts, components, |component| {
+        &mut isotopic(component).stereo
+    })
+}
+
+impl FixedHydrogens {
+    fn parse(
+        formula: &str,
+        offset: usize,
+        main_components: &[Component],
+        protons: i32,
+        parts: &mut Parts,
+    ) -> Result<Self, InChIError> {
+        let has_formula = !formula.is_empty();
+        let mut components = if has_formula {
+            parse_formulas(formula, offset, Layer::FixedHydrogens)?
+        } else {
+            main_components
+                .iter()
+                .map(|component| component.formula.clone())
+                .collect()
+        }
+        .into_iter()
+        .map(|formula| Component {
+            formula,
+            ..Default::default()
+        })
+        .collect::<Vec<_>>();
+
+        component_layer(
+            parts,
+            'h',
+            Layer::FixedHydrogens,
+            &mut components,
+            |component, scanner| {
+                let offset = scanner.offset();
+                let hydrogens = Hydrogens::parse(scanner, component.atom_count())?;
+                if !hydrogens.mobile_hydrogens.is_empty() {
+                    return Err(InChIError::UnexpectedToken {
+                        layer: Layer::FixedHydrogens,
+                        offset,
+                        expected: "fixed hydrogens",
+                    });
+                }
+                component.hydrogens = hydrogens;
+                Ok(())
+            },
+        )?;
+
+        component_layer(
+            parts,
+            'q',
+            Layer::Charge,
+            &mut components,
+            |component, scanner| {
+                component.charge = signed_layer(scanner)?;
+                Ok(())
+            },
+        )?;
+
+        let stereo_type = parse_stereo(parts, &mut components, |component| &mut component.stereo)?;
+        let isotopic_stereo_type = parse_isotopic(parts, &mut components, protons)?;
+
+        Ok(Self {
+            has_formula,
+            components,
+            stereo_type,
+            isotopic_stereo_type,
+        })
+    }
+}
@@ -1,96 +1,194 @@
+use super::error::{InChIError, Layer};
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum Token<'a> {
     Alpha(&'a str),
     Numeric(usize),
     Hyphen,
+    Plus,
+    Question,
     Comma,
     LParen,
     RParen,
 }
 
+impl Token<'_> {
+    fn description(&self) -> &'static str {
+        match self {
+            Token::Alpha("H") => "'H'",
+            Token::Alpha(_) => "a letter",
+            Token::Numeric(_) => "a number",
+            Token::Hyphen => "'-'",
+            Token::Plus => "'+'",
+            Token::Question => "'?'",
+            Token::Comma => "','",
+            Token::LParen => "'('",
+            Token::RParen => "')'",
+        }
+    }
+}
+
 pub struct Scanner<'a> {
     input: &'a str,
+    /// The offset of `input` in the whole InChI string.
+    offset: usize,
+    layer: Layer,
 }
 
 impl<'a> Scanner<'a> {
-    pub fn new(input: &'a str) -> Self {
-        Self { input }
+    pub fn new(input: &'a str, offset: usize, layer: Layer) -> Self {
+        Self {
+            input,
+            offset,
+            layer,
+        }
+    }
+
+    /// The offset of the next token in the whole InChI string.
+    pub fn offset(&self) -> usize {
+        self.offset
+    }
+
+    pub fn layer(&self) -> Layer {
+        self.layer
     }
 
     pub fn lookahead_numeric(&self) -> bool {
-        self.input.starts_with(char::is_numeric)
+        self.input.starts_with(|c: char| c.is_ascii_digit())
     }
 
     pub fn lookahead_hyphen(&self) -> bool {
         self.input.starts_with('-')
     }
 
+    pub fn lookahead_sign(&self) -> bool {
+        self.input.starts_with(['+', '-'])
+    }
+
+    pub fn lookahead_alpha(&self) -> bool {
+        self.input.starts_with(|c: char| c.is_ascii_alphabetic())
+    }
+
     pub fn lookahead_comma(&self) -> bool {
         self.input.starts_with(',')
     }
 
-    pub fn expect(&mut self, expected: Token) {
-        match self.next() {
-            Some(token) if token == expected => {}
-            Some(token) => panic!("Expected token: {expected:?}, got: {token:?}"),
-            None => panic!("Expected token: {expected:?}, got nothing"),
+    pub fn lookahead_lparen(&self) -> bool {
+        self.input.starts_with('(')
+    }
+
+    pub fn expect(&mut self, expected: Token) -> Result<(), InChIError> {
+        let offset = self.offset;
+        match self.next().transpose()? {
+            Some(token) if token == expected => Ok(()),
+            Some(_) => Err(self.unexpected_token(offset, expected.description())),
+            None => Err(self.unexpected_end(expected.description())),
         }
     }
 
-    pub fn expect_numeric(&mut self) -> usize {
-        match self.next() {
-            Some(Token::Numeric(n)) => n,
-            _ => panic!("Expected numeric token"),
+    pub fn expect_numeric(&mut self) -> Result<usize, InChIError> {
+        let offset = self.offset;
+        match self.next().transpose()? {
+            Some(Token::Numeric(n)) => Ok(n),
+            Some(_) => Err(self.unexpected_token(offset, "a number")),
+            None => Err(self.unexpected_end("a number")),
         }
     }
+
+    /// Checks that the layer has been read to the end, where `expected` is
+    /// what could have continued it.
+    pub fn expect_end(&mut self, expected: &'static str) -> Result<(), InChIError> {
+        let offset = self.offset;
+        match self.next().transpose()? {
+            Some(_) => Err(self.unexpected_token(offset, expected)),
+            None => Ok(()),
+        }
+    }
+
+    pub fn unexpected_token(&self, offset: usize, expected: &'static str) -> InChIError {
+        InChIError::UnexpectedToken {
+            layer: self.layer,
+            offset,
+            expected,
+        }
+    }
+
+    pub fn unexpected_end(&self, expected: &'static str) -> InChIError {
+        InChIError::UnexpectedEnd {
+            layer: self.layer,
+            offset: self.offset,
+            expected,
+        }
+    }
+
+    fn advance(&mut self, len: usize) -> &'a str {
+        let (token, rest) = self.input.split_at(len);
+        self.input = rest;
+        self.offset += len;
+        token
+    }
 }
 
 impl<'a> Iterator for Scanner<'a> {
-    type Item = Token<'a>;
+    type Item = Result<Token<'a>, InChIError>;
 
     fn next(&mut self) -> Option<Self::Item> {
-        if self.input.is_empty() {
-            return None;
-        }
-
-        let c = self.input.chars().next().unwrap();
-        let token;
-        match c {
-            _ if c.is_alphabetic() => {
+        let c = self.input.chars().next()?;
+        let offset = self.offset;
+        let token = match c {
+            _ if c.is_ascii_alphabetic() => {
                 let len = self
                     .input
-                    .find(|c: char| !c.is_alphabetic())
+                    .find(|c: char| !c.is_ascii_alphabetic())
                     .unwrap_or(self.input.len());
-                token = Token::Alpha(&self.input[..len]);
-                self.input = &self.input[len..];
+                Token::Alpha(self.advance(len))
             }
-            _ if c.is_numeric() => {
+            _ if c.is_ascii_digit() => {
                 let len = self
                     .input
-                    .find(|c: char| !c.is_numeric())
+                    .find(|c: char| !c.is_ascii_digit())
                     .unwrap_or(self.input.len());
-                token = Token::Numeric(self.input[..len].parse().unwrap());
-                self.input = &self.input[len..];
+                let Ok(n) = self.advance(len).parse() else {
+                    return Some(Err(InChIError::InvalidNumber {
+                        layer: self.layer,
+                        offset,
+                    }));
+                };
+                Token::Numeric(n)
             }
             '-' => {
-                token = Token::Hyphen;
-                self.input = &self.input[1..];
+                self.advance(1);
+                Token::Hyphen
+            }
+            '+' => {
+                self.advance(1);
+                Token::Plus
+            }
+            '?' => {
+                self.advance(1);
+                Token::Question
             }
             ',' => {
-                token = Token::Comma;
-                self.input = &self.input[1..];
+                self.advance(1);
+                Token::Comma
             }
             '(' => {
-                token = Token::LParen;
-                self.input = &self.input[1..];
+                self.advance(1);
+                Token::LParen
             }
             ')' => {
-                token = Token::RParen;
-                self.input = &self.input[1..];
+                self.advance(1);
+                Token::RParen
             }
-            _ => panic!("Unexpected character in InChI: {c:?}"),
-        }
+            _ => {
+                return Some(Err(InChIError::UnexpectedCharacter {
+                    layer: self.layer,
+                    offset,
+                    character: c,
+                }))
+            }
+        };
 
-        Some(token)
+        Some(Ok(token))
     }
 }
@@ -0,0 +1,228 @@
+use blue_book::{graph::Graph, Element};
+use petgraph::{
+    algo::maximum_matching,
+    graph::{NodeIndex, UnGraph},
+};
+
+use super::InChI;
+
+impl InChI {
+    /// One structure with each mobile hydrogen placed on one of its
+    /// endpoints and pi bonds making up the rest of the valences, or `None` if
+    /// there is no such structure.
+    ///
+    /// The hydrogens and the pi bonds are found together, as a single perfect
+    /// matching over the conjugated system, so this takes polynomial time
+    /// however many mobile hydrogens there are.
+    pub fn tautomer(&self) -> Option<Graph> {
+        let problem = Problem::new(self);
+        let solution = problem.solve(&[])?;
+        Some(problem.graph(&solution))
+    }
+
+    /// Every placement of the mobile hydrogens that has a Kekulé structure,
+    /// as kekulized graphs, found lazily. Protons removed by the `/p` layer
+    /// are taken from the mobile hydrogens first, and counted in the charge
+    /// instead.
+    pub fn tautomers(&self) -> Tautomers {
+        let problem = Problem::new(self);
+        let stack = match problem.solve(&[]) {
+            Some(_) => vec![Vec::new()],
+            None => Vec::new(),
+        };
+        Tautomers { problem, stack }
+    }
+
+    /// Moves the protons removed by the `/p` layer from the mobile hydrogens
+    /// to the charge of their component.
+    fn deprotonated(&self) -> InChI {
+        let mut inchi = self.clone();
+        while inchi.protons < 0 {
+            let Some(component) = inchi
+                .components
+                .iter_mut()
+                .find(|component| !component.hydrogens.mobile_hydrogens.is_empty())
+            else {
+                break;
+            };
+            let mobile_hydrogens = &mut component.hydrogens.mobile_hydrogens;
+            mobile_hydrogens[0].0 -= 1;
+            if mobile_hydrogens[0].0 == 0 {
+                mobile_hydrogens.remove(0);
+            }
+            component.charge -= 1;
+            inchi.protons += 1;
+        }
+        inchi
+    }
+}
+
+/// An iterator over the tautomers of an InChI, from [`InChI::tautomers`].
+///
+/// The number of mobile hydrogens on each endpoint is decided in turn, and
+/// only choices that still leave a Kekulé structure are followed, so each
+/// tautomer takes polynomial time to find.
+pub struct Tautomers {
+    problem: Problem,
+    /// The numbers of hydrogens on the first endpoints, for each choice still
+    /// to be followed.
+    stack: Vec<Vec<usize>>,
+}
+
+impl Iterator for Tautomers {
+    type Item = Graph;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while let Some(prefix) = self.stack.pop() {
+            if prefix.len() == self.problem.endpoints.len() {
+                let solution = self.problem.solve(&prefix)?;
+                return Some(self.problem.graph(&solution));
+            }
+
+            let (_, i) = self.problem.endpoints[prefix.len()];
+            // Pushed in increasing order, so the most hydrogens are tried first
+            for count in 0..=self.problem.graph.valence_deficit(i) {
+                let mut prefix = prefix.clone();
+                prefix.push(count);
+                if self.problem.solve(&prefix).is_some() {
+                    self.stack.push(prefix);
+                }
+            }
+        }
+        None
+    }
+}
+
+/// The structure without its mobile hydrogens, and where they can go.
+struct Problem {
+    graph: Graph,
+    /// The number of mobile hydrogens in each group.
+    counts: Vec<usize>,
+    /// The group and atom of each endpoint.
+    endpoints: Vec<(usize, usize)>,
+}
+
+/// The number of mobile hydrogens on each endpoint, and the pi bonds.
+struct Solution {
+    hydrogens: Vec<usize>,
+    pi_bonds: Vec<(usize, usize)>,
+}
+
+impl Problem {
+    fn new(inchi: &InChI) -> Self {
+        let mut inchi = inchi.deprotonated();
+        let mut groups = Vec::new();
+        for (k, component) in inchi.components.iter_mut().enumerate() {
+            for group in std::mem::take(&mut component.hydrogens.mobile_hydrogens) {
+                groups.push((k, group));
+            }
+        }
+        let (graph, starts) = inchi.graph();
+
+        let mut counts = Vec::new();
+        let mut endpoints = Vec::new();
+        for (g, (k, (count, atoms))) in groups.into_iter().enumerate() {
+            counts.push(count);
+            endpoints.extend(atoms.into_iter().map(|i| (g, starts[k] + i - 1)));
+        }
+
+        Self {
+            graph,
+            counts,
+            endpoints,
+        }
+    }
+
+    /// Finds a structure with the given numbers of hydrogens on the first
+    /// endpoints, if there is one.
+    ///
+    /// Each atom short of its bonding number becomes as many vertices as it
+    /// needs bonds, and each mobile hydrogen still to be placed becomes a
+    /// vertex joined to the remaining endpoints of its group. A perfect
+    /// matching then gives the pi bonds and the places of those hydrogens.
+    fn solve(&self, prefix: &[usize]) -> Option<Solution> {
+        let mut deficits = (0..self.graph.atoms.len())
+            .map(|i| match self.graph.atoms[i] {
+                Element::Hydrogen => 0,
+                _ => self.graph.valence_deficit(i),
+            })
+            .collect::<Vec<_>>();
+        let mut remaining = self.counts.clone();
+        for (&(g, i), &count) in self.endpoints.iter().zip(prefix) {
+            deficits[i] = deficits[i].checked_sub(count)?;
+            remaining[g] = remaining[g].checked_sub(count)?;
+        }
+
+        let mut matching_graph = UnGraph::<Option<usize>, ()>::default();
+        let vertices = deficits
+            .iter()
+            .enumerate()
+            .map(|(i, &deficit)| {
+                (0..deficit)
+                    .map(|_| matching_graph.add_node(Some(i)))
+                    .collect::<Vec<_>>()
+            })
+            .collect::<Vec<_>>();
+        for &(i, j) in &self.graph.bonds {
+            for &a in &vertices[i] {
+                for &b in &vertices[j] {
+                    matching_graph.add_edge(a, b, ());
+                }
+            }
+        }
+        for (g, &count) in remaining.iter().enumerate() {
+            for _ in 0..count {
+                let hydrogen = matching_graph.add_node(None);
+                for k in prefix.len()..self.endpoints.len() {
+                    if self.endpoints[k].0 == g {
+                        for &a in &vertices[self.endpoints[k].1] {
+                            matching_graph.add_edge(hydrogen, a, ());
+                        }
+                    }
+                }
+            }
+        }
+
+        let matching = maximum_matching(&matching_graph);
+        if !matching.is_perfect() {
+            return None;
+        }
+
+        let mut hydrogens = prefix.to_vec();
+        hydrogens.resize(self.endpoints.len(), 0);
+        let mut pi_bonds = Vec::new();
+        for (a, b) in matching.edges() {
+            let atom = |vertex: NodeIndex| matching_graph[vertex];
+            match (atom(a), atom(b)) {
+                (Some(i), Some(j)) => pi_bonds.push((i.min(j), i.max(j))),
+                (Some(i), None) | (None, Some(i)) => {
+                    // Any remaining endpoint of the group of this hydrogen
+                    let k = (prefix.len()..self.endpoints.len())
+                        .find(|&k| self.endpoints[k].1 == i)
+                        .unwrap();
+                    hydrogens[k] += 1;
+                }
+                (None, None) => unreachable!(),
+            }
+        }
+
+        Some(Solution {
+            hydrogens,
+            pi_bonds,
+        })
+    }
+
+    /// The structure with the hydrogens and pi bonds of a solution.
+    fn graph(&self, solution: &Solution) -> Graph {
+        let mut graph = self.graph.clone();
+        for (&(_, i), &count) in self.endpoints.iter().zip(&solution.hydrogens) {
+            for _ in 0..count {
+                let h = graph.atoms.len();
+                graph.atoms.push(Element::Hydrogen);
+                graph.bonds.push((i, h));
+            }
+        }
+        graph.pi_bonds.extend(solution.pi_bonds.iter().copied());
+        graph
+    }
+}
@@ -0,0 +1,750 @@
+use std::{collections::BTreeMap, fmt, ops::RangeInclusive};
+
+use blue_book::{formula::Formula, graph::Graph, Element};
+
+use super::{
+    canonical::canonical_numbers, Component, Connections, FixedHydrogens, HydrogenIsotope,
+    Hydrogens, InChI, Isotopic, IsotopicAtom, Parity, Stereo, StereoType,
+};
+
+impl TryFrom<&Graph> for InChI {
+    type Error = &'static str;
+
+    /// Builds the formula, connection, hydrogen and isotopic layers of the
+    /// standard InChI. Stereochemistry is left out.
+    fn try_from(graph: &Graph) -> Result<Self, Self::Error> {
+        if !graph.charges.is_empty() {
+            return Err("Charged atoms are not supported");
+        }
+        if !graph.free_valences.is_empty() {
+            return Err("Free valences are not supported");
+        }
+
+        // The hydrogens counted on the atom they are bonded to
+        let is_terminal_hydrogen =
+            |i: usize| graph.is_terminal_hydrogen(i) || is_molecular_hydrogen(graph, i);
+
+        let atoms = (0..graph.atoms.len())
+            .filter(|&i| !is_terminal_hydrogen(i))
+            .collect::<Vec<_>>();
+        let index = |i: usize| atoms.binary_search(&i).ok();
+
+        let elements = atoms.iter().map(|&i| graph.atoms[i]).collect::<Vec<_>>();
+        let mut neighbors = vec![Vec::new(); atoms.len()];
+        for &(a, b) in &graph.bonds {
+            if let (Some(x), Some(y)) = (index(a), index(b)) {
+                neighbors[x].push(y);
+                neighbors[y].push(x);
+            }
+        }
+        if !is_connected(&neighbors) {
+            return Err("Disconnected structures are not supported");
+        }
+
+        let mut hydrogen_counts = atoms
+            .iter()
+            .map(|&i| {
+                graph
+                    .neighbors(i)
+                    .filter(|&j| is_terminal_hydrogen(j))
+                    .count()
+            })
+            .collect::<Vec<_>>();
+
+        let groups = mobile_groups(&elements, &neighbors, &hydrogen_counts);
+        let mut mobile_counts = Vec::new();
+        for group in &groups {
+            mobile_counts.push(group.iter().map(|&x| hydrogen_counts[x]).sum::<usize>());
+            for &x in group {
+                hydrogen_counts[x] = 0;
+            }
+        }
+
+        // The protium, deuterium and tritium atoms on each atom. Those among
+        // the mobile hydrogens are counted apart
+        let mut hydrogen_isotopes = vec![[0; 3]; atoms.len()];
+        let mut exchangeable = [0; 3];
+        for (x, &i) in atoms.iter().enumerate() {
+            let is_mobile = groups.iter().any(|group| group.contains(&x));
+            for j in graph.neighbors(i) {
+                if let (true, Some(mass_number @ 1..=3)) =
+                    (is_terminal_hydrogen(j), graph.mass_number(j))
+                {
+                    let counts = match is_mobile {
+                        true => &mut exchangeable,
+                        false => &mut hydrogen_isotopes[x],
+                    };
+                    counts[mass_number as usize - 1] += 1;
+                }
+            }
+        }
+        let mass_shifts = atoms
+            .iter()
+            .zip(&elements)
+            .map(|(&i, element)| {
+                let mass_number = graph.mass_number(i)?;
+                Some(mass_number as i32 - element.atomic_mass().round() as i32)
+            })
+            .collect::<Vec<_>>();
+        let isotopic = (0..atoms.len())
+            .map(|x| mass_shifts[x].is_some() || hydrogen_isotopes[x] != [0; 3])
+            .collect::<Vec<_>>();
+
+        let numbers = canonical_numbers(&elements, &neighbors, &hydrogen_counts, &isotopic);
+
+        let mut connections = Vec::new();
+        for (x, neighbors) in neighbors.iter().enumerate() {
+            for &y in neighbors {
+                if numbers[x] < numbers[y] {
+                    connections.push((numbers[x], numbers[y]));
+                }
+            }
+        }
+        connections.sort_unstable();
+
+        let mut by_count = BTreeMap::<_, Vec<_>>::new();
+        for (x, &count) in hydrogen_counts.iter().enumerate() {
+            if count > 0 {
+                by_count.entry(count).or_default().push(numbers[x]);
+            }
+        }
+        let immobile_hydrogens = by_count
+            .into_iter()
+            .map(|(count, mut numbers)| {
+                numbers.sort_unstable();
+                (ranges(numbers), count)
+            })
+            .collect();
+
+        let mut mobile_hydrogens = groups
+            .iter()
+            .zip(mobile_counts)
+            .map(|(group, count)| {
+                let mut endpoints = group.iter().map(|&x| numbers[x]).collect::<Vec<_>>();
+                endpoints.sort_unstable();
+                (count, endpoints)
+            })
+            .collect::<Vec<_>>();
+        mobile_hydrogens.sort_unstable_by(|(_, a), (_, b)| a.cmp(b));
+
+        let mut isotopic_atoms = (0..atoms.len())
+            .filter(|&x| isotopic[x])
+            .map(|x| IsotopicAtom {
+                atom: numbers[x],
+                mass_shift: mass_shifts[x],
+                hydrogens: hydrogen_isotope_counts(hydrogen_isotopes[x]),
+            })
+            .collect::<Vec<_>>();
+        isotopic_atoms.sort_unstable_by_key(|atom| atom.atom);
+        let exchangeable_hydrogens = hydrogen_isotope_counts(exchangeable);
+        let isotopic =
+            (!isotopic_atoms.is_empty() || !exchangeable_hydrogens.is_empty()).then(|| Isotopic {
+                atoms: isotopic_atoms,
+                exchangeable_hydrogens,
+                ..Default::default()
+            });
+
+        // The formula layer leaves out isotopes
+        let mut formula = Formula::from(graph);
+        formula.isotopes.clear();
+        let component = Component {
+            formula: formula.clone(),
+            connections: Connections { connections },
+            hydrogens: Hydrogens {
+                immobile_hydrogens,
+                mobile_hydrogens,
+            },
+            isotopic,
+            ..Default::default()
+        };
+        Ok(Self {
+            standard: true,
+            formula,
+            components: vec![component],
+            ..Default::default()
+        })
+    }
+}
+
+/// Whether `i` is the atom of a hydrogen molecule written as a hydrogen on the
+/// other, as in `InChI=1S/H2/h1H`: the lighter of the two, or the second.
+fn is_molecular_hydrogen(graph: &Graph, i: usize) -> bool {
+    if graph.atoms[i] != Element::Hydrogen {
+        return false;
+    }
+    let mut neighbors = graph.neighbors(i);
+    let (Some(j), None) = (neighbors.next(), neighbors.next()) else {
+        return false;
+    };
+    let mass_number = |i: usize| graph.mass_number(i).unwrap_or(1);
+    graph.atoms[j] == Element::Hydrogen
+        && graph.neighbors(j).count() == 1
+        && (mass_number(i), j) < (mass_number(j), i)
+}
+
+/// The counts of protium, deuterium and tritium, as in an isotopic layer.
+fn hydrogen_isotope_counts(counts: [usize; 3]) -> Vec<(HydrogenIsotope, usize)> {
+    [
+        HydrogenIsotope::Protium,
+        HydrogenIsotope::Deuterium,
+        HydrogenIsotope::Tritium,
+    ]
+    .into_iter()
+    .zip(counts)
+    .filter(|&(_, count)| count > 0)
+    .collect()
+}
+
+/// Groups the nitrogen, oxygen, sulfur, selenium and tellurium atoms that a
+/// hydrogen can move between, which is whenever the structure with the
+/// hydrogen moved still has a Kekulé structure: that is, the two atoms are
+/// joined by a path of alternating single and double bonds.
+fn mobile_groups(
+    elements: &[Element],
+    neighbors: &[Vec<usize>],
+    hydrogen_counts: &[usize],
+) -> Vec<Vec<usize>> {
+    let is_endpoint = |x: usize| {
+        matches!(
+            elements[x],
+            Element::Nitrogen
+                | Element::Oxygen
+                | Element::Sulfur
+                | Element::Selenium
+                | Element::Tellurium
+        )
+    };
+    let endpoints = (0..elements.len())
+        .filter(|&x| is_endpoint(x))
+        .collect::<Vec<_>>();
+
+    let mut skeleton = Graph {
+        atoms: elements.to_vec(),
+        ..Default::default()
+    };
+    for (x, neighbors) in neighbors.iter().enumerate() {
+        skeleton
+            .bonds
+            .extend(neighbors.iter().filter(|&&y| x < y).map(|&y| (x, y)));
+    }
+    let has_kekule_structure = |hydrogen_counts: &[usize]| {
+        let mut graph = skeleton.clone();
+        for (x, &count) in hydrogen_counts.iter().enumerate() {
+            if count + neighbors[x].len() > graph.bonding_number(x) {
+                return false;
+            }
+            for _ in 0..count {
+                let h = graph.atoms.len();
+                graph.atoms.push(Element::Hydrogen);
+                graph.bonds.push((x, h));
+            }
+        }
+        graph.kekulize().is_ok()
+    };
+    if !has_kekule_structure(hydrogen_counts) {
+        return Vec::new();
+    }
+
+    let mut groups = (0..elements.len()).collect::<Vec<_>>();
+    fn root(groups: &mut [usize], mut x: usize) -> usize {
+        while groups[x] != x {
+            groups[x] = groups[groups[x]];
+            x = groups[x];
+        }
+        x
+    }
+
+    for &donor in &endpoints {
+        if hydrogen_counts[donor] == 0 {
+            continue;
+        }
+        for &acceptor in &endpoints {
+            if acceptor == donor {
+                continue;
+            }
+            let mut moved = hydrogen_counts.to_vec();
+            moved[donor] -= 1;
+            moved[acceptor] += 1;
+            if has_kekule_structure(&moved) {
+                let (a, b) = (root(&mut groups, donor), root(&mut groups, acceptor));
+                groups[a] = b;
+            }
+        }
+    }
+
+    let mut members = BTreeMap::<_, Vec<_>>::new();
+    for &x in &endpoints {
+        let root = root(&mut groups, x);
+        members.entry(root).or_default().push(x);
+    }
+    members
+        .into_values()
+        .filter(|group| group.len() > 1)
+        .collect()
+}
+
+fn is_connected(neighbors: &[Vec<usize>]) -> bool {
+    let mut visited = vec![false; neighbors.len()];
+    let mut stack = vec![0];
+    while let Some(x) = stack.pop() {
+        if visited.get(x) == Some(&false) {
+            visited[x] = true;
+            stack.extend(&neighbors[x]);
+        }
+    }
+    visited.iter().all(|&visited| visited)
+}
+
+/// Collapses sorted numbers into runs of consecutive numbers.
+fn ranges(numbers: Vec<usize>) -> Vec<RangeInclusive<usize>> {
+    let mut ranges: Vec<RangeInclusive<usize>> = Vec::new();
+    for number in numbers {
+        match ranges.last_mut() {
+            Some(range) if *range.end() + 1 == number => *range = *range.start()..=number,
+            _ => ranges.push(number..=number),
+        }
+    }
+    ranges
+}
+
+impl fmt::Display for InChI {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let version = if self.standard { "1S" } else { "1" };
+        write!(f, "InChI={version}/")?;
+        write_formulas(f, &self.components)?;
+        self.write_layers(f)
+    }
+}
+
+impl InChI {
+    /// Writes the layers that follow the formula.
+    fn write_layers(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let components = &self.components;
+        write_layer(f, 'c', components, |component| {
+            component.connections.to_string()
+        })?;
+        write_layer(f, 'h', components, |component| {
+            component.hydrogens.to_string()
+        })?;
+        write_layer(f, 'q', components, charge)?;
+        if self.protons != 0 {
+            write!(f, "/p{:+}", self.protons)?;
+        }
+        write_stereo(f, components, self.stereo_type, |component| {
+            &component.stereo
+        })?;
+        write_isotopic(f, components, self.isotopic_stereo_type)?;
+        if let Some(fixed_hydrogens) = &self.fixed_hydrogens {
+            write!(f, "{fixed_hydrogens}")?;
+        }
+        if let Some(reconnected) = &self.reconnected {
+            write!(f, "/r")?;
+            write_formulas(f, &reconnected.components)?;
+            reconnected.write_layers(f)?;
+        }
+        Ok(())
+    }
+}
+
+/// Writes the formula of each component, separated by `.`, with a
+/// multiplier in front of repeated formulas.
+fn write_formulas(f: &mut fmt::Formatter, components: &[Component]) -> fmt::Result {
+    let mut k = 0;
+    while k < components.len() {
+        let formula = &components[k].formula;
+        let count = components[k..]
+            .iter()
+            .take_while(|component| component.formula == *formula)
+            .count();
+        if k > 0 {
+            write!(f, ".")?;
+        }
+        if count > 1 {
+            write!(f, "{count}")?;
+        }
+        write!(f, "{formula}")?;
+        k += count;
+    }
+    Ok(())
+}
+
+/// Writes a layer, with the part for each component separated by `;`, unless
+/// every part is empty. Repeated parts are written once, after `n*`.
+fn write_layer(
+    f: &mut fmt::Formatter,
+    prefix: char,
+    components: &[Component],
+    part: impl Fn(&Component) -> String,
+) -> fmt::Result {
+    let parts = components.iter().map(part).collect::<Vec<_>>();
+    if parts.iter().all(String::is_empty) {
+        return Ok(());
+    }
+    write!(f, "/{prefix}")?;
+    write_parts(f, &parts)
+}
+
+fn write_parts(f: &mut fmt::Formatter, parts: &[String]) -> fmt::Result {
+    let mut k = 0;
+    while k < parts.len() {
+        let count = if parts[k].is_empty() {
+            1
+        } else {
+            parts[k..]
+                .iter()
+                .take_while(|part| **part == parts[k])
+                .count()
+        };
+        if k > 0 {
+            write!(f, ";")?;
+        }
+        if count > 1 {
+            write!(f, "{count}*")?;
+        }
+        write!(f, "{}", parts[k])?;
+        k += count;
+    }
+    Ok(())
+}
+
+fn charge(component: &Component) -> String {
+    if component.charge == 0 {
+        String::new()
+    } else {
+        format!("{:+}", component.charge)
+    }
+}
+
+impl fmt::Display for Parity {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let symbol = match self {
+            Parity::Odd => "-",
+            Parity::Even => "+",
+            Parity::Unknown => "?",
+            Parity::Undefined => "u",
+        };
+        write!(f, "{symbol}")
+    }
+}
+
+/// Writes the `/b`, `/t` and `/m` layers of the [`Stereo`] that `select`
+/// picks from each component, then the `/s` layer.
+fn write_stereo(
+    f: &mut fmt::Formatter,
+    components: &[Component],
+    stereo_type: Option<StereoType>,
+    select: fn(&Component) -> &Stereo,
+) -> fmt::Result {
+    write_layer(f, 'b', components, |component| {
+        let double_bonds = select(component)
+            .double_bonds
+            .iter()
+            .map(|(a, b, parity)| format!("{a}-{b}{parity}"))
+            .collect::<Vec<_>>();
+        double_bonds.join(",")
+    })?;
+    write_layer(f, 't', components, |component| {
+        let tetrahedral = select(component)
+            .tetrahedral
+            .iter()
+            .map(|(atom, parity)| format!("{atom}{parity}"))
+            .collect::<Vec<_>>();
+        tetrahedral.join(",")
+    })?;
+
+    // One digit for each component, separated by `.`
+    let inverted = components
+        .iter()
+        .map(|component| select(component).inverted)
+        .collect::<Vec<_>>();
+    if inverted.iter().any(Option::is_some) {
+        write!(f, "/m")?;
+        for (k, inverted) in inverted.iter().enumerate() {
+            if k > 0 {
+                write!(f, ".")?;
+            }
+            if let Some(inverted) = inverted {
+                write!(f, "{}", *inverted as u8)?;
+            }
+        }
+    }
+
+    if let Some(stereo_type) = stereo_type {
+        let number = match stereo_type {
+            StereoType::Absolute => 1,
+            StereoType::Relative => 2,
+            StereoType::Racemic => 3,
+        };
+        write!(f, "/s{number}")?;
+    }
+    Ok(())
+}
+
+fn hydrogen_isotopes(hydrogens: &[(HydrogenIsotope, usize)]) -> String {
+    let mut isotopes = String::new();
+    for &(isotope, count) in hydrogens {
+        let letter = match isotope {
+            HydrogenIsotope::Protium => 'H',
+            HydrogenIsotope::Deuterium => 'D',
+            HydrogenIsotope::Tritium => 'T',
+        };
+        isotopes.push(letter);
+        if count > 1 {
+            isotopes.push_str(&count.to_string());
+        }
+    }
+    isotopes
+}
+
+/// Writes the `/i` layer and the isotopic layers after it, if any component
+/// has them.
+fn write_isotopic(
+    f: &mut fmt::Formatter,
+    components: &[Component],
+    stereo_type: Option<StereoType>,
+) -> fmt::Result {
+    if components
+        .iter()
+        .all(|component| component.isotopic.is_none())
+    {
+        return Ok(());
+    }
+    static EMPTY: Isotopic = Isotopic {
+        atoms: Vec::new(),
+        exchangeable_hydrogens: Vec::new(),
+        stereo: Stereo {
+            double_bonds: Vec::new(),
+            tetrahedral: Vec::new(),
+            inverted: None,
+        },
+    };
+    fn isotopic(component: &Component) -> &Isotopic {
+        component.isotopic.as_ref().unwrap_or(&EMPTY)
+    }
+
+    // Written even when empty, as the layers after it belong to it
+    write!(f, "/i")?;
+    let atoms = components
+        .iter()
+        .map(|component| {
+            let atoms = isotopic(component)
+                .atoms
+                .iter()
+                .map(|atom| {
+                    let mass_shift = atom
+                        .mass_shift
+                        .map(|mass_shift| format!("{mass_shift:+}"))
+                        .unwrap_or_default();
+                    let hydrogens = hydrogen_isotopes(&atom.hydrogens);
+                    format!("{}{mass_shift}{hydrogens}", atom.atom)
+                })
+                .collect::<Vec<_>>();
+            atoms.join(",")
+        })
+        .collect::<Vec<_>>();
+    if atoms.iter().any(|atoms| !atoms.is_empty()) {
+        write_parts(f, &atoms)?;
+    }
+
+    write_layer(f, 'h', components, |component| {
+        hydrogen_isotopes(&isotopic(component).exchangeable_hydrogens)
+    })?;
+    write_stereo(f, components, stereo_type, |component| {
+        &isotopic(component).stereo
+    })
+}
+
+impl fmt::Display for FixedHydrogens {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "/f")?;
+        if self.has_formula {
+            write_formulas(f, &self.components)?;
+        }
+        write_layer(f, 'h', &self.components, |component| {
+            component.hydrogens.to_string()
+        })?;
+        write_layer(f, 'q', &self.components, charge)?;
+        write_stereo(f, &self.components, self.stereo_type, |component| {
+            &component.stereo
+        })?;
+        write_isotopic(f, &self.components, self.isotopic_stereo_type)
+    }
+}
+
+/// An atom in the depth-first spanning tree that the connection layer is
+/// written from.
+struct Branch {
+    atom: usize,
+    items: Vec<Item>,
+    /// The number of atoms and ring closures in the branch.
+    size: usize,
+}
+
+enum Item {
+    RingClosure(usize),
+    Branch(Branch),
+}
+
+impl Item {
+    fn size(&self) -> usize {
+        match self {
+            Item::RingClosure(_) => 1,
+            Item::Branch(branch) => branch.size,
+        }
+    }
+}
+
+impl Branch {
+    /// Visits neighbours in numerical order. Ring closures are then written
+    /// first, and branches from smallest to largest, so that the largest
+    /// continues the chain.
+    fn new(
+        atom: usize,
+        parent: Option<usize>,
+        neighbors: &[Vec<usize>],
+        state: &mut [State],
+    ) -> Self {
+        state[atom] = State::OnPath;
+        let mut items = Vec::new();
+        for &next in &neighbors[atom] {
+            match state[next] {
+                _ if Some(next) == parent => {}
+                State::Unvisited => items.push(Item::Branch(Branch::new(
+                    next,
+                    Some(atom),
+                    neighbors,
+                    state,
+                ))),
+                State::OnPath => items.push(Item::RingClosure(next)),
+                State::Visited => {}
+            }
+        }
+        state[atom] = State::Visited;
+
+        items.sort_by_key(|item| match item {
+            Item::RingClosure(next) => (0, *next),
+            Item::Branch(branch) => (branch.size, branch.atom),
+        });
+        let size = 1 + items.iter().map(Item::size).sum::<usize>();
+        Self { atom, items, size }
+    }
+}
+
+#[derive(Clone, Copy)]
+enum State {
+    Unvisited,
+    OnPath,
+    Visited,
+}
+
+impl fmt::Display for Branch {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.atom)?;
+        let Some((last, rest)) = self.items.split_last() else {
+            return Ok(());
+        };
+        if rest.is_empty() {
+            write!(f, "-")?;
+        } else {
+            write!(f, "(")?;
+            for (k, item) in rest.iter().enumerate() {
+                if k > 0 {
+                    write!(f, ",")?;
+                }
+                write!(f, "{item}")?;
+            }
+            write!(f, ")")?;
+        }
+        write!(f, "{last}")
+    }
+}
+
+impl fmt::Display for Item {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Item::RingClosure(atom) => write!(f, "{atom}"),
+            Item::Branch(branch) => write!(f, "{branch}"),
+        }
+    }
+}
+
+impl fmt::Display for Connections {
+    /// Writes each connected component depth first, starting from its atom
+    /// with the fewest connections.
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let atom_count = self
+            .connections
+            .iter()
+            .map(|&(i, j)| i.max(j))
+            .max()
+            .unwrap_or_default();
+        let mut neighbors = vec![Vec::new(); atom_count + 1];
+        for &(i, j) in &self.connections {
+            neighbors[i].push(j);
+            neighbors[j].push(i);
+        }
+        for neighbors in &mut neighbors {
+            neighbors.sort_unstable();
+        }
+
+        let mut state = vec![State::Unvisited; atom_count + 1];
+        let mut first = true;
+        while let Some(start) = (1..=atom_count)
+            .filter(|&i| matches!(state[i], State::Unvisited) && !neighbors[i].is_empty())
+            .min_by_key(|&i| (neighbors[i].len(), i))
+        {
+            if !first {
+                write!(f, ";")?;
+            }
+            first = false;
+            write!(f, "{}", Branch::new(start, None, &neighbors, &mut state))?;
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for Hydrogens {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let mut first = true;
+        let mut separator = |f: &mut fmt::Formatter| {
+            let result = if first { Ok(()) } else { write!(f, ",") };
+            first = false;
+            result
+        };
+
+        for (ranges, count) in &self.immobile_hydrogens {
+            separator(f)?;
+            for (k, range) in ranges.iter().enumerate() {
+                if k > 0 {
+                    write!(f, ",")?;
+                }
+                if range.start() == range.end() {
+                    write!(f, "{}", range.start())?;
+                } else {
+                    write!(f, "{}-{}", range.start(), range.end())?;
+                }
+            }
+            write!(f, "H")?;
+            if *count > 1 {
+                write!(f, "{count}")?;
+            }
+        }
+
+        // Groups of mobile hydrogens are written back to back, after a comma
+        if !self.mobile_hydrogens.is_empty() {
+            separator(f)?;
+        }
+        for (count, endpoints) in &self.mobile_hydrogens {
+            write!(f, "(H")?;
+            if *count > 1 {
+                write!(f, "{count}")?;
+            }
+            for endpoint in endpoints {
+                write!(f, ",{endpoint}")?;
+            }
+            write!(f, ")")?;
+        }
+
+        Ok(())
+    }
+}
@@ -1,55 +1,664 @@
-use blue_book::{graph::Graph, parser::parse};
-use inchi::InChI;
+use std::collections::HashSet;
+
+use blue_book::{
+    formula::Formula,
+    graph::{stereo::Stereo, Graph},
+    parser::parse,
+    Element,
+};
+use inchi::{Atom, Bond, InChI, InChIError, Layer, Mismatch, Parity, StereoType};
 use paste::paste;
-use petgraph::{algo::is_isomorphic_matching, graph::UnGraph};
+use petgraph::graph::{NodeIndex, UnGraph};
 
 macro_rules! test_inchi {
-    ($name:ident($inchi:literal)) => {
+    ($name:ident($inchi:literal, $key:literal $(,)?)) => {
         paste! {
             #[test]
             fn [<test_ $name _inchi>]() {
                 test_inchi_impl(
                     &blue_book::test::[<$name:upper>],
                     $inchi,
+                    $key,
                 );
             }
         }
     };
-    ($($name:ident($inchi:literal),)*) => {
-        $(test_inchi!($name($inchi));)*
+    ($($name:ident($inchi:literal, $key:literal $(,)?),)*) => {
+        $(test_inchi!($name($inchi, $key));)*
     };
 }
 
 test_inchi! {
-    isopropanol("InChI=1S/C3H8O/c1-3(2)4/h3-4H,1-2H3"),
-    isobutane("InChI=1S/C4H10/c1-4(2)3/h4H,1-3H3"),
+    isopropanol(
+        "InChI=1S/C3H8O/c1-3(2)4/h3-4H,1-2H3",
+        "KFZMGEQAYNKOFK-UHFFFAOYSA-N",
+    ),
+    isobutane(
+        "InChI=1S/C4H10/c1-4(2)3/h4H,1-3H3",
+        "NNPPMTNAJDCUHE-UHFFFAOYSA-N",
+    ),
     //
-    dopamine("InChI=1S/C8H11NO2/c9-4-3-6-1-2-7(10)8(11)5-6/h1-2,5,10-11H,3-4,9H2"),
-    salbutamol("InChI=1S/C13H21NO3/c1-13(2,3)14-7-12(17)9-4-5-11(16)10(6-9)8-15/h4-6,12,14-17H,7-8H2,1-3H3"),
-    caffeine("InChI=1S/C8H10N4O2/c1-10-4-9-6-5(10)7(13)12(3)8(14)11(6)2/h4H,1-3H3"),
+    dopamine(
+        "InChI=1S/C8H11NO2/c9-4-3-6-1-2-7(10)8(11)5-6/h1-2,5,10-11H,3-4,9H2",
+        "VYFYYTLLBUKUHU-UHFFFAOYSA-N",
+    ),
+    salbutamol(
+        "InChI=1S/C13H21NO3/c1-13(2,3)14-7-12(17)9-4-5-11(16)10(6-9)8-15/h4-6,12,14-17H,7-8H2,1-3H3",
+        "NDAUXUAQIAJITI-UHFFFAOYSA-N",
+    ),
+    caffeine(
+        "InChI=1S/C8H10N4O2/c1-10-4-9-6-5(10)7(13)12(3)8(14)11(6)2/h4H,1-3H3",
+        "RYYVLZVUVIJVGH-UHFFFAOYSA-N",
+    ),
     //
-    adenine("InChI=1S/C5H5N5/c6-4-3-5(9-1-7-3)10-2-8-4/h1-2H,(H3,6,7,8,9,10)"),
-    thymine("InChI=1S/C5H6N2O2/c1-3-2-6-5(9)7-4(3)8/h2H,1H3,(H2,6,7,8,9)"),
-    cytosine("InChI=1S/C4H5N3O/c5-3-1-2-6-4(8)7-3/h1-2H,(H3,5,6,7,8)"),
-    guanine("InChI=1S/C5H5N5O/c6-5-9-3-2(4(11)10-5)7-1-8-3/h1H,(H4,6,7,8,9,10,11)"),
+    adenine(
+        "InChI=1S/C5H5N5/c6-4-3-5(9-1-7-3)10-2-8-4/h1-2H,(H3,6,7,8,9,10)",
+        "GFFGJBXGBJISGV-UHFFFAOYSA-N",
+    ),
+    thymine(
+        "InChI=1S/C5H6N2O2/c1-3-2-6-5(9)7-4(3)8/h2H,1H3,(H2,6,7,8,9)",
+        "RWQNBRDOKXIBIV-UHFFFAOYSA-N",
+    ),
+    cytosine(
+        "InChI=1S/C4H5N3O/c5-3-1-2-6-4(8)7-3/h1-2H,(H3,5,6,7,8)",
+        "OPTASPLRGRRNAP-UHFFFAOYSA-N",
+    ),
+    guanine(
+        "InChI=1S/C5H5N5O/c6-5-9-3-2(4(11)10-5)7-1-8-3/h1H,(H4,6,7,8,9,10,11)",
+        "UYTPUPDQBNUYGX-UHFFFAOYSA-N",
+    ),
 }
 
-fn test_inchi_impl(iupac: &str, inchi: &str) {
-    let iupac = parse(iupac);
+fn test_inchi_impl(name: &str, inchi_string: &str, key: &str) {
+    let iupac = parse(name);
     let iupac_graph = Graph::from(&*iupac);
-    let iupac_graph = UnGraph::from(&iupac_graph);
+    let iupac_formula = Formula::from(&iupac_graph);
+    let iupac_key = iupac_graph.canonical_key();
+
+    let inchi: InChI = inchi_string.parse().unwrap();
+    assert_eq!(inchi.formula(), &iupac_formula);
+    assert_eq!(inchi.to_string(), inchi_string);
+    assert_eq!(inchi.key(), key);
+
+    let tautomer = inchi.tautomer().unwrap();
+    assert!(tautomer
+        .atoms
+        .iter()
+        .enumerate()
+        .all(|(i, &element)| { element == Element::Hydrogen || tautomer.valence_deficit(i) == 0 }));
+
+    let any_match = inchi
+        .tautomers()
+        .any(|tautomer| tautomer.canonical_key() == iupac_key);
+    assert!(any_match, "{iupac_key}");
+    assert_eq!(inchi.check_name(name), Ok(()));
+
+    let generated = InChI::try_from(&iupac_graph).unwrap();
+    assert_eq!(generated.to_string(), inchi_string);
+}
+
+#[test]
+fn test_check_name() {
+    let smiles = |smiles: &str| Graph::from_smiles(smiles).unwrap();
+    let but_1_ene: InChI = "InChI=1S/C4H8/c1-3-4-2/h3H,1,4H2,2H3".parse().unwrap();
+    assert_eq!(but_1_ene.check_graph(&smiles("CCC=C")), Ok(()));
+
+    let mismatch = but_1_ene.check_name("Propan-2-ol").unwrap_err();
+    let Mismatch::Formula { counts, .. } = &mismatch else {
+        panic!("{mismatch:?}");
+    };
+    assert_eq!(counts, &[(Element::Carbon, 3, 4), (Element::Oxygen, 1, 0),],);
+    assert_eq!(
+        mismatch.to_string(),
+        "Formula C3H8O differs from C4H8 in the InChI: 3 C rather than 4, 1 O rather than 0",
+    );
+
+    let isobutene: InChI = "InChI=1S/C4H8/c1-4(2)3/h1H2,2-3H3".parse().unwrap();
+    assert!(matches!(
+        isobutene.check_graph(&smiles("CCC=C")),
+        Err(Mismatch::Connectivity { .. }),
+    ));
+    assert_eq!(
+        but_1_ene.check_graph(&smiles("CC=CC")),
+        Err(Mismatch::Hydrogens)
+    );
+
+    let acetate: InChI = "InChI=1S/C2H4O2/c1-2(3)4/h1H3,(H,3,4)/p-1".parse().unwrap();
+    assert_eq!(
+        acetate.check_graph(&smiles("CC(=O)O")),
+        Err(Mismatch::Formula {
+            structure: "C2H4O2".parse().unwrap(),
+            inchi: "C2H3O2".parse().unwrap(),
+            counts: vec![(Element::Hydrogen, 4, 3)],
+        }),
+    );
+    assert_eq!(acetate.check_graph(&smiles("CC(=O)[O-]")), Ok(()),);
+}
 
-    let inchi: InChI = inchi.parse().unwrap();
-    let isomers = inchi.isomers();
-    assert!(!isomers.is_empty());
+#[test]
+fn test_kekulize_inchi() {
+    let inchi: InChI = "InChI=1S/C8H10N4O2/c1-10-4-9-6-5(10)7(13)12(3)8(14)11(6)2/h4H,1-3H3"
+        .parse()
+        .unwrap();
+    let mut graph = Graph::try_from(&inchi).unwrap();
+    graph.kekulize().unwrap();
+    assert_eq!(graph.pi_bonds.len(), 4);
+    assert_eq!(graph.aromaticity().atoms.len(), 9);
 
-    let any_match = isomers.iter().any(|isomer| {
-        let inchi_graph = UnGraph::from(isomer);
-        is_isomorphic_matching(&iupac_graph, &inchi_graph, eq, eq)
-    });
-    assert!(any_match);
+    // Only some placements of the mobile hydrogens have a Kekulé structure
+    let inchi: InChI = "InChI=1S/C5H5N5/c6-4-3-5(9-1-7-3)10-2-8-4/h1-2H,(H3,6,7,8,9,10)"
+        .parse()
+        .unwrap();
+    assert!(Graph::try_from(&inchi).is_err());
+    let tautomers = inchi.tautomers().collect::<Vec<_>>();
+    assert!(!tautomers.is_empty());
+    for tautomer in &tautomers {
+        assert!(tautomer.clone().kekulize().is_ok());
+        assert_eq!(Formula::from(tautomer), *inchi.formula());
+    }
+    let keys = tautomers
+        .iter()
+        .map(Graph::canonical_key)
+        .collect::<HashSet<_>>();
+    assert_eq!(keys.len(), tautomers.len());
 }
 
-fn eq<T: Eq>(a: &T, b: &T) -> bool {
-    a.eq(b)
+#[test]
+fn test_peptide_tautomer() {
+    // Each amide has its own group of mobile hydrogens, so there are too many
+    // placements to try one at a time
+    let smiles = format!("NCC(=O){}O", "NCC(=O)".repeat(29));
+    let graph = Graph::from_smiles(&smiles).unwrap();
+    let inchi: InChI = InChI::try_from(&graph)
+        .unwrap()
+        .to_string()
+        .parse()
+        .unwrap();
+    assert_eq!(
+        inchi.to_string(),
+        concat!(
+            "InChI=1S/C60H92N30O31/c61-1-31(91)62-2-32(92)63-3-33(93)64-4-34(94)65-5-35(95)",
+            "66-6-36(96)67-7-37(97)68-8-38(98)69-9-39(99)70-10-40(100)71-11-41(101)72-12-42(102)",
+            "73-13-43(103)74-14-44(104)75-15-45(105)76-16-46(106)77-17-47(107)78-18-48(108)",
+            "79-19-49(109)80-20-50(110)81-21-51(111)82-22-52(112)83-23-53(113)84-24-54(114)",
+            "85-25-55(115)86-26-56(116)87-27-57(117)88-28-58(118)89-29-59(119)90-30-60(120)121",
+            "/h1-30,61H2,(H,62,91)(H,63,92)(H,64,93)(H,65,94)(H,66,95)(H,67,96)(H,68,97)",
+            "(H,69,98)(H,70,99)(H,71,100)(H,72,101)(H,73,102)(H,74,103)(H,75,104)(H,76,105)",
+            "(H,77,106)(H,78,107)(H,79,108)(H,80,109)(H,81,110)(H,82,111)(H,83,112)(H,84,113)",
+            "(H,85,114)(H,86,115)(H,87,116)(H,88,117)(H,89,118)(H,90,119)(H,120,121)",
+        ),
+    );
+
+    let tautomer = inchi.tautomer().unwrap();
+    assert_eq!(Formula::from(&tautomer), Formula::from(&graph));
+    assert!(tautomer
+        .atoms
+        .iter()
+        .enumerate()
+        .all(|(i, &element)| { element == Element::Hydrogen || tautomer.valence_deficit(i) == 0 }));
+    assert_eq!(inchi.tautomers().take(20).count(), 20);
+}
+
+#[test]
+fn test_generate_inchi() {
+    for (smiles, inchi) in [
+        ("C", "InChI=1S/CH4/h1H4"),
+        ("CCO", "InChI=1S/C2H6O/c1-2-3/h3H,2H2,1H3"),
+        ("CCC=C", "InChI=1S/C4H8/c1-3-4-2/h3H,1,4H2,2H3"),
+        ("c1ccccc1", "InChI=1S/C6H6/c1-2-4-6-5-3-1/h1-6H"),
+        ("CC(=O)O", "InChI=1S/C2H4O2/c1-2(3)4/h1H3,(H,3,4)"),
+        ("CC(N)=O", "InChI=1S/C2H5NO/c1-2(3)4/h1H3,(H2,3,4)"),
+        (
+            "Oc1ccncc1",
+            "InChI=1S/C5H5NO/c7-5-1-3-6-4-2-5/h1-4H,(H,6,7)",
+        ),
+        // Hydrogen molecules have no connection layer
+        ("[H][H]", "InChI=1S/H2/h1H"),
+        ("[2H][2H]", "InChI=1S/H2/h1H/i1+1D"),
+        // Malonic acid and glycylglycine, with two groups of mobile hydrogens
+        (
+            "OC(=O)CC(=O)O",
+            "InChI=1S/C3H4O4/c4-2(5)1-3(6)7/h1H2,(H,4,5)(H,6,7)",
+        ),
+        (
+            "NCC(=O)NCC(=O)O",
+            "InChI=1S/C4H8N2O3/c5-1-3(7)6-2-4(8)9/h1-2,5H2,(H,6,7)(H,8,9)",
+        ),
+        (
+            "NCC(=O)NCC(=O)NCC(=O)O",
+            "InChI=1S/C6H11N3O4/c7-1-4(10)8-2-5(11)9-3-6(12)13/h1-3,7H2,(H,8,10)(H,9,11)(H,12,13)",
+        ),
+    ] {
+        let graph = Graph::from_smiles(smiles).unwrap();
+        let generated = InChI::try_from(&graph).unwrap();
+        assert_eq!(generated.to_string(), inchi, "{smiles}");
+    }
+
+    let graph = Graph::from_smiles("C[O-]").unwrap();
+    assert!(InChI::try_from(&graph).is_err());
+}
+
+#[test]
+fn test_isotopic_inchi() {
+    for (name, inchi, key) in [
+        (
+            "(²H₃)Methanol",
+            "InChI=1S/CH4O/c1-2/h2H,1H3/i1D3",
+            "OKKJLVBELUTLKV-FIBGUPNXSA-N",
+        ),
+        (
+            "(2H4)methanol",
+            "InChI=1S/CH4O/c1-2/h2H,1H3/i1D3,2D",
+            "OKKJLVBELUTLKV-MZCSYVLQSA-N",
+        ),
+        (
+            "(1-¹³C)ethanol",
+            "InChI=1S/C2H6O/c1-2-3/h3H,2H2,1H3/i2+1",
+            "LFQSCWFLJHTTHZ-VQEHIDDOSA-N",
+        ),
+        (
+            "[2-¹⁴C]ethanol",
+            "InChI=1S/C2H6O/c1-2-3/h3H,2H2,1H3/i1+2",
+            "LFQSCWFLJHTTHZ-NJFSPNSNSA-N",
+        ),
+        (
+            "(²H₃)ethane",
+            "InChI=1S/C2H6/c1-2/h1-2H3/i1D3",
+            "OTMSDBZUPAUEDD-FIBGUPNXSA-N",
+        ),
+    ] {
+        let graph = Graph::from(&*parse(name));
+        let generated = InChI::try_from(&graph).unwrap();
+        assert_eq!(generated.to_string(), inchi, "{name}");
+        assert_eq!(generated.key(), key, "{name}");
+
+        // The labels survive the round trip through the InChI
+        let parsed: InChI = inchi.parse().unwrap();
+        let round_trip = Graph::try_from(&parsed).unwrap();
+        assert_eq!(Formula::from(&round_trip), Formula::from(&graph), "{name}");
+        assert_eq!(InChI::try_from(&round_trip).unwrap().to_string(), inchi);
+        assert_eq!(parsed.check_name(name), Ok(()));
+    }
+}
+
+#[test]
+fn test_inchi_layers() {
+    for (inchi, key) in [
+        (
+            "InChI=1S/C3H7NO2/c1-2(4)3(5)6/h2H,4H2,1H3,(H,5,6)/t2-/m0/s1",
+            "QNAYBMKLOCPYGJ-REOHCLBHSA-N",
+        ),
+        (
+            "InChI=1S/C4H8/c1-3-4-2/h3-4H,1-2H3/b4-3+",
+            "IAQRGUVFOMOMEM-ONEGZZNKSA-N",
+        ),
+        (
+            "InChI=1S/C4H8/c1-3-4-2/h3-4H,1-2H3/b4-3-",
+            "IAQRGUVFOMOMEM-ARJAWSKDSA-N",
+        ),
+        (
+            "InChI=1S/C4H12N/c1-5(2,3)4/h1-4H3/q+1",
+            "QEMXHQIAXOOASZ-UHFFFAOYSA-N",
+        ),
+        (
+            "InChI=1S/C2H4O2/c1-2(3)4/h1H3,(H,3,4)/p-1",
+            "QTBSBXVTEAMEQO-UHFFFAOYSA-M",
+        ),
+        (
+            "InChI=1S/CH4O/c1-2/h2H,1H3/i1D3",
+            "OKKJLVBELUTLKV-FIBGUPNXSA-N",
+        ),
+        ("InChI=1S/CH4/h1H4/i1+1", "VNWKTOKETHGBQD-OUBTZVSYSA-N"),
+        ("InChI=1S/H2O/h1H2/i/hD2", "XLYOFNOQVPJJNP-ZSJDYOACSA-N"),
+        ("InChI=1S/H2/h1H", "UFHFLCQGNIYNRP-UHFFFAOYSA-N"),
+        ("InChI=1S/H2/h1H/i1+1D", "UFHFLCQGNIYNRP-VVKOMZTBSA-N"),
+        (
+            "InChI=1S/C3H4O4/c4-2(5)1-3(6)7/h1H2,(H,4,5)(H,6,7)",
+            "OFOBLEOULBTSOW-UHFFFAOYSA-N",
+        ),
+        (
+            "InChI=1S/C4H8N2O3/c5-1-3(7)6-2-4(8)9/h1-2,5H2,(H,6,7)(H,8,9)",
+            "YMAWOPBAYDPSLA-UHFFFAOYSA-N",
+        ),
+    ] {
+        let parsed: InChI = inchi.parse().unwrap();
+        assert_eq!(parsed.to_string(), inchi);
+        assert_eq!(parsed.key(), key, "{inchi}");
+    }
+
+    let alanine: InChI = "InChI=1S/C3H7NO2/c1-2(4)3(5)6/h2H,4H2,1H3,(H,5,6)/t2-/m0/s1"
+        .parse()
+        .unwrap();
+    let stereo = alanine.components()[0].stereo();
+    assert_eq!(stereo.tetrahedral, [(2, Parity::Odd)]);
+    assert_eq!(stereo.inverted, Some(false));
+    assert_eq!(alanine.stereo_type(), Some(StereoType::Absolute));
+
+    // Groups of mobile hydrogens follow each other without a comma, and each
+    // acid hydrogen is on either oxygen of its group
+    let malonic_acid: InChI = "InChI=1S/C3H4O4/c4-2(5)1-3(6)7/h1H2,(H,4,5)(H,6,7)"
+        .parse()
+        .unwrap();
+    assert_eq!(malonic_acid.tautomers().count(), 4);
+
+    // Fixed-hydrogen and reconnected layers of non-standard InChIs
+    for inchi in [
+        "InChI=1/C2H4O2/c1-2(3)4/h1H3,(H,3,4)/f/h3H",
+        "InChI=1/C2H6O/c1-2-3/h3H,2H2,1H3/i1+1/rC2H6O/c1-2-3/h3H,2H2,1H3",
+    ] {
+        let parsed: InChI = inchi.parse().unwrap();
+        assert!(!parsed.is_standard());
+        assert_eq!(parsed.to_string(), inchi);
+    }
+    let acetic_acid: InChI = "InChI=1/C2H4O2/c1-2(3)4/h1H3,(H,3,4)/f/h3H"
+        .parse()
+        .unwrap();
+    let fixed_hydrogens = acetic_acid.fixed_hydrogens().unwrap();
+    assert_eq!(
+        fixed_hydrogens.components()[0].immobile_hydrogens(),
+        [(vec![3..=3], 1)],
+    );
+    assert!(acetic_acid.key().ends_with("NA-N"));
+}
+
+#[test]
+fn test_inchi_layers_graph() {
+    let graph = |inchi: &str| {
+        let inchi: InChI = inchi.parse().unwrap();
+        inchi.tautomer().unwrap().canonical_smiles()
+    };
+    let smiles = |smiles: &str| Graph::from_smiles(smiles).unwrap().canonical_smiles();
+
+    assert_eq!(
+        graph("InChI=1S/C3H7NO2/c1-2(4)3(5)6/h2H,4H2,1H3,(H,5,6)/t2-/m0/s1"),
+        smiles("N[C@@H](C)C(=O)O"),
+    );
+    assert_eq!(
+        graph("InChI=1S/C3H7NO2/c1-2(4)3(5)6/h2H,4H2,1H3,(H,5,6)/t2-/m1/s1"),
+        smiles("N[C@H](C)C(=O)O"),
+    );
+    assert_eq!(
+        graph("InChI=1S/C4H10O/c1-3-4(2)5/h4-5H,3H2,1-2H3/t4-/m1/s1"),
+        smiles("CC[C@@H](C)O"),
+    );
+    assert_eq!(
+        graph("InChI=1S/C4H12N/c1-5(2,3)4/h1-4H3/q+1"),
+        smiles("C[N+](C)(C)C"),
+    );
+    assert_eq!(
+        graph("InChI=1S/C2H4O2/c1-2(3)4/h1H3,(H,3,4)/p-1"),
+        smiles("CC(=O)[O-]"),
+    );
+    assert_eq!(graph("InChI=1S/H3N/h1H3/p+1"), smiles("[NH4+]"));
+    assert_eq!(
+        graph("InChI=1S/C5H5N/c1-2-4-6-5-3-1/h1-5H/p+1"),
+        smiles("c1cc[nH+]cc1"),
+    );
+
+    for (inchi, together) in [
+        ("InChI=1S/C4H8/c1-3-4-2/h3-4H,1-2H3/b4-3+", false),
+        ("InChI=1S/C4H8/c1-3-4-2/h3-4H,1-2H3/b4-3-", true),
+    ] {
+        let inchi: InChI = inchi.parse().unwrap();
+        let graph = Graph::try_from(&inchi).unwrap();
+        assert_eq!(
+            graph.stereo,
+            [Stereo::DoubleBond {
+                atoms: [1, 3, 2, 0],
+                together,
+            }],
+        );
+    }
+
+    let inchi: InChI = "InChI=1S/CH4O/c1-2/h2H,1H3/i1+1D3".parse().unwrap();
+    let graph = Graph::try_from(&inchi).unwrap();
+    assert_eq!(graph.mass_number(0), Some(13));
+    let deuterium = (0..graph.atoms.len())
+        .filter(|&i| graph.mass_number(i) == Some(2))
+        .count();
+    assert_eq!(deuterium, 3);
+
+    let inchi: InChI = "InChI=1S/C4H12N/c1-5(2,3)4/h1-4H3/q+1/i1+1"
+        .parse()
+        .unwrap();
+    let ungraph = UnGraph::<Atom, Bond>::try_from(&inchi).unwrap();
+    assert_eq!(ungraph[NodeIndex::new(0)].mass_number, Some(13));
+    assert_eq!(ungraph[NodeIndex::new(4)].charge, 1);
+
+    let inchi: InChI = "InChI=1S/C4H10O/c1-3-4(2)5/h4-5H,3H2,1-2H3/t4-/m1/s1"
+        .parse()
+        .unwrap();
+    let ungraph = UnGraph::<Atom, Bond>::try_from(&inchi).unwrap();
+    assert_eq!(ungraph[NodeIndex::new(3)].parity, Some(Parity::Even));
+}
+
+#[test]
+fn test_multi_component_inchi() {
+    for (inchi, key) in [
+        ("InChI=1S/ClH.H3N/h1H;1H3", "NLXLAEXVIDQMFP-UHFFFAOYSA-N"),
+        (
+            "InChI=1S/C4H12N.ClH/c1-5(2,3)4;/h1-4H3;1H/q+1;/p-1",
+            "OKIZCWYLBDKLSU-UHFFFAOYSA-M",
+        ),
+        // Salts and hydrates of metals
+        (
+            "InChI=1S/C2H4O2.Na/c1-2(3)4;/h1H3,(H,3,4);/q;+1/p-1",
+            "VMHLLURERBWHNL-UHFFFAOYSA-M",
+        ),
+        (
+            "InChI=1S/Ca.2ClH/h;2*1H/q+2;;/p-2",
+            "UXVMQQNJUSDDNG-UHFFFAOYSA-L",
+        ),
+        (
+            "InChI=1S/Cu.H2O4S.5H2O/c;1-5(2,3)4;;;;;/h;(H2,1,2,3,4);5*1H2/q+2;;;;;;/p-2",
+            "JZCCFEFSEZPSOG-UHFFFAOYSA-L",
+        ),
+    ] {
+        let parsed: InChI = inchi.parse().unwrap();
+        assert_eq!(parsed.to_string(), inchi);
+        assert_eq!(parsed.key(), key, "{inchi}");
+    }
+
+    // Repeated components
+    let hydrate = "InChI=1S/C2H3Cl3O.3H2O/c3-2(4,5)1-6;;;/h6H,1H2;3*1H2";
+    let inchi: InChI = hydrate.parse().unwrap();
+    assert_eq!(inchi.to_string(), hydrate);
+    assert_eq!(inchi.components().len(), 4);
+    assert_eq!(inchi.formula().to_string(), "C2H9Cl3O4");
+
+    let alanine_hydrochloride =
+        "InChI=1S/C3H7NO2.ClH/c1-2(4)3(5)6;/h2H,4H2,1H3,(H,5,6);1H/t2-;/m0./s1";
+    let inchi: InChI = alanine_hydrochloride.parse().unwrap();
+    assert_eq!(inchi.to_string(), alanine_hydrochloride);
+    assert_eq!(inchi.components()[0].stereo().inverted, Some(false));
+    assert_eq!(inchi.components()[1].stereo().inverted, None);
+
+    let graph = |inchi: &str| {
+        let inchi: InChI = inchi.parse().unwrap();
+        inchi.tautomer().unwrap().canonical_smiles()
+    };
+    let smiles = |smiles: &str| Graph::from_smiles(smiles).unwrap().canonical_smiles();
+    assert_eq!(
+        graph("InChI=1S/C4H12N.ClH/c1-5(2,3)4;/h1-4H3;1H/q+1;/p-1"),
+        smiles("C[N+](C)(C)C.[Cl-]"),
+    );
+    assert_eq!(
+        graph("InChI=1S/C2H4O2.H3N/c1-2(3)4;/h1H3,(H,3,4);1H3"),
+        smiles("CC(=O)O.N"),
+    );
+    assert_eq!(
+        graph("InChI=1S/CH4O.2H2O/c1-2;;/h2H,1H3;2*1H2"),
+        smiles("CO.O.O"),
+    );
+    assert_eq!(
+        graph("InChI=1S/C2H4O2.Na/c1-2(3)4;/h1H3,(H,3,4);/q;+1/p-1"),
+        smiles("CC(=O)[O-].[Na+]"),
+    );
+    assert_eq!(
+        graph("InChI=1S/Ca.2ClH/h;2*1H/q+2;;/p-2"),
+        smiles("[Ca+2].[Cl-].[Cl-]"),
+    );
+
+    assert_eq!(
+        "InChI=1S/ClH.H3N/h1H;1H3;1H".parse::<InChI>().unwrap_err(),
+        InChIError::TooManyComponents {
+            layer: Layer::Hydrogens,
+            offset: 25,
+        },
+    );
+}
+
+#[test]
+fn test_inchi_errors() {
+    let error = |s: &str| s.parse::<InChI>().unwrap_err();
+
+    assert_eq!(error("C3H8O/c1-3(2)4"), InChIError::MissingPrefix);
+    assert_eq!(
+        error("InChI=1S/C3Xx8O"),
+        InChIError::InvalidFormula {
+            layer: Layer::Formula,
+            offset: 9,
+            message: "Unknown element",
+        },
+    );
+    assert_eq!(
+        error("InChI=1S/C3H8O/c1-3(2)4!"),
+        InChIError::UnexpectedCharacter {
+            layer: Layer::Connections,
+            offset: 23,
+            character: '!',
+        },
+    );
+    assert_eq!(
+        error("InChI=1S/C3H8O/c1-3(2-4"),
+        InChIError::UnmatchedParenthesis {
+            layer: Layer::Connections,
+            offset: 19,
+        },
+    );
+    assert_eq!(
+        error("InChI=1S/C3H8O/c1-3(2)5"),
+        InChIError::InvalidNumber {
+            layer: Layer::Connections,
+            offset: 22,
+        },
+    );
+    assert_eq!(
+        error("InChI=1S/C3H8O/c1-1"),
+        InChIError::InvalidNumber {
+            layer: Layer::Connections,
+            offset: 18,
+        },
+    );
+    assert_eq!(
+        error("InChI=1S/C3H8O/c1-3(2)4/h3-4H,1-2"),
+        InChIError::UnexpectedEnd {
+            layer: Layer::Hydrogens,
+            offset: 33,
+            expected: "'H'",
+        },
+    );
+    assert_eq!(
+        error("InChI=1S/C3H8O/c1-3(2)4/h3-4H,(H,99999999999999999999999)"),
+        InChIError::InvalidNumber {
+            layer: Layer::Hydrogens,
+            offset: 33,
+        },
+    );
+    // Isotopes out of range, and more isotopic hydrogens than hydrogens
+    for isotopic in [
+        "1+2147483647",
+        "1+99999",
+        "1-20",
+        "1D99999999999",
+        "1D4",
+        "/hD5",
+    ] {
+        assert_eq!(
+            error(&format!("InChI=1S/CH4O/c1-2/h2H,1H3/i{isotopic}")),
+            InChIError::InvalidNumber {
+                layer: Layer::Isotopic,
+                offset: if isotopic.starts_with('/') { 30 } else { 29 },
+            },
+            "{isotopic}",
+        );
+    }
+
+    // Charges that no atom can take, such as a cation of ethane
+    assert_eq!(
+        error("InChI=1S/C2H6/c1-2/h1-2H3/q+1"),
+        InChIError::UnplacedCharge {
+            layer: Layer::Charge,
+            offset: 27,
+        },
+    );
+    assert_eq!(
+        error("InChI=1S/CH4/h1H4/p+1"),
+        InChIError::UnplacedCharge {
+            layer: Layer::Protons,
+            offset: 19,
+        },
+    );
+
+    // Errors in the fixed-hydrogen and reconnected layers name them
+    assert_eq!(
+        error("InChI=1/C2H4O2/c1-2(3)4/h1H3,(H,3,4)/fC2Xx/h3H"),
+        InChIError::InvalidFormula {
+            layer: Layer::FixedHydrogens,
+            offset: 38,
+            message: "Unknown element",
+        },
+    );
+    let fixed_hydrogens = error("InChI=1/C2H4O2/c1-2(3)4/h1H3,(H,3,4)/f/h9H");
+    assert_eq!(
+        fixed_hydrogens,
+        InChIError::InvalidNumber {
+            layer: Layer::FixedHydrogens,
+            offset: 40,
+        },
+    );
+    assert_eq!(
+        fixed_hydrogens.to_string(),
+        "Invalid number in the fixed hydrogen layer at offset 40",
+    );
+    assert_eq!(
+        error("InChI=1/C2H6O/c1-2-3/h3H,2H2,1H3/rC2Xx"),
+        InChIError::InvalidFormula {
+            layer: Layer::Reconnected,
+            offset: 34,
+            message: "Unknown element",
+        },
+    );
+
+    // The formula has four hydrogens fewer than the hydrogen layer
+    assert_eq!(
+        error("InChI=1S/C5HN5O/c6-5-9-3-2(4(11)10-5)7-1-8-3/h1H,(H4,6,7,8,9,10,11)"),
+        InChIError::HydrogenCount { offset: 46 },
+    );
+
+    assert_eq!(
+        error("InChI=1S/C3H8O/c1-3(2)4/h3-4H,1-2H3/t3+/m2"),
+        InChIError::InvalidNumber {
+            layer: Layer::Inverted,
+            offset: 41,
+        },
+    );
+    assert_eq!(
+        error("InChI=1S/C3H8O/c1-3(2)4/h3-4H,1-2H3/t3*"),
+        InChIError::UnexpectedCharacter {
+            layer: Layer::Tetrahedral,
+            offset: 38,
+            character: '*',
+        },
+    );
+    assert_eq!(
+        error("InChI=1S/C3H8O/c1-3(2)4/h3-4H,1-2H3/q+1/c1-2"),
+        InChIError::UnexpectedLayer { offset: 40 },
+    );
+
+    let message = error("InChI=1S/C3H8O/c1-3(2)4/h3-4H,1-2").to_string();
+    assert_eq!(
+        message,
+        "Expected 'H', got nothing in the hydrogens layer at offset 33",
+    );
 }
@@ -0,0 +1,39 @@
+//! Prints an SD file of laid out structures for IUPAC names, with NAME and
+//! FORMULA data fields.
+//!
+//! # Usage
+//!
+//! ```sh
+//! cargo run --bin sdf 'Propan-2-ol' 'Butan-1-ol' > molecules.sdf
+//! cargo run --bin sdf -- --v3000 'Propan-2-ol'
+//! ```
+
+use std::env;
+
+use blue_book::{
+    formula::Formula,
+    graph::Graph,
+    molfile::{write_sdf, SdfRecord, Version},
+    parser::parse,
+};
+use structural_formula::molfile::ToMolfile;
+
+fn main() {
+    let mut version = Version::V2000;
+    let mut records = Vec::new();
+    for arg in env::args().skip(1) {
+        match arg.as_str() {
+            "--v3000" => version = Version::V3000,
+            name => {
+                let graph = Graph::from(&*parse(name));
+                let formula = Formula::from(&graph).to_string();
+                let mut record = SdfRecord::new(graph.to_molfile(name));
+                record.data.push(("NAME".to_owned(), name.to_owned()));
+                record.data.push(("FORMULA".to_owned(), formula));
+                records.push(record);
+            }
+        }
+    }
+
+    print!("{}", write_sdf(&records, version));
+}
@@ -0,0 +1,265 @@
+use blue_book::{graph::Graph, Element};
+use glam::Vec2;
+use petgraph::graph::NodeIndex;
+
+use crate::structure::{Atom, Bond, Structure};
+
+impl From<&Graph> for Structure {
+    /// Kekulizes mancude rings, collapses terminal hydrogens into hydrogen
+    /// counts, turns pi bonds into bond orders, keeps charges and mass numbers
+    /// and lays out the remaining atoms. Hydrogens that are specific isotopes
+    /// or part of a stereo configuration stay atoms of their own, so that the
+    /// configurations can refer to them.
+    fn from(graph: &Graph) -> Self {
+        let mut kekulized = graph.clone();
+        // Structures without a Kekulé form are drawn with single bonds
+        if kekulized.kekulize().is_ok() {
+            return Structure::from_kekulized(&kekulized);
+        }
+        Structure::from_kekulized(graph)
+    }
+}
+
+impl Structure {
+    fn from_kekulized(graph: &Graph) -> Self {
+        let mut structure = Structure::new();
+        let stereo_atoms = graph
+            .stereo
+            .iter()
+            .flat_map(|stereo| stereo.atoms())
+            .collect::<Vec<_>>();
+        let mut nodes = vec![None; graph.atoms.len()];
+        for (i, &element) in graph.atoms.iter().enumerate() {
+            if graph.is_folded_hydrogen(i) && !stereo_atoms.contains(&i) {
+                continue;
+            }
+            let atom = Atom {
+                element,
+                hydrogen_count: 0,
+                charge: graph.charge(i),
+                mass_number: graph.mass_number(i),
+                position: Vec2::ZERO,
+            };
+            nodes[i] = Some(structure.graph.add_node(atom));
+        }
+
+        for &(a, b) in &graph.bonds {
+            match (nodes[a], nodes[b]) {
+                (Some(a), Some(b)) => {
+                    structure.graph.add_edge(a, b, Bond { bond_order: 1 });
+                }
+                (Some(id), None) | (None, Some(id)) => {
+                    structure.graph[id].hydrogen_count += 1;
+                }
+                (None, None) => unreachable!("implicit hydrogens are never bonded together"),
+            }
+        }
+        for &(a, b) in &graph.pi_bonds {
+            let (Some(a), Some(b)) = (nodes[a], nodes[b]) else {
+                unreachable!("implicit hydrogens never have pi bonds")
+            };
+            let edge = structure.graph.find_edge(a, b).unwrap();
+            structure.graph[edge].bond_order += 1;
+        }
+
+        structure.locants = graph
+            .positions
+            .iter()
+            .filter_map(|&(locant, i)| Some((locant, nodes[i]?)))
+            .collect();
+
+        for &i in &graph.free_valences {
+            let id = nodes[i].unwrap();
+            match structure.free_valences.iter_mut().find(|(j, _)| *j == id) {
+                Some((_, order)) => *order += 1,
+                None => structure.free_valences.push((id, 1)),
+            }
+        }
+
+        structure.stereo = graph.stereo.clone();
+        for stereo in &mut structure.stereo {
+            stereo.map_atoms(|i| nodes[i].unwrap().index());
+        }
+
+        structure.layout();
+        structure
+    }
+}
+
+impl From<&Structure> for Graph {
+    /// Expands hydrogen counts into hydrogen atoms, which are placed at the end
+    /// of the atom list, and bond orders into pi bonds. Charges, mass numbers
+    /// and stereo configurations are kept and coordinates are discarded.
+    fn from(structure: &Structure) -> Self {
+        let index = |id: NodeIndex| id.index();
+
+        let mut graph = Graph {
+            atoms: structure
+                .graph
+                .node_weights()
+                .map(|atom| atom.element)
+                .collect(),
+            ..Default::default()
+        };
+
+        for id in structure.graph.node_indices() {
+            if structure.graph[id].charge != 0 {
+                graph.charges.push((index(id), structure.graph[id].charge));
+            }
+            if let Some(mass_number) = structure.graph[id].mass_number {
+                graph.isotopes.push((index(id), mass_number));
+            }
+        }
+
+        for edge in structure.graph.edge_indices() {
+            let (a, b) = structure.graph.edge_endpoints(edge).unwrap();
+            let (a, b) = (index(a), index(b));
+            graph.bonds.push((a, b));
+            for _ in 1..structure.graph[edge].bond_order {
+                graph.pi_bonds.push((a, b));
+            }
+        }
+
+        for id in structure.graph.node_indices() {
+            for _ in 0..structure.graph[id].hydrogen_count {
+                let h = graph.atoms.len();
+                graph.atoms.push(Element::Hydrogen);
+                graph.bonds.push((index(id), h));
+            }
+        }
+
+        graph.positions = structure
+            .locants
+            .iter()
+            .map(|&(locant, id)| (locant, index(id)))
+            .collect();
+        graph.free_valences = structure
+            .free_valences
+            .iter()
+            .flat_map(|&(id, order)| (0..order).map(move |_| index(id)))
+            .collect();
+        graph.stereo = structure.stereo.clone();
+
+        graph
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use blue_book::{graph::Graph, parser::parse, test, Element};
+    use petgraph::{algo::is_isomorphic_matching, graph::UnGraph};
+
+    use crate::structure::{Structure, ToStructure};
+
+    #[test]
+    fn test_round_trip() {
+        for name in [
+            test::ISOPROPANOL,
+            test::ISOBUTANE,
+            test::DOPAMINE,
+            test::SALBUTAMOL,
+            test::CAFFEINE,
+            test::ADENINE,
+            test::THYMINE,
+            test::CYTOSINE,
+            test::GUANINE,
+            "Ethene",
+            "Propyne",
+        ] {
+            let mut graph = Graph::from(&*parse(name));
+            graph.kekulize().unwrap();
+            let structure = Structure::from(&graph);
+            let round_trip = Graph::from(&structure);
+
+            assert_eq!(round_trip.atoms.len(), graph.atoms.len(), "{name}");
+            assert_eq!(round_trip.pi_bonds.len(), graph.pi_bonds.len(), "{name}");
+            assert_eq!(round_trip.positions.len(), graph.positions.len(), "{name}");
+            assert!(
+                is_isomorphic_matching(
+                    &UnGraph::<Element, ()>::from(&graph),
+                    &UnGraph::<Element, ()>::from(&round_trip),
+                    |a, b| a == b,
+                    |_, _| true,
+                ),
+                "{name}",
+            );
+        }
+    }
+
+    #[test]
+    fn test_isotopes() {
+        let graph = Graph::from_smiles("[13CH3]C([2H])=O").unwrap();
+        let structure = Structure::from(&graph);
+
+        // The deuterium stays an atom, while the other hydrogens are counted
+        assert_eq!(structure.graph.node_count(), 4);
+        let mass_numbers = structure
+            .graph
+            .node_weights()
+            .filter_map(|atom| atom.mass_number)
+            .collect::<Vec<_>>();
+        assert_eq!(mass_numbers, vec![13, 2]);
+
+        let round_trip = Graph::from(&structure);
+        assert_eq!(round_trip.canonical_smiles(), graph.canonical_smiles());
+    }
+
+    #[test]
+    fn test_stereo() {
+        for smiles in ["C/C=C/C", "C/C=C\\C", "N[C@@H](C)C(=O)O", "N[C@H](C)C(=O)O"] {
+            let graph = Graph::from_smiles(smiles).unwrap();
+            assert!(!graph.stereo.is_empty(), "{smiles}");
+
+            let round_trip = Graph::from(&Structure::from(&graph));
+            assert_eq!(round_trip.stereo.len(), graph.stereo.len(), "{smiles}");
+            assert_eq!(
+                round_trip.canonical_smiles(),
+                graph.canonical_smiles(),
+                "{smiles}",
+            );
+        }
+
+        // The hydrogen of the stereocentre stays an atom
+        let graph = Graph::from_smiles("N[C@@H](C)C(=O)O").unwrap();
+        assert_eq!(Structure::from(&graph).graph.node_count(), 7);
+    }
+
+    #[test]
+    fn test_bond_orders() {
+        let structure = parse("Propyne").to_structure();
+
+        let orders = structure
+            .graph
+            .edge_weights()
+            .map(|bond| bond.bond_order)
+            .collect::<Vec<_>>();
+        assert_eq!(orders, vec![3, 1]);
+        assert_eq!(structure.graph[structure.locants[0].1].hydrogen_count, 1);
+    }
+
+    #[test]
+    fn test_kekulized() {
+        let structure = parse(test::DOPAMINE).to_structure();
+
+        let double_bonds = structure
+            .graph
+            .edge_weights()
+            .filter(|bond| bond.bond_order == 2)
+            .count();
+        assert_eq!(double_bonds, 3);
+    }
+
+    #[test]
+    fn test_from_smiles() {
+        let graph = Graph::from_smiles("NCCc1ccc(O)c(O)c1").unwrap();
+        let structure = Structure::from(&graph);
+
+        assert_eq!(structure.graph.node_count(), 11);
+        let double_bonds = structure
+            .graph
+            .edge_weights()
+            .filter(|bond| bond.bond_order == 2)
+            .count();
+        assert_eq!(double_bonds, 3);
+    }
+}
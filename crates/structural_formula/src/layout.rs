@@ -0,0 +1,284 @@
+//! Positions atoms in two passes. The first is stress majorization, which
+//! places atoms so that their distances match the number of bonds between them
+//! and so keeps rings untangled. The second minimises the potential energy of a
+//! mechanical model of the molecule, as in the `gradient_descent` crate, with
+//! bonds and bond angles modelled as springs and atoms as repelling charges.
+
+use std::{collections::VecDeque, f32::consts::PI};
+
+use glam::Vec2;
+use petgraph::{graph::NodeIndex, visit::EdgeRef};
+
+use crate::structure::Structure;
+
+const MDS_ITERATIONS: usize = 100;
+const STRESS_ITERATIONS: usize = 300;
+const SPRING_ITERATIONS: usize = 1000;
+
+const STEP_SIZE: f32 = 0.05;
+const BOND_STIFFNESS: f32 = 1.0;
+const BOND_TARGET_LENGTH: f32 = 1.0;
+const ANGLE_STIFFNESS: f32 = 0.5;
+const ATOM_REPULSION: f32 = 0.01;
+const COMPONENT_SPACING: f32 = 2.0;
+
+impl Structure {
+    /// Assigns a position to every atom, with bonds of roughly unit length.
+    pub fn layout(&mut self) {
+        let mut right = f32::NEG_INFINITY;
+        for component in components(self) {
+            let index = |id: NodeIndex| component.iter().position(|&i| i == id.index());
+            let bonds = self
+                .graph
+                .edge_references()
+                .filter_map(|edge| Some((index(edge.source())?, index(edge.target())?)))
+                .collect::<Vec<_>>();
+
+            let mut positions = stress_majorization(&bonds, component.len());
+            let springs = springs(&bonds, component.len());
+            for _ in 0..SPRING_ITERATIONS {
+                step(&springs, &mut positions);
+            }
+
+            // Lay components out from left to right
+            let left = positions.iter().map(|p| p.x).fold(f32::INFINITY, f32::min);
+            let offset = if right.is_finite() {
+                Vec2::new(right + COMPONENT_SPACING - left, 0.0)
+            } else {
+                Vec2::ZERO
+            };
+            for (&i, &position) in component.iter().zip(&positions) {
+                let position = position + offset;
+                self.graph[NodeIndex::new(i)].position = position;
+                right = right.max(position.x);
+            }
+        }
+    }
+}
+
+fn components(structure: &Structure) -> Vec<Vec<usize>> {
+    let graph = &structure.graph;
+
+    let mut components = Vec::new();
+    let mut visited = vec![false; graph.node_count()];
+    for root in graph.node_indices() {
+        if visited[root.index()] {
+            continue;
+        }
+        visited[root.index()] = true;
+
+        let mut component = Vec::new();
+        let mut queue = VecDeque::from([root]);
+        while let Some(id) = queue.pop_front() {
+            component.push(id.index());
+            for neighbor in graph.neighbors(id) {
+                if !visited[neighbor.index()] {
+                    visited[neighbor.index()] = true;
+                    queue.push_back(neighbor);
+                }
+            }
+        }
+        component.sort();
+        components.push(component);
+    }
+    components
+}
+
+/// The number of bonds on the shortest path between each pair of atoms.
+fn bond_distances(bonds: &[(usize, usize)], n: usize) -> Vec<Vec<usize>> {
+    let mut neighbors = vec![Vec::new(); n];
+    for &(a, b) in bonds {
+        neighbors[a].push(b);
+        neighbors[b].push(a);
+    }
+
+    (0..n)
+        .map(|root| {
+            let mut distances = vec![usize::MAX; n];
+            distances[root] = 0;
+            let mut queue = VecDeque::from([root]);
+            while let Some(i) = queue.pop_front() {
+                for &j in &neighbors[i] {
+                    if distances[j] == usize::MAX {
+                        distances[j] = distances[i] + 1;
+                        queue.push_back(j);
+                    }
+                }
+            }
+            distances
+        })
+        .collect()
+}
+
+/// The distance between two atoms `d` bonds apart along a zig-zag chain.
+fn zig_zag_length(d: usize) -> f32 {
+    let x = 0.5 * 3f32.sqrt() * BOND_TARGET_LENGTH * d as f32;
+    let y = if d % 2 == 1 {
+        0.5 * BOND_TARGET_LENGTH
+    } else {
+        0.0
+    };
+    (x * x + y * y).sqrt()
+}
+
+/// Lays out a connected component by classical multidimensional scaling,
+/// refined by stress majorization.
+fn stress_majorization(bonds: &[(usize, usize)], n: usize) -> Vec<Vec2> {
+    if n == 1 {
+        return vec![Vec2::ZERO];
+    }
+
+    let distances = bond_distances(bonds, n);
+    let targets = distances
+        .iter()
+        .map(|row| row.iter().map(|&d| zig_zag_length(d)).collect::<Vec<_>>())
+        .collect::<Vec<_>>();
+
+    // Double-centre the squared distances
+    let squared = |i: usize, j: usize| targets[i][j].powi(2);
+    let row_means = (0..n)
+        .map(|i| (0..n).map(|j| squared(i, j)).sum::<f32>() / n as f32)
+        .collect::<Vec<_>>();
+    let mean = row_means.iter().sum::<f32>() / n as f32;
+    let gram = (0..n)
+        .map(|i| {
+            (0..n)
+                .map(|j| -0.5 * (squared(i, j) - row_means[i] - row_means[j] + mean))
+                .collect::<Vec<_>>()
+        })
+        .collect::<Vec<_>>();
+
+    // Find the two largest eigenvectors by power iteration with deflation
+    let mut axes: Vec<Vec<f32>> = Vec::new();
+    for axis in 0..2 {
+        let mut v = (0..n)
+            .map(|i| ((i + 1) as f32 * (axis + 1) as f32).sin())
+            .collect::<Vec<_>>();
+        let mut eigenvalue = 0.0;
+        for _ in 0..MDS_ITERATIONS {
+            let mut w = (0..n)
+                .map(|i| (0..n).map(|j| gram[i][j] * v[j]).sum::<f32>())
+                .collect::<Vec<_>>();
+            for previous in &axes {
+                let dot = w.iter().zip(previous).map(|(a, b)| a * b).sum::<f32>();
+                let norm = previous.iter().map(|a| a * a).sum::<f32>();
+                if norm > f32::EPSILON {
+                    for (a, b) in w.iter_mut().zip(previous) {
+                        *a -= dot / norm * b;
+                    }
+                }
+            }
+            eigenvalue = w.iter().map(|a| a * a).sum::<f32>().sqrt();
+            if eigenvalue <= f32::EPSILON {
+                break;
+            }
+            v = w.into_iter().map(|a| a / eigenvalue).collect();
+        }
+        let scale = eigenvalue.sqrt();
+        axes.push(v.into_iter().map(|a| a * scale).collect());
+    }
+    let mut positions = (0..n)
+        .map(|i| Vec2::new(axes[0][i], axes[1][i]) + 0.01 * Vec2::from_angle(i as f32))
+        .collect::<Vec<_>>();
+
+    // Move each atom to where its distances best match the targets, weighting
+    // nearby atoms more heavily
+    for _ in 0..STRESS_ITERATIONS {
+        for i in 0..n {
+            let mut sum = Vec2::ZERO;
+            let mut weight_sum = 0.0;
+            for j in (0..n).filter(|&j| j != i) {
+                let weight = 1.0 / targets[i][j].powi(2);
+                let r_vec = positions[i] - positions[j];
+                let r = r_vec.length().max(f32::EPSILON);
+                sum += weight * (positions[j] + targets[i][j] * r_vec / r);
+                weight_sum += weight;
+            }
+            positions[i] = sum / weight_sum;
+        }
+    }
+
+    positions
+}
+
+/// Returns `(a, b, target_length, stiffness)` for every bond, and for every
+/// pair of atoms bonded to a common atom. The latter hold the bonds apart at
+/// equal angles around the common atom.
+fn springs(bonds: &[(usize, usize)], n: usize) -> Vec<(usize, usize, f32, f32)> {
+    let mut neighbors = vec![Vec::new(); n];
+    for &(a, b) in bonds {
+        neighbors[a].push(b);
+        neighbors[b].push(a);
+    }
+
+    let mut springs = bonds
+        .iter()
+        .map(|&(a, b)| (a, b, BOND_TARGET_LENGTH, BOND_STIFFNESS))
+        .collect::<Vec<_>>();
+    for neighbors in &neighbors {
+        let angle = 2.0 * PI / neighbors.len().max(3) as f32;
+        let target_length = 2.0 * BOND_TARGET_LENGTH * (0.5 * angle).sin();
+        for (k, &i) in neighbors.iter().enumerate() {
+            for &j in &neighbors[k + 1..] {
+                springs.push((i, j, target_length, ANGLE_STIFFNESS));
+            }
+        }
+    }
+    springs
+}
+
+/// Takes a gradient descent step.
+fn step(springs: &[(usize, usize, f32, f32)], positions: &mut [Vec2]) {
+    let n = positions.len();
+    let mut energy_gradient = vec![Vec2::ZERO; n];
+
+    for &(i, j, target_length, stiffness) in springs {
+        let u_vec = positions[j] - positions[i];
+        let u = u_vec.length();
+        if u <= f32::EPSILON {
+            continue;
+        }
+        let x = u - target_length;
+        let denergy_by_du_vec = stiffness * x * u_vec / u;
+        energy_gradient[j] += denergy_by_du_vec;
+        energy_gradient[i] -= denergy_by_du_vec;
+    }
+
+    // Model atoms as repelling charges
+    for i in 0..n {
+        for j in i + 1..n {
+            let r_vec = positions[j] - positions[i];
+            let r = r_vec.length();
+            if r <= f32::EPSILON {
+                continue;
+            }
+            let denergy_by_dr_vec = -ATOM_REPULSION / r.powi(2) * r_vec / r;
+            energy_gradient[j] += denergy_by_dr_vec;
+            energy_gradient[i] -= denergy_by_dr_vec;
+        }
+    }
+
+    for (position, gradient) in positions.iter_mut().zip(energy_gradient) {
+        *position -= STEP_SIZE * gradient;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use blue_book::parser::parse;
+    use petgraph::visit::EdgeRef;
+
+    use crate::structure::ToStructure;
+
+    #[test]
+    fn test_bond_lengths() {
+        let structure = parse("4-(2-Aminoethyl)benzene-1,2-diol").to_structure();
+
+        for bond in structure.graph.edge_references() {
+            let a = structure.graph[bond.source()].position;
+            let b = structure.graph[bond.target()].position;
+            let length = a.distance(b);
+            assert!((0.8..1.2).contains(&length), "bond length {length}");
+        }
+    }
+}
@@ -1,7 +1,7 @@
-pub mod characteristic_groups;
-pub mod hydrides;
+pub mod graph;
+pub mod layout;
+pub mod molfile;
 pub mod rect;
 pub mod structure;
-pub mod substitution;
 pub mod svg;
 pub mod transform;
@@ -0,0 +1,92 @@
+//! Molfiles with 2D coordinates taken from the layout of a structure.
+
+use blue_book::{graph::Graph, molfile::Molfile, parser::AST};
+
+use crate::structure::Structure;
+
+/// A typical carbon–carbon bond length, in ångströms, to scale the unit bonds
+/// of the layout by.
+const BOND_LENGTH: f32 = 1.5;
+
+pub trait ToMolfile {
+    fn to_molfile(&self, name: &str) -> Molfile;
+}
+
+impl ToMolfile for AST {
+    fn to_molfile(&self, name: &str) -> Molfile {
+        Graph::from(self).to_molfile(name)
+    }
+}
+
+impl ToMolfile for Graph {
+    /// Lays out the molecule, placing terminal hydrogens that are not a
+    /// specific isotope, which the structure counts rather than lays out, on
+    /// the atom they are bonded to.
+    fn to_molfile(&self, name: &str) -> Molfile {
+        let structure = Structure::from(self);
+
+        // Nodes are added in atom order, skipping folded hydrogens
+        let mut nodes = structure.graph.node_weights();
+        let mut coordinates = (0..self.atoms.len())
+            .map(|i| {
+                if self.is_folded_hydrogen(i) {
+                    return None;
+                }
+                let position = nodes.next().unwrap().position * BOND_LENGTH;
+                // The layout's y axis points down, as in SVG
+                Some([position.x as f64, -position.y as f64])
+            })
+            .collect::<Vec<_>>();
+        for &(a, b) in &self.bonds {
+            match (coordinates[a], coordinates[b]) {
+                (Some(position), None) => coordinates[b] = Some(position),
+                (None, Some(position)) => coordinates[a] = Some(position),
+                _ => {}
+            }
+        }
+
+        Molfile {
+            name: name.to_owned(),
+            graph: self.clone(),
+            coordinates: coordinates
+                .into_iter()
+                .map(Option::unwrap_or_default)
+                .collect(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use blue_book::{
+        graph::Graph,
+        molfile::{Molfile, Version},
+        parser::parse,
+        test::{CAFFEINE, DOPAMINE},
+    };
+
+    use super::ToMolfile;
+
+    #[test]
+    fn test_coordinates() {
+        for name in [DOPAMINE, CAFFEINE] {
+            let graph = Graph::from(&*parse(name));
+            let molfile = graph.to_molfile(name);
+            assert_eq!(molfile.coordinates.len(), graph.atoms.len());
+
+            // Bonded atoms are roughly a bond length apart
+            for &(a, b) in &graph.bonds {
+                if graph.is_terminal_hydrogen(a) || graph.is_terminal_hydrogen(b) {
+                    continue;
+                }
+                let [xa, ya] = molfile.coordinates[a];
+                let [xb, yb] = molfile.coordinates[b];
+                let length = (xa - xb).hypot(ya - yb);
+                assert!((0.75..2.25).contains(&length), "{name}: {length}");
+            }
+
+            let round_trip = molfile.write(Version::V2000).parse::<Molfile>().unwrap();
+            assert_eq!(round_trip.graph.canonical_key(), graph.canonical_key());
+        }
+    }
+}
@@ -1,13 +1,19 @@
-use blue_book::{parser::AST, Element, Locant};
+use blue_book::{
+    graph::{stereo::Stereo, Graph},
+    parser::AST,
+    Element, Locant,
+};
 use glam::Vec2;
+use inchi::InChI;
 use petgraph::graph::{NodeIndex, UnGraph};
 
-use crate::substitution::substitute;
-
 #[derive(Debug, Clone)]
 pub struct Atom {
     pub element: Element,
     pub hydrogen_count: u8,
+    pub charge: i8,
+    /// The mass number, for an atom that is a specific isotope.
+    pub mass_number: Option<u16>,
     pub position: Vec2,
 }
 
@@ -20,7 +26,10 @@ pub struct Bond {
 #[derive(Debug, Default, Clone)]
 pub struct Structure {
     pub graph: UnGraph<Atom, Bond>,
+    pub locants: Vec<(Locant, NodeIndex)>,
     pub free_valences: Vec<(NodeIndex, u8)>,
+    /// Stereo configurations, with the indices of nodes in place of atoms.
+    pub stereo: Vec<Stereo>,
 }
 
 pub trait ToStructure {
@@ -29,23 +38,25 @@ pub trait ToStructure {
 
 impl ToStructure for AST {
     fn to_structure(&self) -> Structure {
-        match self {
-            AST::Hydride(hydride) => hydride.to_structure(),
-            AST::Group(ast) => into_group(ast.to_structure()),
-            AST::CharacteristicGroup(group) => group.to_structure(),
-            AST::Unsaturated(_, _) => todo!(),
-            AST::Substitution(locant, group, parent) => {
-                substitute(*locant, group.to_structure(), parent.to_structure())
-            }
-        }
+        Structure::from(&Graph::from(self))
     }
 }
 
-fn into_group(mut structure: Structure) -> Structure {
-    let id = structure.locate(Locant::Number(1)).unwrap();
-    structure.graph[id].hydrogen_count -= 1;
-    structure.free_valences.push((id, 1));
-    structure
+impl ToStructure for Graph {
+    fn to_structure(&self) -> Structure {
+        Structure::from(self)
+    }
+}
+
+impl TryFrom<&InChI> for Structure {
+    type Error = &'static str;
+
+    /// Places the mobile hydrogens and the bond orders of one tautomer, which
+    /// needs a Kekulé structure to exist.
+    fn try_from(inchi: &InChI) -> Result<Self, Self::Error> {
+        let graph = inchi.tautomer().ok_or("No Kekulé structure exists")?;
+        Ok(Structure::from(&graph))
+    }
 }
 
 impl Structure {
@@ -54,33 +65,22 @@ impl Structure {
     }
 
     pub fn locate(&self, locant: Locant) -> Option<NodeIndex> {
-        match locant {
-            Locant::Unspecified => todo!(),
-            Locant::Number(n) => self.nth_atom_of_element(n, Element::Carbon),
-            Locant::Element(n, element) => self.nth_atom_of_element(n, element),
-        }
-    }
-
-    fn nth_atom_of_element(&self, n: u16, element: Element) -> Option<NodeIndex> {
-        let mut current_n = 1;
-        for id in self.graph.node_indices() {
-            if self.graph.node_weight(id).unwrap().element != element {
-                continue;
-            }
-            if current_n == n {
-                return Some(id);
-            }
-            current_n += 1;
+        if locant == Locant::Unspecified {
+            return self.locants.first().map(|&(_, id)| id);
         }
-        None
+        self.locants
+            .iter()
+            .find(|&&(l, _)| l == locant)
+            .map(|&(_, id)| id)
     }
 }
 
 #[cfg(test)]
 mod tests {
-    use blue_book::{parser::parse, test::ISOPROPANOL};
+    use blue_book::{parser::parse, test::ISOPROPANOL, Element, Locant};
+    use inchi::InChI;
 
-    use super::ToStructure;
+    use super::{Structure, ToStructure};
 
     #[test]
     fn test_structure_simple() {
@@ -90,5 +90,42 @@ mod tests {
         assert_eq!(structure.graph.node_count(), 4);
         assert_eq!(structure.graph.edge_count(), 3);
         assert!(structure.free_valences.is_empty());
+
+        let c2 = structure.locate(Locant::Number(2)).unwrap();
+        assert_eq!(structure.graph[c2].hydrogen_count, 1);
+    }
+
+    #[test]
+    fn test_structure_from_inchi() {
+        // Acetic acid, with its mobile hydrogen removed by the /p layer
+        let inchi: InChI = "InChI=1S/C2H4O2/c1-2(3)4/h1H3,(H,3,4)/p-1".parse().unwrap();
+        let structure = Structure::try_from(&inchi).unwrap();
+
+        assert_eq!(structure.graph.node_count(), 4);
+        let orders = structure
+            .graph
+            .edge_weights()
+            .map(|bond| bond.bond_order)
+            .sum::<u8>();
+        assert_eq!(orders, 4);
+        let hydrogens = structure
+            .graph
+            .node_weights()
+            .map(|atom| atom.hydrogen_count)
+            .sum::<u8>();
+        assert_eq!(hydrogens, 3);
+        let charged = structure
+            .graph
+            .node_weights()
+            .filter(|atom| atom.charge != 0)
+            .collect::<Vec<_>>();
+        assert_eq!(charged.len(), 1);
+        assert_eq!(charged[0].element, Element::Oxygen);
+        assert_eq!(charged[0].charge, -1);
+        assert!(structure.svg().to_string().contains("−</text>"));
+
+        // The methyl radical has no Kekulé structure
+        let inchi: InChI = "InChI=1S/CH3/h1H3".parse().unwrap();
+        assert!(Structure::try_from(&inchi).is_err());
     }
 }
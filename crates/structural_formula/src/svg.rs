@@ -9,13 +9,16 @@ const SCALE: f32 = 30.0;
 const PADDING: f32 = 15.0;
 const FONT_SIZE: f32 = 16.0;
 const TEXT_EXCLUSION_RADIUS: f32 = 6.0;
+const BOND_SPACING: f32 = 4.0;
+const CHARGE_FONT_SIZE: f32 = 11.0;
+const CHARGE_OFFSET: f32 = 8.0;
 
 pub struct SVG<'a> {
     structure: &'a Structure,
 }
 
 impl Structure {
-    pub fn svg(&self) -> SVG {
+    pub fn svg(&self) -> SVG<'_> {
         SVG { structure: self }
     }
 }
@@ -82,10 +85,25 @@ fn write_atom(f: &mut fmt::Formatter, atom: &Atom) -> fmt::Result {
         )?;
     }
 
+    // Charges are written above and to the right, as in "2−"
+    if atom.charge != 0 {
+        let sign = if atom.charge > 0 { '+' } else { '−' };
+        let magnitude = match atom.charge.unsigned_abs() {
+            1 => String::new(),
+            n => n.to_string(),
+        };
+        writeln!(
+            f,
+            "<text x='{x}' y='{y}' font-size='{CHARGE_FONT_SIZE}px' text-anchor='middle' dominant-baseline='middle'>{magnitude}{sign}</text>",
+            x = SCALE * atom.position.x + CHARGE_OFFSET,
+            y = SCALE * atom.position.y - CHARGE_OFFSET,
+        )?;
+    }
+
     Ok(())
 }
 
-fn write_bond(f: &mut fmt::Formatter, a: &Atom, b: &Atom, _bond: &Bond) -> fmt::Result {
+fn write_bond(f: &mut fmt::Formatter, a: &Atom, b: &Atom, bond: &Bond) -> fmt::Result {
     let mut a_position = SCALE * a.position;
     let mut b_position = SCALE * b.position;
     let direction = (b_position - a_position).normalize();
@@ -97,14 +115,19 @@ fn write_bond(f: &mut fmt::Formatter, a: &Atom, b: &Atom, _bond: &Bond) -> fmt::
         b_position -= direction * TEXT_EXCLUSION_RADIUS;
     }
 
-    writeln!(
-        f,
-        "<line x1='{x1}' y1='{y1}' x2='{x2}' y2='{y2}' stroke='black' />",
-        x1 = a_position.x,
-        y1 = a_position.y,
-        x2 = b_position.x,
-        y2 = b_position.y,
-    )?;
+    // Multiple bonds are drawn as parallel lines, centred on the bond
+    let normal = direction.perp();
+    for k in 0..bond.bond_order {
+        let offset = (k as f32 - 0.5 * (bond.bond_order - 1) as f32) * BOND_SPACING * normal;
+        writeln!(
+            f,
+            "<line x1='{x1}' y1='{y1}' x2='{x2}' y2='{y2}' stroke='black' />",
+            x1 = a_position.x + offset.x,
+            y1 = a_position.y + offset.y,
+            x2 = b_position.x + offset.x,
+            y2 = b_position.y + offset.y,
+        )?;
+    }
 
     Ok(())
 }
@@ -0,0 +1,84 @@
+use std::{fs, path::PathBuf};
+
+use blue_book::{
+    formula::Formula,
+    graph::Graph,
+    molfile::{parse_sdf, write_sdf, Molfile, SdfRecord, Version},
+    parser::parse,
+};
+use paste::paste;
+use structural_formula::molfile::ToMolfile;
+
+macro_rules! test_molfile {
+    ($name:ident) => {
+        paste! {
+            #[test]
+            fn [<test_ $name _molfile>]() {
+                test_molfile_impl(
+                    &stringify!($name),
+                    &blue_book::test::[<$name:upper>],
+                );
+            }
+        }
+    };
+    ($($name:ident,)*) => {
+        $(test_molfile!($name);)*
+    };
+}
+
+test_molfile!(
+    isopropanol,
+    isobutane,
+    //
+    dopamine,
+    salbutamol,
+    caffeine,
+    //
+    adenine,
+    thymine,
+    cytosine,
+    guanine,
+);
+
+fn test_molfile_impl(name: &str, iupac_name: &str) {
+    let graph = Graph::from(&*parse(iupac_name));
+    let molfile = graph.to_molfile(iupac_name);
+
+    let path = PathBuf::from(format!("examples/{name}.mol"));
+    let contents = molfile.write(Version::V2000);
+    if !path.exists() || fs::read_to_string(&path).unwrap() != contents {
+        fs::write(&path, &contents).unwrap();
+    }
+
+    let round_trip = contents.parse::<Molfile>().unwrap();
+    assert_eq!(round_trip.name, iupac_name);
+    assert_eq!(round_trip.graph.canonical_key(), graph.canonical_key());
+}
+
+#[test]
+fn test_sdf() {
+    let names = [
+        blue_book::test::ISOPROPANOL,
+        blue_book::test::DOPAMINE,
+        blue_book::test::CAFFEINE,
+    ];
+    let records = names.map(|name| {
+        let graph = Graph::from(&*parse(name));
+        let formula = Formula::from(&graph).to_string();
+        let mut record = SdfRecord::new(graph.to_molfile(name));
+        record.data.push(("NAME".to_owned(), name.to_owned()));
+        record.data.push(("FORMULA".to_owned(), formula));
+        record
+    });
+
+    let sdf = write_sdf(&records, Version::V3000);
+    let round_trip = parse_sdf(&sdf).unwrap();
+    assert_eq!(round_trip.len(), names.len());
+    for (record, name) in round_trip.iter().zip(names) {
+        assert_eq!(record.field("NAME"), Some(name));
+        assert_eq!(
+            record.field("FORMULA").unwrap(),
+            Formula::from(&record.molfile.graph).to_string(),
+        );
+    }
+}
@@ -25,14 +25,14 @@ test_svg!(
     isopropanol,
     isobutane,
     //
-    // dopamine,
-    // salbutamol,
-    // caffeine,
+    dopamine,
+    salbutamol,
+    caffeine,
     //
-    // adenine,
-    // thymine,
-    // cytosine,
-    // guanine,
+    adenine,
+    thymine,
+    cytosine,
+    guanine,
 );
 
 fn test_svg_impl(name: &str, iupac_name: &str) {